@@ -0,0 +1,131 @@
+use chipinho::audio::Beeper;
+use chipinho::constants::{NUM_KEYS, STATE_SIZE, VRAM_SIZE};
+use chipinho::emulator::Emulator;
+use chipinho::error::Error;
+
+use crate::options::Options;
+use crate::sys::*;
+
+pub const SAMPLE_RATE: u32 = 44_100;
+
+// joypad buttons for each key, directions on the 2/4/6/8 most games steer with and A on 5
+pub const JOYPAD_KEYS: [(u32, usize); NUM_KEYS] = [
+    (RETRO_DEVICE_ID_JOYPAD_UP, 0x2),
+    (RETRO_DEVICE_ID_JOYPAD_DOWN, 0x8),
+    (RETRO_DEVICE_ID_JOYPAD_LEFT, 0x4),
+    (RETRO_DEVICE_ID_JOYPAD_RIGHT, 0x6),
+    (RETRO_DEVICE_ID_JOYPAD_A, 0x5),
+    (RETRO_DEVICE_ID_JOYPAD_B, 0x0),
+    (RETRO_DEVICE_ID_JOYPAD_X, 0x1),
+    (RETRO_DEVICE_ID_JOYPAD_Y, 0x3),
+    (RETRO_DEVICE_ID_JOYPAD_L, 0x7),
+    (RETRO_DEVICE_ID_JOYPAD_R, 0x9),
+    (RETRO_DEVICE_ID_JOYPAD_L2, 0xA),
+    (RETRO_DEVICE_ID_JOYPAD_R2, 0xB),
+    (RETRO_DEVICE_ID_JOYPAD_L3, 0xC),
+    (RETRO_DEVICE_ID_JOYPAD_R3, 0xD),
+    (RETRO_DEVICE_ID_JOYPAD_SELECT, 0xE),
+    (RETRO_DEVICE_ID_JOYPAD_START, 0xF),
+];
+
+// keyboards use the same layout as the other frontends, libretro key codes are ASCII for these
+pub const KEYBOARD_KEYS: [(u32, usize); NUM_KEYS] = [
+    (b'1' as u32, 0x1),
+    (b'2' as u32, 0x2),
+    (b'3' as u32, 0x3),
+    (b'4' as u32, 0xC),
+    (b'q' as u32, 0x4),
+    (b'w' as u32, 0x5),
+    (b'e' as u32, 0x6),
+    (b'r' as u32, 0xD),
+    (b'a' as u32, 0x7),
+    (b's' as u32, 0x8),
+    (b'd' as u32, 0x9),
+    (b'f' as u32, 0xE),
+    (b'z' as u32, 0xA),
+    (b'x' as u32, 0x0),
+    (b'c' as u32, 0xB),
+    (b'v' as u32, 0xF),
+];
+
+/// A loaded game.
+pub struct Core {
+    pub emulator: Emulator,
+    pub options: Options,
+    program: Vec<u8>,
+    beeper: Beeper,
+    // the fault that stopped the program, until a reset or a loaded state
+    fault: Option<Error>,
+    pub frame: [u32; VRAM_SIZE],
+    // interleaved stereo
+    pub audio: Vec<i16>,
+    mono: Vec<i16>,
+}
+
+impl Core {
+    pub fn new(program: &[u8], options: Options) -> Result<Self, Error> {
+        let beeper = Beeper::new(SAMPLE_RATE);
+        let mut core = Core {
+            emulator: Emulator::new(),
+            options,
+            program: program.to_vec(),
+            beeper,
+            fault: None,
+            frame: [0; VRAM_SIZE],
+            audio: vec![0; beeper.samples_per_frame() * 2],
+            mono: vec![0; beeper.samples_per_frame()],
+        };
+        core.reset()?;
+        Ok(core)
+    }
+
+    pub fn reset(&mut self) -> Result<(), Error> {
+        self.emulator = Emulator::new();
+        self.options.apply(&mut self.emulator);
+        self.fault = None;
+        self.emulator.load_program(&self.program)
+    }
+
+    pub fn set_options(&mut self, options: Options) {
+        self.options = options;
+        self.options.apply(&mut self.emulator);
+    }
+
+    /// Run one frame with `keypad`, then fill `frame` and `audio`.
+    /// Returns the fault when the program stops on this frame.
+    pub fn run(&mut self, keypad: &[u8; NUM_KEYS]) -> Option<Error> {
+        let mut stopped = None;
+        if self.fault.is_none() {
+            if let Err(err) = self.emulator.run_frame(keypad, self.options.cycles_per_frame) {
+                // the frontend keeps showing the last frame, silent
+                self.fault = Some(err);
+                stopped = Some(err);
+            }
+        }
+        let palette = self.options.palette;
+        self.frame
+            .iter_mut()
+            .zip(self.emulator.get_vram())
+            .for_each(|(color, pixel)| *color = if *pixel != 0 { palette.on } else { palette.off });
+
+        let beeping = self.fault.is_none() && self.emulator.should_beep();
+        self.beeper.fill(beeping, &mut self.mono);
+        self.audio
+            .chunks_exact_mut(2)
+            .zip(&self.mono)
+            .for_each(|(frame, sample)| frame.fill(*sample));
+        stopped
+    }
+
+    pub fn serialize(&self, out: &mut [u8]) -> bool {
+        out.len() >= STATE_SIZE && self.emulator.save_state(out).is_ok()
+    }
+
+    pub fn unserialize(&mut self, state: &[u8]) -> bool {
+        let loaded = self.emulator.load_state(state.get(..STATE_SIZE).unwrap_or(state)).is_ok();
+        if loaded {
+            self.fault = None;
+        }
+        loaded
+    }
+}
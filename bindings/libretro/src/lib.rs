@@ -0,0 +1,359 @@
+//! libretro core, build it with `cargo build --release -p chipinho-libretro` and load
+//! `libchipinho_libretro.so` (renamed to `chipinho_libretro.so`) in RetroArch or any other frontend.
+use std::ffi::{c_char, c_uint, c_void, CStr, CString};
+use std::slice;
+use std::sync::Mutex;
+
+use chipinho::constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE, NUM_KEYS, STATE_SIZE, TIMER_FREQUENCY};
+use chipinho::error::Error;
+
+mod core;
+mod options;
+mod sys;
+
+use crate::core::{Core, JOYPAD_KEYS, KEYBOARD_KEYS, SAMPLE_RATE};
+use crate::options::{Options, VARIABLES};
+use crate::sys::*;
+
+#[derive(Clone, Copy)]
+struct Callbacks {
+    environment: Option<EnvironmentFn>,
+    video_refresh: Option<VideoRefreshFn>,
+    audio_sample_batch: Option<AudioSampleBatchFn>,
+    input_poll: Option<InputPollFn>,
+    input_state: Option<InputStateFn>,
+    log: Option<LogPrintfFn>,
+}
+
+// libretro cores are singletons, the frontend calls in from one thread at a time
+static CALLBACKS: Mutex<Callbacks> = Mutex::new(Callbacks {
+    environment: None,
+    video_refresh: None,
+    audio_sample_batch: None,
+    input_poll: None,
+    input_state: None,
+    log: None,
+});
+static CORE: Mutex<Option<Box<Core>>> = Mutex::new(None);
+
+fn callbacks() -> Callbacks {
+    *CALLBACKS.lock().unwrap()
+}
+
+fn with_core<T>(default: T, f: impl FnOnce(&mut Core) -> T) -> T {
+    CORE.lock().unwrap().as_deref_mut().map_or(default, f)
+}
+
+// the options as currently set in the frontend
+fn read_options(environment: EnvironmentFn) -> Options {
+    let mut options = Options::default();
+    for (key, _) in VARIABLES {
+        let mut variable = Variable {
+            key: key.as_ptr(),
+            value: std::ptr::null(),
+        };
+        let found = unsafe { environment(RETRO_ENVIRONMENT_GET_VARIABLE, &mut variable as *mut _ as *mut c_void) };
+        if found && !variable.value.is_null() {
+            let value = unsafe { CStr::from_ptr(variable.value) };
+            options.set(key, &value.to_string_lossy());
+        }
+    }
+    options
+}
+
+fn options_changed(environment: EnvironmentFn) -> bool {
+    let mut updated = false;
+    let data = &mut updated as *mut _ as *mut c_void;
+    let supported = unsafe { environment(RETRO_ENVIRONMENT_GET_VARIABLE_UPDATE, data) };
+    supported && updated
+}
+
+// to the frontend's log, stderr may go nowhere and frontends without a log interface don't want one
+fn log_error(log: Option<LogPrintfFn>, err: &Error) {
+    if let (Some(log), Ok(message)) = (log, CString::new(err.to_string())) {
+        unsafe { log(RETRO_LOG_ERROR, c"chipinho: %s\n".as_ptr(), message.as_ptr()) };
+    }
+}
+
+fn read_keypad(input_state: InputStateFn) -> [u8; NUM_KEYS] {
+    let mut keypad = [0; NUM_KEYS];
+    let pressed = |device, id| unsafe { input_state(0, device, 0, id) } != 0;
+    for (id, key) in JOYPAD_KEYS {
+        keypad[key] |= pressed(RETRO_DEVICE_JOYPAD, id) as u8;
+    }
+    for (id, key) in KEYBOARD_KEYS {
+        keypad[key] |= pressed(RETRO_DEVICE_KEYBOARD, id) as u8;
+    }
+    keypad
+}
+
+#[no_mangle]
+pub extern "C" fn retro_api_version() -> c_uint {
+    RETRO_API_VERSION
+}
+
+/// # Safety
+/// `environment` must be a valid libretro environment callback.
+#[no_mangle]
+pub unsafe extern "C" fn retro_set_environment(environment: EnvironmentFn) {
+    CALLBACKS.lock().unwrap().environment = Some(environment);
+    let mut variables: Vec<Variable> = VARIABLES
+        .iter()
+        .map(|(key, value)| Variable {
+            key: key.as_ptr(),
+            value: value.as_ptr(),
+        })
+        .collect();
+    variables.push(Variable {
+        key: std::ptr::null(),
+        value: std::ptr::null(),
+    });
+    environment(RETRO_ENVIRONMENT_SET_VARIABLES, variables.as_mut_ptr() as *mut c_void);
+
+    let mut log = LogCallback { log: None };
+    let found = environment(RETRO_ENVIRONMENT_GET_LOG_INTERFACE, &mut log as *mut _ as *mut c_void);
+    CALLBACKS.lock().unwrap().log = if found { log.log } else { None };
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_video_refresh(video_refresh: VideoRefreshFn) {
+    CALLBACKS.lock().unwrap().video_refresh = Some(video_refresh);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample(_audio_sample: AudioSampleFn) {}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample_batch(audio_sample_batch: AudioSampleBatchFn) {
+    CALLBACKS.lock().unwrap().audio_sample_batch = Some(audio_sample_batch);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_poll(input_poll: InputPollFn) {
+    CALLBACKS.lock().unwrap().input_poll = Some(input_poll);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_state(input_state: InputStateFn) {
+    CALLBACKS.lock().unwrap().input_state = Some(input_state);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_controller_port_device(_port: c_uint, _device: c_uint) {}
+
+#[no_mangle]
+pub extern "C" fn retro_init() {}
+
+#[no_mangle]
+pub extern "C" fn retro_deinit() {
+    *CORE.lock().unwrap() = None;
+}
+
+/// # Safety
+/// `info` must point to a `retro_system_info`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut SystemInfo) {
+    *info = SystemInfo {
+        library_name: c"chipinho".as_ptr(),
+        library_version: c"0.1.0".as_ptr(),
+        valid_extensions: c"ch8|c8".as_ptr(),
+        need_fullpath: false,
+        block_extract: false,
+    };
+}
+
+/// # Safety
+/// `info` must point to a `retro_system_av_info`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut SystemAvInfo) {
+    *info = SystemAvInfo {
+        geometry: GameGeometry {
+            base_width: DISPLAY_WIDTH as c_uint,
+            base_height: DISPLAY_HEIGHT as c_uint,
+            max_width: DISPLAY_WIDTH as c_uint,
+            max_height: DISPLAY_HEIGHT as c_uint,
+            aspect_ratio: DISPLAY_WIDTH as f32 / DISPLAY_HEIGHT as f32,
+        },
+        timing: SystemTiming {
+            fps: TIMER_FREQUENCY as f64,
+            sample_rate: SAMPLE_RATE as f64,
+        },
+    };
+}
+
+/// # Safety
+/// `game` must be null or point to a `retro_game_info` with `size` bytes of data.
+#[no_mangle]
+pub unsafe extern "C" fn retro_load_game(game: *const GameInfo) -> bool {
+    let callbacks = callbacks();
+    let Some(environment) = callbacks.environment else {
+        return false;
+    };
+    if game.is_null() || (*game).data.is_null() {
+        return false;
+    }
+    let mut format = RETRO_PIXEL_FORMAT_XRGB8888;
+    if !environment(RETRO_ENVIRONMENT_SET_PIXEL_FORMAT, &mut format as *mut _ as *mut c_void) {
+        return false;
+    }
+    let mut descriptors: Vec<InputDescriptor> = JOYPAD_KEYS
+        .iter()
+        .zip(KEY_NAMES)
+        .map(|((id, _), name)| InputDescriptor {
+            port: 0,
+            device: RETRO_DEVICE_JOYPAD,
+            index: 0,
+            id: *id,
+            description: name.as_ptr(),
+        })
+        .collect();
+    descriptors.push(InputDescriptor {
+        port: 0,
+        device: 0,
+        index: 0,
+        id: 0,
+        description: std::ptr::null(),
+    });
+    environment(RETRO_ENVIRONMENT_SET_INPUT_DESCRIPTORS, descriptors.as_mut_ptr() as *mut c_void);
+
+    let program = slice::from_raw_parts((*game).data as *const u8, (*game).size);
+    match Core::new(program, read_options(environment)) {
+        Ok(core) => {
+            *CORE.lock().unwrap() = Some(Box::new(core));
+            true
+        }
+        Err(err) => {
+            log_error(callbacks.log, &err);
+            false
+        }
+    }
+}
+
+// in the same order as `JOYPAD_KEYS`
+const KEY_NAMES: [&CStr; NUM_KEYS] = [
+    c"2 (up)",
+    c"8 (down)",
+    c"4 (left)",
+    c"6 (right)",
+    c"5",
+    c"0",
+    c"1",
+    c"3",
+    c"7",
+    c"9",
+    c"A",
+    c"B",
+    c"C",
+    c"D",
+    c"E",
+    c"F",
+];
+
+#[no_mangle]
+pub extern "C" fn retro_load_game_special(_type: c_uint, _info: *const GameInfo, _num: usize) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn retro_unload_game() {
+    *CORE.lock().unwrap() = None;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_reset() {
+    with_core((), |core| {
+        // the program loaded once already, so it fits
+        let _ = core.reset();
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn retro_run() {
+    let callbacks = callbacks();
+    if let Some(input_poll) = callbacks.input_poll {
+        unsafe { input_poll() };
+    }
+    let keypad = callbacks.input_state.map_or([0; NUM_KEYS], read_keypad);
+    let environment = callbacks.environment.filter(|environment| options_changed(*environment));
+    let mut core = CORE.lock().unwrap();
+    let Some(core) = core.as_deref_mut() else {
+        return;
+    };
+    if let Some(environment) = environment {
+        core.set_options(read_options(environment));
+    }
+    if let Some(err) = core.run(&keypad) {
+        log_error(callbacks.log, &err);
+    }
+
+    if let Some(video_refresh) = callbacks.video_refresh {
+        let pitch = DISPLAY_WIDTH as usize * std::mem::size_of::<u32>();
+        unsafe {
+            video_refresh(
+                core.frame.as_ptr() as *const c_void,
+                DISPLAY_WIDTH as c_uint,
+                DISPLAY_HEIGHT as c_uint,
+                pitch,
+            )
+        };
+    }
+    if let Some(audio_sample_batch) = callbacks.audio_sample_batch {
+        unsafe { audio_sample_batch(core.audio.as_ptr(), core.audio.len() / 2) };
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_serialize_size() -> usize {
+    STATE_SIZE
+}
+
+/// # Safety
+/// `data` must point to `size` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    if data.is_null() {
+        return false;
+    }
+    let out = slice::from_raw_parts_mut(data as *mut u8, size);
+    with_core(false, |core| core.serialize(out))
+}
+
+/// # Safety
+/// `data` must point to `size` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    if data.is_null() {
+        return false;
+    }
+    let state = slice::from_raw_parts(data as *const u8, size);
+    with_core(false, |core| core.unserialize(state))
+}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_reset() {}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_set(_index: c_uint, _enabled: bool, _code: *const c_char) {}
+
+#[no_mangle]
+pub extern "C" fn retro_get_region() -> c_uint {
+    RETRO_REGION_NTSC
+}
+
+/// The 4 KiB of memory as system RAM, for cheats and achievements.
+#[no_mangle]
+pub extern "C" fn retro_get_memory_data(id: c_uint) -> *mut c_void {
+    if id != RETRO_MEMORY_SYSTEM_RAM {
+        return std::ptr::null_mut();
+    }
+    // the core is boxed, so the memory stays put until the game is unloaded
+    with_core(std::ptr::null_mut(), |core| core.emulator.get_memory_mut().as_mut_ptr() as *mut c_void)
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_size(id: c_uint) -> usize {
+    match id {
+        RETRO_MEMORY_SYSTEM_RAM => MEMORY_SIZE as usize,
+        _ => 0,
+    }
+}
@@ -0,0 +1,108 @@
+use std::ffi::CStr;
+
+use chipinho::constants::{
+    DEFAULT_CYCLES_PER_FRAME, DEFAULT_RANDOM_SEED, MAX_STACK_SIZE, SCHIP_STACK_SIZE, VIP_STACK_SIZE,
+};
+use chipinho::emulator::{Emulator, MemoryPolicy};
+use chipinho::random::RandomKind;
+
+// the first value is the default, frontends show the text before `;` as the name. The cycles default is
+// `DEFAULT_CYCLES_PER_FRAME`, a literal because `CStr` constants can't be formatted
+pub const VARIABLES: [(&CStr, &CStr); 4] = [
+    (
+        c"chipinho_platform",
+        c"Platform (stack depth and RND); modern|vip|schip",
+    ),
+    (
+        c"chipinho_memory_policy",
+        c"Memory past 4 KiB; strict|wrap|clamp",
+    ),
+    (
+        c"chipinho_cycles_per_frame",
+        c"Instructions per frame; 10|5|15|20|30|50|100|200|500|1000",
+    ),
+    (
+        c"chipinho_palette",
+        c"Palette; white|green|amber|gameboy",
+    ),
+];
+
+/// What to emulate: how deep calls nest and where random numbers come from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Platform {
+    /// 32 levels and PCG32
+    Modern,
+    /// the COSMAC VIP: 12 levels and its interpreter's RND routine
+    Vip,
+    /// SUPER-CHIP on the HP48: 16 levels
+    Schip,
+}
+
+impl Platform {
+    pub fn apply(&self, emulator: &mut Emulator) {
+        let (stack, random) = match self {
+            Platform::Modern => (MAX_STACK_SIZE, RandomKind::Pcg32),
+            Platform::Vip => (VIP_STACK_SIZE, RandomKind::Vip),
+            Platform::Schip => (SCHIP_STACK_SIZE, RandomKind::Pcg32),
+        };
+        emulator.set_stack_limit(stack as u16);
+        if emulator.get_random_kind() != Some(random) {
+            emulator.set_random(random, DEFAULT_RANDOM_SEED);
+        }
+    }
+}
+
+/// Lit and unlit pixel colors, as XRGB8888.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub on: u32,
+    pub off: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Options {
+    pub platform: Platform,
+    pub memory_policy: MemoryPolicy,
+    pub cycles_per_frame: u32,
+    pub palette: Palette,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            platform: Platform::Modern,
+            memory_policy: MemoryPolicy::Strict,
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            palette: Palette { on: 0xFFFFFF, off: 0x000000 },
+        }
+    }
+}
+
+impl Options {
+    /// Take the value the frontend has for `key`, values it doesn't know are ignored.
+    pub fn set(&mut self, key: &CStr, value: &str) {
+        match (key.to_bytes(), value) {
+            (b"chipinho_platform", "modern") => self.platform = Platform::Modern,
+            (b"chipinho_platform", "vip") => self.platform = Platform::Vip,
+            (b"chipinho_platform", "schip") => self.platform = Platform::Schip,
+            (b"chipinho_memory_policy", "strict") => self.memory_policy = MemoryPolicy::Strict,
+            (b"chipinho_memory_policy", "wrap") => self.memory_policy = MemoryPolicy::Wrap,
+            (b"chipinho_memory_policy", "clamp") => self.memory_policy = MemoryPolicy::Clamp,
+            (b"chipinho_cycles_per_frame", cycles) => {
+                if let Ok(cycles) = cycles.parse() {
+                    self.cycles_per_frame = cycles;
+                }
+            }
+            (b"chipinho_palette", "white") => self.palette = Palette { on: 0xFFFFFF, off: 0x000000 },
+            (b"chipinho_palette", "green") => self.palette = Palette { on: 0x33FF66, off: 0x0A1A0F },
+            (b"chipinho_palette", "amber") => self.palette = Palette { on: 0xFFB000, off: 0x1A1000 },
+            (b"chipinho_palette", "gameboy") => self.palette = Palette { on: 0x0F380F, off: 0x9BBC0F },
+            _ => {}
+        }
+    }
+
+    pub fn apply(&self, emulator: &mut Emulator) {
+        self.platform.apply(emulator);
+        emulator.set_memory_policy(self.memory_policy);
+    }
+}
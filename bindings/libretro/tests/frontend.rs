@@ -0,0 +1,341 @@
+//! A minimal libretro frontend that `dlopen`s the built core and drives it like RetroArch would.
+use std::collections::HashMap;
+use std::env;
+use std::ffi::{c_char, c_uint, c_void, CStr, CString};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use chipinho::constants::DEFAULT_CYCLES_PER_FRAME;
+use chipinho::testing::programs::BEEPER_PROGRAM;
+use libloading::{Library, Symbol};
+
+const RETRO_DEVICE_JOYPAD: c_uint = 1;
+const RETRO_DEVICE_KEYBOARD: c_uint = 3;
+const RETRO_DEVICE_ID_JOYPAD_A: c_uint = 8;
+const RETRO_ENVIRONMENT_SET_PIXEL_FORMAT: c_uint = 10;
+const RETRO_ENVIRONMENT_SET_INPUT_DESCRIPTORS: c_uint = 11;
+const RETRO_ENVIRONMENT_GET_VARIABLE: c_uint = 15;
+const RETRO_ENVIRONMENT_SET_VARIABLES: c_uint = 16;
+const RETRO_ENVIRONMENT_GET_VARIABLE_UPDATE: c_uint = 17;
+const RETRO_ENVIRONMENT_GET_LOG_INTERFACE: c_uint = 27;
+const RETRO_LOG_ERROR: c_uint = 3;
+const RETRO_PIXEL_FORMAT_XRGB8888: c_uint = 1;
+const RETRO_MEMORY_SYSTEM_RAM: c_uint = 2;
+
+#[repr(C)]
+struct Variable {
+    key: *const c_char,
+    value: *const c_char,
+}
+
+#[repr(C)]
+struct GameInfo {
+    path: *const c_char,
+    data: *const c_void,
+    size: usize,
+    meta: *const c_char,
+}
+
+// `retro_log_printf_t` is variadic, which stable Rust can't define. Where variadic arguments are passed like
+// fixed ones, the core's one `%s` argument arrives as the third parameter
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+type LogFn = unsafe extern "C" fn(c_uint, *const c_char, *const c_char);
+
+#[derive(Default)]
+struct Frontend {
+    // the declared options with their current values
+    variables: HashMap<String, CString>,
+    updated: bool,
+    pixel_format: Option<c_uint>,
+    has_input_descriptors: bool,
+    frame: Vec<u32>,
+    size: (c_uint, c_uint),
+    audio: Vec<i16>,
+    joypad_a: bool,
+    keyboard: Option<c_uint>,
+    log: Vec<(c_uint, String)>,
+}
+
+static FRONTEND: Mutex<Option<Frontend>> = Mutex::new(None);
+// the core is a singleton, so tests take turns
+static TURN: Mutex<()> = Mutex::new(());
+
+fn frontend() -> MutexGuard<'static, Option<Frontend>> {
+    FRONTEND.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+unsafe extern "C" fn environment(cmd: c_uint, data: *mut c_void) -> bool {
+    let mut guard = frontend();
+    let frontend = guard.as_mut().unwrap();
+    match cmd {
+        RETRO_ENVIRONMENT_SET_PIXEL_FORMAT => {
+            frontend.pixel_format = Some(*(data as *const c_uint));
+            true
+        }
+        RETRO_ENVIRONMENT_SET_INPUT_DESCRIPTORS => {
+            frontend.has_input_descriptors = true;
+            true
+        }
+        RETRO_ENVIRONMENT_SET_VARIABLES => {
+            let mut variable = data as *const Variable;
+            while !(*variable).key.is_null() {
+                let key = CStr::from_ptr((*variable).key).to_string_lossy().into_owned();
+                let value = CStr::from_ptr((*variable).value).to_string_lossy().into_owned();
+                // "Description; default|other|..."
+                let (_, values) = value.split_once("; ").unwrap();
+                let default = values.split('|').next().unwrap();
+                frontend.variables.insert(key, CString::new(default).unwrap());
+                variable = variable.add(1);
+            }
+            true
+        }
+        RETRO_ENVIRONMENT_GET_VARIABLE => {
+            let variable = &mut *(data as *mut Variable);
+            let key = CStr::from_ptr(variable.key).to_string_lossy();
+            match frontend.variables.get(key.as_ref()) {
+                Some(value) => {
+                    variable.value = value.as_ptr();
+                    true
+                }
+                None => false,
+            }
+        }
+        RETRO_ENVIRONMENT_GET_VARIABLE_UPDATE => {
+            *(data as *mut bool) = std::mem::take(&mut frontend.updated);
+            true
+        }
+        #[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+        RETRO_ENVIRONMENT_GET_LOG_INTERFACE => {
+            *(data as *mut Option<LogFn>) = Some(log);
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+unsafe extern "C" fn log(level: c_uint, fmt: *const c_char, message: *const c_char) {
+    let fmt = CStr::from_ptr(fmt).to_string_lossy();
+    let message = CStr::from_ptr(message).to_string_lossy();
+    let line = fmt.replacen("%s", &message, 1);
+    frontend().as_mut().unwrap().log.push((level, line));
+}
+
+unsafe extern "C" fn video_refresh(data: *const c_void, width: c_uint, height: c_uint, pitch: usize) {
+    let mut guard = frontend();
+    let frontend = guard.as_mut().unwrap();
+    assert_eq!(pitch, width as usize * 4);
+    frontend.frame = std::slice::from_raw_parts(data as *const u32, (width * height) as usize).to_vec();
+    frontend.size = (width, height);
+}
+
+unsafe extern "C" fn audio_sample(_left: i16, _right: i16) {}
+
+unsafe extern "C" fn audio_sample_batch(data: *const i16, frames: usize) -> usize {
+    let mut guard = frontend();
+    guard.as_mut().unwrap().audio = std::slice::from_raw_parts(data, frames * 2).to_vec();
+    frames
+}
+
+unsafe extern "C" fn input_poll() {}
+
+unsafe extern "C" fn input_state(port: c_uint, device: c_uint, _index: c_uint, id: c_uint) -> i16 {
+    let guard = frontend();
+    let frontend = guard.as_ref().unwrap();
+    let pressed = match device {
+        RETRO_DEVICE_JOYPAD => port == 0 && id == RETRO_DEVICE_ID_JOYPAD_A && frontend.joypad_a,
+        RETRO_DEVICE_KEYBOARD => frontend.keyboard == Some(id),
+        _ => false,
+    };
+    pressed as i16
+}
+
+// the test binary is in target/<profile>/deps, the cdylib there or one level up
+fn library_path() -> PathBuf {
+    let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    ["libchipinho_libretro.so", "libchipinho_libretro.dylib", "chipinho_libretro.dll"]
+        .iter()
+        .flat_map(|name| [deps.join(name), deps.parent().unwrap().join(name)])
+        .find(|path| path.exists())
+        .expect("the chipinho_libretro cdylib should be built before the tests")
+}
+
+fn core() -> &'static Library {
+    static CORE: OnceLock<Library> = OnceLock::new();
+    CORE.get_or_init(|| unsafe { Library::new(library_path()).unwrap() })
+}
+
+fn symbol<T>(name: &str) -> Symbol<'static, T> {
+    unsafe { core().get(name.as_bytes()).unwrap() }
+}
+
+fn call(name: &str) {
+    unsafe { symbol::<unsafe extern "C" fn()>(name)() }
+}
+
+// a fresh frontend with `BEEPER_PROGRAM` loaded, like picking a game in a menu
+fn start() -> MutexGuard<'static, ()> {
+    let turn = TURN.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *frontend() = Some(Frontend::default());
+    unsafe {
+        assert_eq!(symbol::<unsafe extern "C" fn() -> c_uint>("retro_api_version")(), 1);
+        symbol::<unsafe extern "C" fn(unsafe extern "C" fn(c_uint, *mut c_void) -> bool)>("retro_set_environment")(
+            environment,
+        );
+        symbol::<unsafe extern "C" fn(unsafe extern "C" fn(*const c_void, c_uint, c_uint, usize))>(
+            "retro_set_video_refresh",
+        )(video_refresh);
+        symbol::<unsafe extern "C" fn(unsafe extern "C" fn(i16, i16))>("retro_set_audio_sample")(audio_sample);
+        symbol::<unsafe extern "C" fn(unsafe extern "C" fn(*const i16, usize) -> usize)>(
+            "retro_set_audio_sample_batch",
+        )(audio_sample_batch);
+        symbol::<unsafe extern "C" fn(unsafe extern "C" fn())>("retro_set_input_poll")(input_poll);
+        symbol::<unsafe extern "C" fn(unsafe extern "C" fn(c_uint, c_uint, c_uint, c_uint) -> i16)>(
+            "retro_set_input_state",
+        )(input_state);
+        call("retro_init");
+        let game = GameInfo {
+            path: std::ptr::null(),
+            data: BEEPER_PROGRAM.as_ptr() as *const c_void,
+            size: BEEPER_PROGRAM.len(),
+            meta: std::ptr::null(),
+        };
+        assert!(symbol::<unsafe extern "C" fn(*const GameInfo) -> bool>("retro_load_game")(&game));
+    }
+    turn
+}
+
+fn stop() {
+    call("retro_unload_game");
+    call("retro_deinit");
+}
+
+fn pixel(x: usize, y: usize) -> u32 {
+    let guard = frontend();
+    let frontend = guard.as_ref().unwrap();
+    frontend.frame[y * frontend.size.0 as usize + x]
+}
+
+fn audio_is_silent() -> bool {
+    frontend().as_ref().unwrap().audio.iter().all(|sample| *sample == 0)
+}
+
+#[test]
+fn renders_frames_and_plays_the_beeper() {
+    let _turn = start();
+    {
+        let guard = frontend();
+        let frontend = guard.as_ref().unwrap();
+        assert_eq!(frontend.pixel_format, Some(RETRO_PIXEL_FORMAT_XRGB8888));
+        assert!(frontend.has_input_descriptors);
+        assert_eq!(frontend.variables.len(), 4);
+        assert_eq!(
+            frontend.variables["chipinho_cycles_per_frame"].to_str().unwrap(),
+            DEFAULT_CYCLES_PER_FRAME.to_string()
+        );
+    }
+    call("retro_run");
+    assert_eq!(frontend().as_ref().unwrap().size, (64, 32));
+    assert_eq!(pixel(8, 8), 0xFFFFFF);
+    assert_eq!(pixel(12, 8), 0x000000);
+    assert_eq!(frontend().as_ref().unwrap().audio.len(), 735 * 2);
+    assert!(audio_is_silent());
+
+    // A is key 5, which the program beeps on
+    frontend().as_mut().unwrap().joypad_a = true;
+    call("retro_run");
+    call("retro_run");
+    assert!(!audio_is_silent());
+    frontend().as_mut().unwrap().joypad_a = false;
+    for _ in 0..10 {
+        call("retro_run");
+    }
+    assert!(audio_is_silent());
+
+    // so is W on a keyboard
+    frontend().as_mut().unwrap().keyboard = Some(b'w' as c_uint);
+    call("retro_run");
+    call("retro_run");
+    assert!(!audio_is_silent());
+    stop();
+}
+
+#[test]
+fn follows_core_options() {
+    let _turn = start();
+    {
+        let mut guard = frontend();
+        let frontend = guard.as_mut().unwrap();
+        frontend.variables.insert("chipinho_palette".into(), c"gameboy".into());
+        frontend.updated = true;
+    }
+    call("retro_run");
+    assert_eq!(pixel(8, 8), 0x0F380F);
+    assert_eq!(pixel(0, 0), 0x9BBC0F);
+    stop();
+}
+
+#[test]
+fn saves_and_restores_states() {
+    let _turn = start();
+    let size = unsafe { symbol::<unsafe extern "C" fn() -> usize>("retro_serialize_size")() };
+    let serialize = symbol::<unsafe extern "C" fn(*mut c_void, usize) -> bool>("retro_serialize");
+    let unserialize = symbol::<unsafe extern "C" fn(*const c_void, usize) -> bool>("retro_unserialize");
+
+    call("retro_run");
+    let mut saved = vec![0u8; size];
+    assert!(unsafe { serialize(saved.as_mut_ptr() as *mut c_void, size) });
+    frontend().as_mut().unwrap().joypad_a = true;
+    call("retro_run");
+    frontend().as_mut().unwrap().joypad_a = false;
+
+    let mut beeping = vec![0u8; size];
+    assert!(unsafe { serialize(beeping.as_mut_ptr() as *mut c_void, size) });
+    assert_ne!(beeping, saved);
+    assert!(unsafe { unserialize(saved.as_ptr() as *const c_void, size) });
+    let mut restored = vec![0u8; size];
+    assert!(unsafe { serialize(restored.as_mut_ptr() as *mut c_void, size) });
+    assert_eq!(restored, saved);
+    assert!(!unsafe { unserialize(saved.as_ptr() as *const c_void, size - 1) });
+    assert!(!unsafe { serialize(restored.as_mut_ptr() as *mut c_void, size - 1) });
+    stop();
+}
+
+#[test]
+fn exposes_memory_and_resets() {
+    let _turn = start();
+    let memory_data = symbol::<unsafe extern "C" fn(c_uint) -> *mut u8>("retro_get_memory_data");
+    let memory_size = symbol::<unsafe extern "C" fn(c_uint) -> usize>("retro_get_memory_size");
+    let data = unsafe { memory_data(RETRO_MEMORY_SYSTEM_RAM) };
+    let size = unsafe { memory_size(RETRO_MEMORY_SYSTEM_RAM) };
+    assert_eq!(size, 4096);
+    let memory = unsafe { std::slice::from_raw_parts_mut(data, size) };
+    assert_eq!(memory[0x200..0x210], BEEPER_PROGRAM);
+
+    call("retro_run");
+    assert_eq!(pixel(8, 8), 0xFFFFFF);
+    // a cheat turning the sprite off, which a reset undoes
+    memory[0x20E] = 0;
+    call("retro_reset");
+    call("retro_run");
+    assert_eq!(pixel(8, 8), 0xFFFFFF);
+    stop();
+}
+
+#[test]
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn logs_faults_to_the_frontend() {
+    let _turn = start();
+    let memory_data = symbol::<unsafe extern "C" fn(c_uint) -> *mut u8>("retro_get_memory_data");
+    let memory = unsafe { std::slice::from_raw_parts_mut(memory_data(RETRO_MEMORY_SYSTEM_RAM), 4096) };
+    // the loop jumps into an invalid instruction
+    memory[0x20C..0x210].copy_from_slice(&[0x12, 0x0E, 0xFF, 0xFF]);
+    call("retro_run");
+    call("retro_run");
+    let log = std::mem::take(&mut frontend().as_mut().unwrap().log);
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].0, RETRO_LOG_ERROR);
+    assert!(log[0].1.starts_with("chipinho: "), "{:?}", log);
+    assert!(log[0].1.ends_with('\n'));
+    stop();
+}
@@ -0,0 +1,227 @@
+use chipinho::constants::*;
+use chipinho::emulator::{self, MemoryPolicy};
+use chipinho::error::Error;
+use chipinho::random::RandomKind;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+create_exception!(chipinho, ChipinhoError, PyException, "Raised when the program faults or a ROM/state can't be loaded.");
+
+fn raise(error: Error) -> PyErr {
+    ChipinhoError::new_err(error.to_string())
+}
+
+// `addr..addr + len` when it fits in memory
+fn memory_range(addr: usize, len: usize) -> PyResult<std::ops::Range<usize>> {
+    match addr.checked_add(len) {
+        Some(end) if end <= MEMORY_SIZE as usize => Ok(addr..end),
+        _ => Err(PyIndexError::new_err(format!("0x{:X}+{} is past the end of memory", addr, len))),
+    }
+}
+
+/// A CHIP-8 machine along with the state of its keypad.
+#[pyclass(module = "chipinho")]
+struct Emulator {
+    emulator: emulator::Emulator,
+    keypad: [u8; NUM_KEYS],
+}
+
+#[pymethods]
+impl Emulator {
+    #[classattr]
+    const WIDTH: usize = DISPLAY_WIDTH as usize;
+    #[classattr]
+    const HEIGHT: usize = DISPLAY_HEIGHT as usize;
+    #[classattr]
+    const MEMORY_SIZE: usize = MEMORY_SIZE as usize;
+    #[classattr]
+    const STATE_SIZE: usize = STATE_SIZE;
+
+    #[new]
+    fn new() -> Self {
+        Emulator {
+            emulator: emulator::Emulator::new(),
+            keypad: [0; NUM_KEYS],
+        }
+    }
+
+    /// Copy the ROM to 0x200 and jump there.
+    fn load_rom(&mut self, rom: &[u8]) -> PyResult<()> {
+        self.emulator.load_program(rom).map_err(raise)
+    }
+
+    /// Execute one instruction, without touching the timers.
+    fn step(&mut self) -> PyResult<()> {
+        self.emulator.step(&self.keypad).map_err(raise)
+    }
+
+    /// Update the timers and execute one instruction.
+    fn tick(&mut self) -> PyResult<()> {
+        self.emulator.tick(&self.keypad).map_err(raise)
+    }
+
+    /// Run one 60Hz frame: the timers are updated once and `cycles` instructions are executed.
+    #[pyo3(signature = (cycles = DEFAULT_CYCLES_PER_FRAME))]
+    fn run_frame(&mut self, cycles: u32) -> PyResult<()> {
+        self.emulator.run_frame(&self.keypad, cycles).map_err(raise)
+    }
+
+    /// Press or release one of the 16 keys, which stays that way until changed.
+    fn set_key(&mut self, key: usize, pressed: bool) -> PyResult<()> {
+        let slot = self
+            .keypad
+            .get_mut(key)
+            .ok_or_else(|| PyValueError::new_err(format!("invalid key 0x{:X}", key)))?;
+        *slot = pressed as u8;
+        Ok(())
+    }
+
+    fn release_keys(&mut self) {
+        self.keypad = [0; NUM_KEYS];
+    }
+
+    /// The pressed keys, one byte per key.
+    #[getter]
+    fn keys<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.keypad)
+    }
+
+    /// A copy of the screen, `WIDTH * HEIGHT` bytes (0 or 1) row by row. It supports the buffer protocol,
+    /// so `numpy.frombuffer(emulator.vram, numpy.uint8).reshape(Emulator.HEIGHT, Emulator.WIDTH)` works.
+    #[getter]
+    fn vram<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, self.emulator.get_vram())
+    }
+
+    fn read_memory<'py>(&self, py: Python<'py>, addr: usize, length: usize) -> PyResult<Bound<'py, PyBytes>> {
+        let range = memory_range(addr, length)?;
+        Ok(PyBytes::new(py, &self.emulator.get_memory()[range]))
+    }
+
+    fn write_memory(&mut self, addr: usize, data: &[u8]) -> PyResult<()> {
+        let range = memory_range(addr, data.len())?;
+        self.emulator.get_memory_mut()[range].copy_from_slice(data);
+        Ok(())
+    }
+
+    /// V0 to VF.
+    #[getter]
+    fn registers(&self) -> Vec<u8> {
+        self.emulator.get_registers().to_vec()
+    }
+
+    fn set_register(&mut self, register: usize, value: u8) -> PyResult<()> {
+        let slot = self
+            .emulator
+            .get_registers_mut()
+            .get_mut(register)
+            .ok_or_else(|| PyIndexError::new_err(format!("invalid register V{:X}", register)))?;
+        *slot = value;
+        Ok(())
+    }
+
+    #[getter]
+    fn pc(&self) -> u16 {
+        self.emulator.program_counter
+    }
+
+    #[setter]
+    fn set_pc(&mut self, pc: u16) {
+        self.emulator.program_counter = pc;
+    }
+
+    #[getter]
+    fn index(&self) -> u16 {
+        self.emulator.index
+    }
+
+    #[setter]
+    fn set_index(&mut self, index: u16) {
+        self.emulator.index = index;
+    }
+
+    #[getter]
+    fn delay_timer(&self) -> u8 {
+        self.emulator.delay_timer
+    }
+
+    #[setter]
+    fn set_delay_timer(&mut self, value: u8) {
+        self.emulator.delay_timer = value;
+    }
+
+    #[getter]
+    fn sound_timer(&self) -> u8 {
+        self.emulator.sound_timer
+    }
+
+    #[setter]
+    fn set_sound_timer(&mut self, value: u8) {
+        self.emulator.sound_timer = value;
+    }
+
+    /// The return addresses, innermost call last.
+    #[getter]
+    fn stack(&self) -> Vec<u16> {
+        self.emulator.get_stack().to_vec()
+    }
+
+    fn should_beep(&self) -> bool {
+        self.emulator.should_beep()
+    }
+
+    /// Whether the program is stuck jumping to itself, how most programs signal they are done.
+    fn is_halted(&self) -> bool {
+        self.emulator.is_halted()
+    }
+
+    /// Use `"strict"`, `"wrap"` or `"clamp"` for accesses past the end of memory.
+    fn set_memory_policy(&mut self, policy: &str) -> PyResult<()> {
+        let policy = match policy {
+            "strict" => MemoryPolicy::Strict,
+            "wrap" => MemoryPolicy::Wrap,
+            "clamp" => MemoryPolicy::Clamp,
+            _ => return Err(PyValueError::new_err(format!("unknown memory policy {:?}", policy))),
+        };
+        self.emulator.set_memory_policy(policy);
+        Ok(())
+    }
+
+    /// Switch RND to `"pcg32"`, `"legacy"` or `"vip"`, starting from `seed`.
+    fn set_random(&mut self, kind: &str, seed: u64) -> PyResult<()> {
+        let kind = match kind {
+            "pcg32" => RandomKind::Pcg32,
+            "legacy" => RandomKind::Legacy,
+            "vip" => RandomKind::Vip,
+            _ => return Err(PyValueError::new_err(format!("unknown generator {:?}", kind))),
+        };
+        self.emulator.set_random(kind, seed);
+        Ok(())
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.emulator.set_seed(seed);
+    }
+
+    /// The whole machine as `STATE_SIZE` bytes, the keypad is not included.
+    fn save_state<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let mut state = [0; STATE_SIZE];
+        self.emulator.save_state(&mut state).map_err(raise)?;
+        Ok(PyBytes::new(py, &state))
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> PyResult<()> {
+        self.emulator.load_state(state).map_err(raise)
+    }
+}
+
+/// Python bindings for the chipinho CHIP-8 emulator.
+#[pymodule]
+#[pyo3(name = "chipinho")]
+fn chipinho_python(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Emulator>()?;
+    module.add("ChipinhoError", module.py().get_type::<ChipinhoError>())?;
+    Ok(())
+}
@@ -0,0 +1,33 @@
+//! Runs the unittest suite next to this file against the freshly built module, needs `python3`
+//! (or `$PYTHON`).
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn python_suite_passes() {
+    // the test binary is in target/<profile>/deps, the cdylib one level up
+    let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let library = ["libchipinho_python.so", "libchipinho_python.dylib", "chipinho_python.dll"]
+        .iter()
+        .flat_map(|name| [deps.join(name), deps.parent().unwrap().join(name)])
+        .find(|path| path.exists())
+        .expect("the chipinho_python cdylib should be built before the tests");
+
+    // python only imports the module under its own name
+    let module_dir = deps.join("chipinho-python-module");
+    fs::create_dir_all(&module_dir).unwrap();
+    let module = if cfg!(windows) { "chipinho.pyd" } else { "chipinho.so" };
+    fs::copy(&library, module_dir.join(module)).unwrap();
+
+    let tests = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let python = env::var("PYTHON").unwrap_or_else(|_| "python3".to_string());
+    let output = Command::new(python)
+        .args(["-m", "unittest", "discover", "-s"])
+        .arg(&tests)
+        .env("PYTHONPATH", &module_dir)
+        .output()
+        .expect("python3 is needed for this test");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
@@ -0,0 +1,87 @@
+import unittest
+
+import chipinho
+from chipinho import ChipinhoError, Emulator
+
+# 0x200: LD V0, 0x05
+# 0x202: LD F, V0
+# 0x204: LD V1, 0x08
+# 0x206: LD I, 0x20E
+# 0x208: DRW V1, V1, 1
+# 0x20A: SKP V1
+# 0x20C: JP 0x20A
+# 0x20E: JP 0x20E (also the sprite, 0x12 = 0b00010010)
+PROGRAM = bytes([0x60, 0x05, 0xF0, 0x29, 0x61, 0x08, 0xA2, 0x0E, 0xD1, 0x11, 0xE1, 0x9E, 0x12, 0x0A, 0x12, 0x0E])
+
+
+class EmulatorTest(unittest.TestCase):
+    def setUp(self):
+        self.emulator = Emulator()
+        self.emulator.load_rom(PROGRAM)
+
+    def test_draws_into_vram(self):
+        self.emulator.run_frame(5)
+        vram = self.emulator.vram
+        self.assertEqual(len(vram), Emulator.WIDTH * Emulator.HEIGHT)
+        row = vram[8 * Emulator.WIDTH:9 * Emulator.WIDTH]
+        self.assertEqual(row[8:16], bytes([0, 0, 0, 1, 0, 0, 1, 0]))
+        self.assertEqual(memoryview(vram).nbytes, 2048)
+
+    def test_waits_for_keys(self):
+        self.emulator.run_frame(20)
+        self.assertEqual(self.emulator.pc, 0x20C)
+        self.emulator.set_key(8, True)
+        self.assertEqual(self.emulator.keys[8], 1)
+        self.emulator.run_frame(2)
+        self.assertTrue(self.emulator.is_halted())
+        self.emulator.release_keys()
+        self.assertEqual(self.emulator.keys, bytes(16))
+        with self.assertRaises(ValueError):
+            self.emulator.set_key(16, True)
+
+    def test_reads_and_writes_the_machine(self):
+        self.emulator.step()
+        self.assertEqual(self.emulator.registers[0], 5)
+        self.emulator.set_register(0xF, 0xAB)
+        self.assertEqual(self.emulator.registers[0xF], 0xAB)
+        self.assertEqual(self.emulator.read_memory(0x200, 2), b"\x60\x05")
+        self.emulator.write_memory(0x300, b"\x01\x02")
+        self.assertEqual(self.emulator.read_memory(0x300, 2), b"\x01\x02")
+        with self.assertRaises(IndexError):
+            self.emulator.read_memory(0xFFF, 2)
+        self.emulator.pc = 0x206
+        self.emulator.step()
+        self.assertEqual(self.emulator.index, 0x20E)
+        self.emulator.sound_timer = 2
+        self.assertTrue(self.emulator.should_beep())
+        self.assertEqual(self.emulator.stack, [])
+
+    def test_saves_and_loads_states(self):
+        self.emulator.set_random("legacy", 7)
+        self.emulator.run_frame(3)
+        state = self.emulator.save_state()
+        self.assertEqual(len(state), Emulator.STATE_SIZE)
+        self.emulator.run_frame(10)
+        self.emulator.load_state(state)
+        self.assertEqual(self.emulator.pc, 0x206)
+
+        other = Emulator()
+        other.load_state(state)
+        self.assertEqual(other.save_state(), state)
+        with self.assertRaises(ChipinhoError):
+            other.load_state(state[:-1])
+
+    def test_raises_faults(self):
+        self.emulator.load_rom(b"\xF0\xFF")
+        with self.assertRaises(ChipinhoError) as raised:
+            self.emulator.step()
+        self.assertEqual(str(raised.exception), "invalid instruction F0FF at 0x200")
+        with self.assertRaises(ChipinhoError):
+            self.emulator.load_rom(bytes(4096))
+        with self.assertRaises(ValueError):
+            self.emulator.set_memory_policy("bounce")
+        self.assertTrue(issubclass(chipinho.ChipinhoError, Exception))
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,210 @@
+/* chipinho C interface, generated by cbindgen from src/ffi.rs (`make header`), do not edit */
+
+#ifndef CHIPINHO_H
+#define CHIPINHO_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define CHIPINHO_OK 0
+
+#define CHIPINHO_ERROR_KIND_MASK 4294901760
+
+#define CHIPINHO_ERROR_PAYLOAD_MASK 65535
+
+// Payload: the opcode.
+#define CHIPINHO_ERROR_INVALID_INSTRUCTION 268500992
+
+// Payload: the address.
+#define CHIPINHO_ERROR_OUT_OF_BOUNDS_MEMORY_ACCESS 268566528
+
+#define CHIPINHO_ERROR_NOT_ENOUGH_MEMORY_FOR_PROGRAM 268632064
+
+// Also returned for a null pointer with a non zero length.
+#define CHIPINHO_ERROR_INVALID_BUFFER_SIZE 268697600
+
+// Payload: the line number.
+#define CHIPINHO_ERROR_INVALID_SYMBOL_FILE 268763136
+
+// Payload: the stack limit.
+#define CHIPINHO_ERROR_STACK_OVERFLOW 268828672
+
+#define CHIPINHO_ERROR_STACK_UNDERFLOW 268894208
+
+// Payload: the key.
+#define CHIPINHO_ERROR_INVALID_KEY 268959744
+
+#define CHIPINHO_ERROR_INVALID_STATE 269025280
+
+// A `ChipinhoMemoryPolicy` or `ChipinhoRandomKind` value out of range.
+#define CHIPINHO_ERROR_INVALID_ARGUMENT 269090816
+
+#define CHIPINHO_DISPLAY_WIDTH 64
+
+#define CHIPINHO_DISPLAY_HEIGHT 32
+
+#define CHIPINHO_VRAM_SIZE 2048
+
+#define CHIPINHO_MEMORY_SIZE 4096
+
+#define CHIPINHO_NUM_KEYS 16
+
+#define CHIPINHO_NUM_REGISTERS 16
+
+#define CHIPINHO_MAX_STACK_SIZE 32
+
+// What happens when PC, or I plus an offset, points past the end of memory.
+//
+// * `Strict` - the instruction fails with `OutOfBoundsMemoryAccess` and nothing is changed
+// * `Wrap` - addresses are masked to 12 bits like real interpreters do, so they wrap to 0
+// * `Clamp` - the part past the end is skipped: writes are dropped, sprite rows read as
+//   empty and registers keep their values. Fetching an instruction there still fails
+typedef enum ChipinhoMemoryPolicy {
+  ChipinhoMemoryPolicy_Strict,
+  ChipinhoMemoryPolicy_Wrap,
+  ChipinhoMemoryPolicy_Clamp,
+} ChipinhoMemoryPolicy;
+
+// The built-in generators, for picking one without the Rust types (from C and wasm).
+typedef enum ChipinhoRandomKind {
+  ChipinhoRandomKind_Pcg32,
+  ChipinhoRandomKind_Legacy,
+  ChipinhoRandomKind_Vip,
+} ChipinhoRandomKind;
+
+// An emulator, only handled through pointers from `chipinho_new`.
+typedef struct Chipinho Chipinho;
+
+// A copy of the machine state, filled by `chipinho_get_state`.
+typedef struct ChipinhoState {
+  uint16_t program_counter;
+  uint16_t index;
+  uint8_t delay_timer;
+  uint8_t sound_timer;
+  // whether `Fx0A` is waiting for a key
+  bool waiting_key;
+  uint16_t stack_size;
+  uint16_t stack_limit;
+  uint8_t registers[CHIPINHO_NUM_REGISTERS];
+  // only the first `stack_size` entries are meaningful
+  uint16_t stack[CHIPINHO_MAX_STACK_SIZE];
+} ChipinhoState;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create an emulator with the font loaded, free it with `chipinho_free`.
+struct Chipinho *chipinho_new(void);
+
+// # Safety
+// `chipinho` must come from `chipinho_new` and not be used afterwards. Null is ignored.
+void chipinho_free(struct Chipinho *chipinho);
+
+// Copy `len` bytes of program to 0x200 and point PC at it.
+//
+// # Safety
+// `chipinho` must be a live handle and `program` must point to `len` bytes.
+uint32_t chipinho_load_program(struct Chipinho *chipinho, const uint8_t *program, size_t len);
+
+// Update the timers and execute one instruction. `keypad` holds one byte per key, non zero when pressed.
+//
+// # Safety
+// `chipinho` must be a live handle and `keypad` must point to `len` bytes.
+uint32_t chipinho_tick(struct Chipinho *chipinho,
+                       const uint8_t *keypad,
+                       size_t len);
+
+// Execute one instruction without touching the timers.
+//
+// # Safety
+// `chipinho` must be a live handle and `keypad` must point to `len` bytes.
+uint32_t chipinho_step(struct Chipinho *chipinho, const uint8_t *keypad, size_t len);
+
+// Update the timers once and execute `cycles` instructions, stopping at the first error.
+//
+// # Safety
+// `chipinho` must be a live handle and `keypad` must point to `len` bytes.
+uint32_t chipinho_run_frame(struct Chipinho *chipinho,
+                            const uint8_t *keypad,
+                            size_t len,
+                            uint32_t cycles);
+
+// # Safety
+// `chipinho` must be a live handle.
+void chipinho_update_timers(struct Chipinho *chipinho);
+
+// The `CHIPINHO_VRAM_SIZE` bytes of the screen, row major with one byte (0 or 1) per pixel. The
+// pointer stays valid as long as the handle.
+//
+// # Safety
+// `chipinho` must be a live handle.
+const uint8_t *chipinho_vram(const struct Chipinho *chipinho);
+
+// The `CHIPINHO_MEMORY_SIZE` bytes of memory, writable. The pointer stays valid as long as the handle.
+//
+// # Safety
+// `chipinho` must be a live handle.
+uint8_t *chipinho_memory(struct Chipinho *chipinho);
+
+// Whether the sound timer is running, the buzzer should sound while it is.
+//
+// # Safety
+// `chipinho` must be a live handle.
+bool chipinho_should_beep(const struct Chipinho *chipinho);
+
+// # Safety
+// `chipinho` must be a live handle.
+uint8_t chipinho_sound_timer(const struct Chipinho *chipinho);
+
+// Copy the registers, timers and stack into `state`.
+//
+// # Safety
+// `chipinho` must be a live handle and `state` must point to a `ChipinhoState`.
+void chipinho_get_state(const struct Chipinho *chipinho, struct ChipinhoState *state);
+
+// Limit how deep calls can nest, capped to `CHIPINHO_MAX_STACK_SIZE`.
+//
+// # Safety
+// `chipinho` must be a live handle.
+void chipinho_set_stack_limit(struct Chipinho *chipinho, uint16_t limit);
+
+// Pick what accesses past 4 KiB do, `policy` is a `ChipinhoMemoryPolicy`. Any other value is
+// `CHIPINHO_ERROR_INVALID_ARGUMENT` and changes nothing.
+//
+// # Safety
+// `chipinho` must be a live handle.
+uint32_t chipinho_set_memory_policy(struct Chipinho *chipinho, uint32_t policy);
+
+// Switch `CxKK` to one of the built-in generators, starting from `seed`. `kind` is a `ChipinhoRandomKind`,
+// any other value is `CHIPINHO_ERROR_INVALID_ARGUMENT` and changes nothing.
+//
+// # Safety
+// `chipinho` must be a live handle.
+uint32_t chipinho_set_random(struct Chipinho *chipinho,
+                             uint32_t kind,
+                             uint64_t seed);
+
+// Restart the current generator from `seed`.
+//
+// # Safety
+// `chipinho` must be a live handle.
+void chipinho_set_seed(struct Chipinho *chipinho, uint64_t seed);
+
+// Write a NUL terminated description of `code` to `buffer`, truncated to `len - 1` characters. Returns the
+// length of the full description like `snprintf`, so a result of `len` or more means it was truncated.
+//
+// # Safety
+// `buffer` must point to `len` bytes, it can be null when `len` is 0.
+size_t chipinho_describe_error(uint32_t code,
+                               char *buffer,
+                               size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHIPINHO_H */
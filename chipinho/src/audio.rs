@@ -0,0 +1,42 @@
+use crate::constants::*;
+
+/// Square wave for the buzzer, as signed 16 bit mono samples, for frontends that
+/// push samples instead of pausing an audio device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Beeper {
+    pub sample_rate: u32,
+    pub frequency: u32,
+    pub volume: i16,
+    // position in the period as a fraction of 2^32, kept between calls so chunks join without clicks
+    phase: u32,
+}
+
+impl Beeper {
+    pub fn new(sample_rate: u32) -> Self {
+        Beeper {
+            sample_rate,
+            frequency: BEEPER_FREQUENCY,
+            volume: BEEPER_VOLUME,
+            phase: 0,
+        }
+    }
+
+    /// How many samples last one 60Hz frame.
+    pub fn samples_per_frame(&self) -> usize {
+        (self.sample_rate / TIMER_FREQUENCY) as usize
+    }
+
+    /// Fill `out` with the wave while `on` (usually `Emulator::should_beep`), and with silence otherwise.
+    pub fn fill(&mut self, on: bool, out: &mut [i16]) {
+        if !on || self.sample_rate == 0 {
+            self.phase = 0;
+            out.iter_mut().for_each(|sample| *sample = 0);
+            return;
+        }
+        let step = ((self.frequency as u64) << 32) / self.sample_rate as u64;
+        for sample in out.iter_mut() {
+            *sample = if self.phase < 1 << 31 { self.volume } else { -self.volume };
+            self.phase = self.phase.wrapping_add(step as u32);
+        }
+    }
+}
@@ -1,12 +1,44 @@
 pub const PROGRAM_BEGIN_ADDR : u16 = 0x200;
 pub const NUM_REGISTERS : usize = 16;
 pub const MAX_STACK_SIZE : usize = 32;
+pub const VIP_STACK_SIZE : usize = 12;
+pub const SCHIP_STACK_SIZE : usize = 16;
 pub const NUM_KEYS : usize = 16;
 pub const FONT_BEGIN_ADDR : u16 = 0x00;
 pub const MEMORY_SIZE : u16 = 4096;
+pub const ADDRESS_MASK : u16 = MEMORY_SIZE - 1;
 pub const DISPLAY_HEIGHT : u8 = 32;
 pub const DISPLAY_WIDTH : u8 = 64;
+pub const VRAM_SIZE : usize = DISPLAY_WIDTH as usize * DISPLAY_HEIGHT as usize;
 
 pub const RANDOM_MULTIPLIER : u8 = 42;
 pub const RANDOM_INCREMENT : u8 = 31;
 pub const RANDOM_MODULE : u8 = 253;
+pub const DEFAULT_RANDOM_SEED : u64 = 123;
+pub const PCG_MULTIPLIER : u64 = 6364136223846793005;
+pub const PCG_INCREMENT : u64 = 1442695040888963407;
+
+pub const TIMER_FREQUENCY : u32 = 60;
+pub const DEFAULT_CYCLES_PER_FRAME : u32 = 10;
+pub const SCHEDULER_MAX_CATCH_UP_FRAMES : u32 = 4;
+pub const SCHEDULER_JITTER_MICROS : u64 = 500;
+pub const BEEPER_FREQUENCY : u32 = 440;
+pub const BEEPER_VOLUME : i16 = i16::MAX / 4;
+
+pub const PHOSPHOR_MAX_BLEND_FRAMES : u8 = 8;
+pub const PHOSPHOR_DEFAULT_DECAY : u8 = 192;
+
+pub const RGBA_SIZE : usize = VRAM_SIZE * 4;
+pub const FRAMEBUFFER_ON : u32 = 0xFFFFFFFF;
+pub const FRAMEBUFFER_OFF : u32 = 0x000000FF;
+
+pub const STATE_MAGIC : [u8; 4] = *b"CH8S";
+pub const STATE_VERSION : u8 = 1;
+// the generator kind stored for a custom generator
+pub const STATE_CUSTOM_RANDOM : u8 = 0xFF;
+// magic, version, registers and timers, the waiting key, stack size and limit, memory policy and the generator
+pub const STATE_HEADER_SIZE : usize = 28;
+pub const STATE_SIZE : usize = STATE_HEADER_SIZE + NUM_REGISTERS + MAX_STACK_SIZE * 2 + MEMORY_SIZE as usize + VRAM_SIZE;
+
+pub const DEFAULT_HISTORY_SIZE : usize = 64;
+pub const CRASH_MEMORY_WINDOW : usize = 16;
@@ -0,0 +1,200 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+use crate::constants::{CRASH_MEMORY_WINDOW, NUM_REGISTERS};
+use crate::emulator::Emulator;
+use crate::error::Error;
+use crate::history::Entry;
+use crate::instruction::Instruction;
+use crate::symbols::Symbols;
+
+/// A slice of memory copied into a crash report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryWindow {
+    pub start: u16,
+    pub bytes: Vec<u8>,
+}
+
+impl MemoryWindow {
+    // `CRASH_MEMORY_WINDOW` bytes on each side of `addr`, clamped to memory
+    fn around(emulator: &Emulator, addr: u16) -> Self {
+        let memory = emulator.get_memory();
+        let start = (addr as usize).saturating_sub(CRASH_MEMORY_WINDOW).min(memory.len());
+        let end = (addr as usize + CRASH_MEMORY_WINDOW).min(memory.len());
+        MemoryWindow {
+            start: start as u16,
+            bytes: memory[start..end].to_vec(),
+        }
+    }
+}
+
+/// Everything needed to triage an emulator error after the fact: the error, the machine
+/// state at that point, memory around PC and I and the instructions that led there.
+///
+/// `write_text` is meant for people and `write_json` for tools.
+#[derive(Clone, Debug)]
+pub struct CrashReport {
+    pub error: Error,
+    pub program_counter: u16,
+    // missing when PC itself is out of memory
+    pub opcode: Option<u16>,
+    pub registers: [u8; NUM_REGISTERS],
+    pub index: u16,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub stack: Vec<u16>,
+    pub memory_at_pc: MemoryWindow,
+    pub memory_at_index: MemoryWindow,
+    // oldest first
+    pub history: Vec<Entry>,
+}
+
+fn write_memory(out: &mut impl Write, window: &MemoryWindow, marked: u16) -> fmt::Result {
+    for (row, bytes) in window.bytes.chunks(8).enumerate() {
+        let row_start = window.start as usize + row * 8;
+        write!(out, "  0x{:03X}:", row_start)?;
+        for (offset, byte) in bytes.iter().enumerate() {
+            let separator = if row_start + offset == marked as usize { '>' } else { ' ' };
+            write!(out, "{}{:02X}", separator, byte)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn write_json_memory(out: &mut impl Write, window: &MemoryWindow) -> fmt::Result {
+    write!(out, "{{\"start\":{},\"bytes\":[", window.start)?;
+    write_json_list(out, window.bytes.iter())?;
+    write!(out, "]}}")
+}
+
+fn write_json_list<T: fmt::Display>(out: &mut impl Write, values: impl Iterator<Item = T>) -> fmt::Result {
+    for (i, value) in values.enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        write!(out, "{}", value)?;
+    }
+    Ok(())
+}
+
+impl CrashReport {
+    pub fn new<'a>(emulator: &Emulator, error: Error, history: impl Iterator<Item = &'a Entry>) -> Self {
+        let mut registers = [0; NUM_REGISTERS];
+        registers.copy_from_slice(&emulator.get_registers()[..NUM_REGISTERS]);
+        CrashReport {
+            error,
+            program_counter: emulator.program_counter,
+            opcode: emulator.opcode_at(emulator.program_counter),
+            registers,
+            index: emulator.index,
+            delay_timer: emulator.delay_timer,
+            sound_timer: emulator.sound_timer,
+            stack: emulator.get_stack().to_vec(),
+            memory_at_pc: MemoryWindow::around(emulator, emulator.program_counter),
+            memory_at_index: MemoryWindow::around(emulator, emulator.index),
+            history: history.copied().collect(),
+        }
+    }
+
+    pub fn write_text(&self, out: &mut impl Write, symbols: &Symbols) -> fmt::Result {
+        writeln!(out, "crash: {}", symbols.describe_error(&self.error))?;
+        match self.opcode.map(|opcode| (opcode, Instruction::parse(opcode))) {
+            Some((opcode, Ok(instruction))) => {
+                writeln!(out, "opcode: {:04X} ({})", opcode, instruction.with_symbols(symbols))?
+            }
+            Some((opcode, Err(_))) => writeln!(out, "opcode: {:04X} (invalid)", opcode)?,
+            None => writeln!(out, "opcode: out of memory")?,
+        }
+
+        writeln!(out, "registers:")?;
+        for (row, values) in self.registers.chunks(4).enumerate() {
+            write!(out, " ")?;
+            for (column, value) in values.iter().enumerate() {
+                write!(out, " V{:X}=0x{:02X}", row * 4 + column, value)?;
+            }
+            writeln!(out)?;
+        }
+        writeln!(out, "  I={}", symbols.location(self.index))?;
+        writeln!(out, "  DT={} ST={}", self.delay_timer, self.sound_timer)?;
+
+        writeln!(out, "stack (most recent call first):")?;
+        for addr in self.stack.iter().rev() {
+            writeln!(out, "  {}", symbols.location(*addr))?;
+        }
+
+        writeln!(out, "memory around PC:")?;
+        write_memory(out, &self.memory_at_pc, self.program_counter)?;
+        writeln!(out, "memory around I:")?;
+        write_memory(out, &self.memory_at_index, self.index)?;
+
+        writeln!(out, "last {} instructions (oldest first):", self.history.len())?;
+        for entry in self.history.iter() {
+            let location = symbols.location(entry.pc).to_string();
+            let instruction = match Instruction::parse(entry.opcode) {
+                Ok(instruction) => instruction.with_symbols(symbols).to_string(),
+                Err(_) => String::from("???"),
+            };
+            let mut line = format!("  {:<24} {:04X}  {:<20}", location, entry.opcode, instruction);
+            for (register, value) in entry.register_changes() {
+                write!(line, " V{:X}=0x{:02X}", register, value)?;
+            }
+            if let Some(index) = entry.index_change() {
+                write!(line, " I=0x{:03X}", index)?;
+            }
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json(&self, out: &mut impl Write) -> fmt::Result {
+        write!(
+            out,
+            "{{\"error\":{{\"code\":{},\"description\":\"{}\"}}",
+            u32::from(self.error),
+            self.error
+        )?;
+        write!(out, ",\"program_counter\":{}", self.program_counter)?;
+        match self.opcode {
+            Some(opcode) => write!(out, ",\"opcode\":{}", opcode)?,
+            None => write!(out, ",\"opcode\":null")?,
+        }
+        write!(out, ",\"registers\":[")?;
+        write_json_list(out, self.registers.iter())?;
+        write!(out, "],\"index\":{}", self.index)?;
+        write!(out, ",\"delay_timer\":{},\"sound_timer\":{}", self.delay_timer, self.sound_timer)?;
+        write!(out, ",\"stack\":[")?;
+        write_json_list(out, self.stack.iter())?;
+        write!(out, "],\"memory_at_pc\":")?;
+        write_json_memory(out, &self.memory_at_pc)?;
+        write!(out, ",\"memory_at_index\":")?;
+        write_json_memory(out, &self.memory_at_index)?;
+        write!(out, ",\"history\":[")?;
+        for (i, entry) in self.history.iter().enumerate() {
+            if i > 0 {
+                out.write_char(',')?;
+            }
+            write!(out, "{{\"pc\":{},\"opcode\":{},\"changes\":{{", entry.pc, entry.opcode)?;
+            let registers = entry
+                .register_changes()
+                .map(|(register, value)| (format!("V{:X}", register), value as u16));
+            let changes = registers.chain(entry.index_change().map(|index| (String::from("I"), index)));
+            for (i, (name, value)) in changes.enumerate() {
+                if i > 0 {
+                    out.write_char(',')?;
+                }
+                write!(out, "\"{}\":{}", name, value)?;
+            }
+            write!(out, "}}}}")?;
+        }
+        writeln!(out, "]}}")
+    }
+}
+
+impl fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_text(f, &Symbols::new())
+    }
+}
@@ -1,49 +1,68 @@
-#[cfg(target_family = "wasm")]
+#[cfg(feature = "wasm")]
+use alloc::vec::Vec;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::wasm_bindgen;
 
 use crate::{
     constants::*,
-    error::Error,
+    error::{self, Error, ErrorKind},
     font::{FONT_SET, FONT_SIZE},
     instruction::Instruction,
+    random::{RandomGenerator, RandomKind, Rng},
 };
 
-#[derive(Clone, Copy)]
-#[cfg_attr(target_family = "wasm", wasm_bindgen)]
-#[cfg_attr(not(target_family = "wasm"), repr(C))]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct WaitingKey {
     register_index: usize,
     key_index: usize,
     has_been_pressed: bool,
 }
 
-#[derive(Clone, Copy)]
-#[cfg_attr(target_family = "wasm", wasm_bindgen)]
-#[cfg_attr(not(target_family = "wasm"), repr(C))]
+/// What happens when PC, or I plus an offset, points past the end of memory.
+///
+/// * `Strict` - the instruction fails with `OutOfBoundsMemoryAccess` and nothing is changed
+/// * `Wrap` - addresses are masked to 12 bits like real interpreters do, so they wrap to 0
+/// * `Clamp` - the part past the end is skipped: writes are dropped, sprite rows read as
+///   empty and registers keep their values. Fetching an instruction there still fails
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(C)]
+pub enum MemoryPolicy {
+    #[default]
+    Strict,
+    Wrap,
+    Clamp,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct Emulator {
     pub program_counter: u16,
     pub index: u16,
     pub delay_timer: u8,
     pub sound_timer: u8,
     pub waiting_key: Option<WaitingKey>,
-    pub last_random_u8: u8,
     pub stack_size: u16,
 
+    // how deep calls can nest, up to `MAX_STACK_SIZE`
+    stack_limit: u16,
+    memory_policy: MemoryPolicy,
+    rng: RandomGenerator,
     registers: [u8; NUM_REGISTERS],
     stack: [u16; MAX_STACK_SIZE],
     memory: [u8; MEMORY_SIZE as usize],
     vram: [u8; DISPLAY_WIDTH as usize * DISPLAY_HEIGHT as usize],
 
-    #[cfg(target_family = "wasm")]
+    #[cfg(feature = "wasm")]
     pub display_height: u8,
-    #[cfg(target_family = "wasm")]
+    #[cfg(feature = "wasm")]
     pub display_width: u8,
 }
 
-#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Emulator {
-    #[cfg_attr(not(target_family = "wasm"), no_mangle)]
-    #[cfg_attr(target_family = "wasm", wasm_bindgen(constructor))]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new() -> Self {
         let mut emulator = Emulator {
             program_counter: PROGRAM_BEGIN_ADDR,
@@ -51,16 +70,18 @@ impl Emulator {
             delay_timer: 0,
             sound_timer: 0,
             waiting_key: None,
-            last_random_u8: 123,
             stack_size: 0,
+            stack_limit: MAX_STACK_SIZE as u16,
+            memory_policy: MemoryPolicy::Strict,
+            rng: RandomGenerator::default(),
             registers: [0; NUM_REGISTERS],
             stack: [0; MAX_STACK_SIZE],
             memory: [0; MEMORY_SIZE as usize],
             vram: [0; DISPLAY_WIDTH as usize * DISPLAY_HEIGHT as usize],
 
-            #[cfg(target_family = "wasm")]
+            #[cfg(feature = "wasm")]
             display_height: DISPLAY_HEIGHT,
-            #[cfg(target_family = "wasm")]
+            #[cfg(feature = "wasm")]
             display_width: DISPLAY_WIDTH,
         };
 
@@ -75,73 +96,112 @@ impl Emulator {
         emulator
     }
 
-    #[cfg_attr(not(target_family = "wasm"), no_mangle)]
-    pub extern "C" fn should_beep(&self) -> bool {
+    pub fn should_beep(&self) -> bool {
         self.sound_timer > 0
     }
 
-    #[cfg_attr(not(target_family = "wasm"), no_mangle)]
-    pub extern "C" fn load_program(&mut self, program: &[u8]) -> u32 {
-        let max_program_length = (self.memory.len() as u16) - PROGRAM_BEGIN_ADDR;
-        if (program.len() as u16) > max_program_length {
-            return Error::NotEnoughMemoryForProgram.into();
-        }
-        self.program_counter = PROGRAM_BEGIN_ADDR;
-        self.memory
-            .iter_mut() // grab memory mutably
-            .skip(PROGRAM_BEGIN_ADDR as usize) // skip to address where program will be written to
-            .take(program.len()) // truncate to program size
-            .zip(program)
-            .for_each(|(memory_byte, program_byte)| *memory_byte = *program_byte);
-        0
+    /// Limit how deep calls can nest, e.g. to `VIP_STACK_SIZE` or `SCHIP_STACK_SIZE`. Anything above
+    /// `MAX_STACK_SIZE` is capped to it.
+    pub fn set_stack_limit(&mut self, limit: u16) {
+        self.stack_limit = limit.min(MAX_STACK_SIZE as u16);
     }
 
-    fn get_random_u8(&mut self) -> u8 {
-        self.last_random_u8 = (RANDOM_MULTIPLIER
-            .wrapping_mul(self.last_random_u8)
-            .wrapping_add(RANDOM_INCREMENT))
-            % RANDOM_MODULE;
-        self.last_random_u8
+    pub fn get_stack_limit(&self) -> u16 {
+        self.stack_limit
     }
 
-    #[cfg(target_family = "wasm")]
-    pub fn get_vram(&self) -> Vec<u8> {
+    pub fn set_memory_policy(&mut self, policy: MemoryPolicy) {
+        self.memory_policy = policy;
+    }
+
+    pub fn get_memory_policy(&self) -> MemoryPolicy {
+        self.memory_policy
+    }
+
+    /// `load_program` for wasm, returning the error code or 0.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = load_program))]
+    pub fn load_program_code(&mut self, program: &[u8]) -> u32 {
+        error::to_code(self.load_program(program))
+    }
+
+    /// Switch `CxKK` to one of the built-in generators, starting from `seed`.
+    pub fn set_random(&mut self, kind: RandomKind, seed: u64) {
+        self.rng = RandomGenerator::new(kind, seed);
+    }
+
+    /// The built-in generator in use, `None` after `set_rng` with a custom one.
+    pub fn get_random_kind(&self) -> Option<RandomKind> {
+        self.rng.kind()
+    }
+
+    /// Restart the current generator from `seed`, so runs can be reproduced.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng.seed(seed);
+    }
+
+    /// A copy of the vram for JS, Rust code borrows it with `get_vram`.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = get_vram)]
+    pub fn copy_vram(&self) -> Vec<u8> {
         self.vram.to_vec()
     }
 
-    #[no_mangle]
-    #[cfg(not(target_family = "wasm"))]
-    pub extern "C" fn get_vram(&self) -> &[u8] {
-        &self.vram
+    /// Where the vram starts in wasm memory, so JS can view it in place with
+    /// `new Uint8Array(memory.buffer, emulator.vram_ptr(), emulator.vram_len())`.
+    /// Views are detached when memory grows, so make them again rather than keeping them around.
+    #[cfg(feature = "wasm")]
+    pub fn vram_ptr(&self) -> *const u8 {
+        self.vram.as_ptr()
     }
 
-    // #[cfg_attr(not(target_family = "wasm"), no_mangle)]
-    fn get_opcode(&self) -> Result<Instruction, Error> {
+    #[cfg(feature = "wasm")]
+    pub fn vram_len(&self) -> usize {
+        self.vram.len()
+    }
+
+    /// Where memory starts in wasm memory, like `vram_ptr`. Writes through the view poke memory.
+    #[cfg(feature = "wasm")]
+    pub fn memory_ptr(&mut self) -> *mut u8 {
+        self.memory.as_mut_ptr()
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn memory_len(&self) -> usize {
+        self.memory.len()
+    }
+
+    fn fetch_opcode(&self) -> Result<u16, Error> {
+        let out_of_bounds = || Error::new(ErrorKind::OutOfBoundsMemoryAccess(self.program_counter));
         let first_byte: u8 = self
-            .memory
-            .get(self.program_counter as usize)
-            .cloned()
-            .ok_or_else(|| Error::OutOfBoundsMemoryAccess(self.program_counter))?;
+            .memory_address(self.program_counter as usize)
+            .map(|addr| self.memory[addr])
+            .ok_or_else(|| out_of_bounds().with_pc(self.program_counter))?;
         let second_byte: u8 = self
-            .memory
-            .get((self.program_counter + 1) as usize)
-            .cloned()
-            .ok_or_else(|| Error::OutOfBoundsMemoryAccess(self.program_counter))?;
+            .memory_address(self.program_counter as usize + 1)
+            .map(|addr| self.memory[addr])
+            .ok_or_else(|| out_of_bounds().with_pc(self.program_counter))?;
 
-        let raw_opcode = ((first_byte as u16) << 8) | (second_byte as u16);
-        Instruction::parse(raw_opcode)
+        Ok(((first_byte as u16) << 8) | (second_byte as u16))
     }
 
-    #[cfg_attr(not(target_family = "wasm"), no_mangle)]
-    pub extern "C" fn tick(&mut self, keypad: &[u8]) -> u32 {
-        // update timers (even if blocking for keypress)
-        if self.delay_timer > 0 {
-            self.delay_timer -= 1;
-        }
-        if self.sound_timer > 0 {
-            self.sound_timer -= 1;
-        }
+    fn get_opcode(&self) -> Result<Instruction, Error> {
+        let raw_opcode = self.fetch_opcode()?;
+        Instruction::parse(raw_opcode).map_err(|err| err.with_pc(self.program_counter).with_opcode(raw_opcode))
+    }
+
+    /// `tick` for wasm, returning the error code or 0.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = tick))]
+    pub fn tick_code(&mut self, keypad: &[u8]) -> u32 {
+        error::to_code(self.tick(keypad))
+    }
+
+    /// `run_frame` for wasm, returning the error code or 0.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = run_frame))]
+    pub fn run_frame_code(&mut self, keypad: &[u8], cycles: u32) -> u32 {
+        error::to_code(self.run_frame(keypad, cycles))
+    }
 
+    fn execute(&mut self, keypad: &[u8]) -> Result<(), Error> {
         match self.waiting_key {
             Some(WaitingKey {
                 register_index,
@@ -154,7 +214,7 @@ impl Emulator {
                         key_index,
                         has_been_pressed: true
                     });
-                    return 0;
+                    return Ok(());
                 }
             },
             Some(WaitingKey {
@@ -162,7 +222,8 @@ impl Emulator {
                 key_index,
                 has_been_pressed: true,
             }) => {
-                if keypad[key_index] == 0 {
+                // a keypad too short to hold the key counts as released
+                if keypad.get(key_index).is_none_or(|pressed| *pressed == 0) {
                     self.registers[register_index] = key_index as u8;
                     self.program_counter += 2;
                     self.waiting_key = None;
@@ -170,16 +231,18 @@ impl Emulator {
             }
             None => {}
         };
-        match self.get_opcode() {
-            Ok(opcode) => {
-                self.run_opcode(opcode, &keypad);
-                0
-            },
-            Err(err) => err.into(),
+        if self.memory_policy == MemoryPolicy::Wrap {
+            self.program_counter &= ADDRESS_MASK;
         }
+        let pc = self.program_counter;
+        let raw_opcode = self.fetch_opcode()?;
+        // faults leave the machine as it was before the instruction
+        Instruction::parse(raw_opcode)
+            .and_then(|opcode| self.run_opcode(opcode, keypad))
+            .map_err(|err| err.with_pc(pc).with_opcode(raw_opcode))
     }
 
-    fn run_opcode(&mut self, opcode: Instruction, keypad: &[u8]) -> () {
+    fn run_opcode(&mut self, opcode: Instruction, keypad: &[u8]) -> Result<(), Error> {
         match opcode {
             Instruction::Op0nnn(addr) => {
                 self.program_counter = addr as u16;
@@ -189,13 +252,24 @@ impl Emulator {
                 self.program_counter += 2;
             }
             Instruction::Op00EE => {
+                if self.stack_size == 0 {
+                    return Err(ErrorKind::StackUnderflow.into());
+                }
+                // `stack_size` is public, so it could have been set past the stack
+                let addr = *self
+                    .stack
+                    .get(self.stack_size as usize - 1)
+                    .ok_or(ErrorKind::StackOverflow(self.stack_limit))?;
                 self.stack_size -= 1;
-                self.program_counter = self.stack[self.stack_size as usize];
+                self.program_counter = addr;
             }
             Instruction::Op1nnn(addr) => {
                 self.program_counter = addr as u16;
             }
             Instruction::Op2nnn(addr) => {
+                if self.stack_size >= self.stack_limit {
+                    return Err(ErrorKind::StackOverflow(self.stack_limit).into());
+                }
                 self.stack[self.stack_size as usize] = self.program_counter + 2;
                 self.stack_size += 1;
                 self.program_counter = addr as u16;
@@ -324,18 +398,20 @@ impl Emulator {
                 self.program_counter = addr as u16 + self.registers[0] as u16;
             }
             Instruction::OpCxkk(register_index, value) => {
-                self.registers[register_index as usize] = self.get_random_u8() & value as u8;
+                self.registers[register_index as usize] = self.rng.next_u8(&self.memory) & value as u8;
                 self.program_counter += 2;
             }
             Instruction::OpDxyn(register_index1, register_index2, value) => {
                 let x = self.registers[register_index1 as usize] % DISPLAY_WIDTH;
                 let y = self.registers[register_index2 as usize] % DISPLAY_HEIGHT;
-                self.registers[NUM_REGISTERS - 1] = 0;
                 let value = if y + value < DISPLAY_HEIGHT {
                     value as usize
                 } else {
                     DISPLAY_HEIGHT as usize - y as usize
                 };
+                // only the rows that end up on screen are read
+                self.check_memory(value)?;
+                self.registers[NUM_REGISTERS - 1] = 0;
                 let max_bit = if x + 8 < DISPLAY_WIDTH {
                     8
                 } else {
@@ -343,10 +419,12 @@ impl Emulator {
                 };
                 for byte in 0..value {
                     let py = (y as usize + byte as usize) % DISPLAY_HEIGHT as usize;
+                    let row = self
+                        .memory_address(self.index as usize + byte)
+                        .map_or(0, |addr| self.memory[addr]);
                     for bit in 0..max_bit as usize {
                         let px = (x as usize + bit) % DISPLAY_WIDTH as usize;
-                        let color =
-                            (self.memory[self.index as usize + byte as usize] >> (7 - bit)) & 1;
+                        let color = (row >> (7 - bit)) & 1;
                         let pixel =
                             &mut self.vram[py as usize * DISPLAY_WIDTH as usize + px as usize];
                         self.registers[NUM_REGISTERS - 1] |= color & (*pixel) as u8;
@@ -356,13 +434,13 @@ impl Emulator {
                 self.program_counter += 2;
             }
             Instruction::OpEx9E(register_index) => {
-                if keypad[self.registers[register_index as usize] as usize] != 0 {
+                if self.is_pressed(keypad, self.registers[register_index as usize])? {
                     self.program_counter += 2;
                 }
                 self.program_counter += 2;
             }
             Instruction::OpExA1(register_index) => {
-                if keypad[self.registers[register_index as usize] as usize] == 0 {
+                if !self.is_pressed(keypad, self.registers[register_index as usize])? {
                     self.program_counter += 2;
                 }
                 self.program_counter += 2;
@@ -387,7 +465,7 @@ impl Emulator {
                 self.program_counter += 2;
             }
             Instruction::OpFx1E(register_index) => {
-                self.index = self.index + self.registers[register_index as usize] as u16;
+                self.index = self.index.wrapping_add(self.registers[register_index as usize] as u16);
                 self.program_counter += 2;
             }
             Instruction::OpFx29(register_index) => {
@@ -398,38 +476,291 @@ impl Emulator {
             Instruction::OpFx33(register_index) => {
                 let value = self.registers[register_index as usize];
                 let digits: [u8; 3] = [value / 100, (value % 100) / 10, value % 10];
-                self.memory
-                    .iter_mut()
-                    .skip(self.index as usize)
-                    .take(3)
-                    .zip(digits)
-                    .for_each(|(byte, digit)| {
-                        *byte = digit;
-                    });
+                self.check_memory(digits.len())?;
+                for (offset, digit) in digits.into_iter().enumerate() {
+                    if let Some(addr) = self.memory_address(self.index as usize + offset) {
+                        self.memory[addr] = digit;
+                    }
+                }
                 self.program_counter += 2;
             }
             Instruction::OpFx55(register_index) => {
-                self.memory
-                    .iter_mut()
-                    .skip(self.index as usize)
-                    .take(register_index as usize + 1)
-                    .zip(self.registers.iter().take(register_index as usize + 1))
-                    .for_each(|(byte, register)| *byte = *register);
-                self.index += register_index as u16 + 1;
+                self.check_memory(register_index as usize + 1)?;
+                for offset in 0..=register_index as usize {
+                    if let Some(addr) = self.memory_address(self.index as usize + offset) {
+                        self.memory[addr] = self.registers[offset];
+                    }
+                }
+                self.index = self.index.wrapping_add(register_index as u16 + 1);
                 self.program_counter += 2;
             }
             Instruction::OpFx65(register_index) => {
-                self.memory
-                    .iter()
-                    .skip(self.index as usize)
-                    .take(register_index as usize + 1)
-                    .zip(self.registers.iter_mut().take(register_index as usize + 1))
-                    .for_each(|(byte, register)| {
-                        *register = *byte;
-                    });
-                self.index += register_index as u16 + 1;
+                self.check_memory(register_index as usize + 1)?;
+                for offset in 0..=register_index as usize {
+                    if let Some(addr) = self.memory_address(self.index as usize + offset) {
+                        self.registers[offset] = self.memory[addr];
+                    }
+                }
+                self.index = self.index.wrapping_add(register_index as u16 + 1);
                 self.program_counter += 2;
             }
         }
+        Ok(())
+    }
+
+    // where `addr` lands in memory under the memory policy, if anywhere
+    fn memory_address(&self, addr: usize) -> Option<usize> {
+        match self.memory_policy {
+            MemoryPolicy::Wrap => Some(addr & ADDRESS_MASK as usize),
+            MemoryPolicy::Strict | MemoryPolicy::Clamp => (addr < self.memory.len()).then_some(addr),
+        }
+    }
+
+    // with the strict policy, fails when the `len` bytes starting at I don't all fit in memory
+    fn check_memory(&self, len: usize) -> Result<(), Error> {
+        if self.memory_policy == MemoryPolicy::Strict && self.index as usize + len > self.memory.len() {
+            let addr = self.index.max(MEMORY_SIZE);
+            return Err(ErrorKind::OutOfBoundsMemoryAccess(addr).into());
+        }
+        Ok(())
+    }
+
+    // keys missing from a short keypad are released
+    fn is_pressed(&self, keypad: &[u8], key: u8) -> Result<bool, Error> {
+        if key as usize >= NUM_KEYS {
+            return Err(ErrorKind::InvalidKey(key as u16).into());
+        }
+        Ok(keypad.get(key as usize).is_some_and(|pressed| *pressed != 0))
+    }
+}
+
+impl Emulator {
+    pub fn load_program(&mut self, program: &[u8]) -> Result<(), Error> {
+        let max_program_length = self.memory.len() - PROGRAM_BEGIN_ADDR as usize;
+        if program.len() > max_program_length {
+            return Err(ErrorKind::NotEnoughMemoryForProgram.into());
+        }
+        self.program_counter = PROGRAM_BEGIN_ADDR;
+        self.memory
+            .iter_mut() // grab memory mutably
+            .skip(PROGRAM_BEGIN_ADDR as usize) // skip to address where program will be written to
+            .take(program.len()) // truncate to program size
+            .zip(program)
+            .for_each(|(memory_byte, program_byte)| *memory_byte = *program_byte);
+        Ok(())
+    }
+
+    /// Update the timers (even if blocking for keypress) and execute one instruction.
+    ///
+    /// Never panics, for any program and keypad: faults are returned as errors and keys missing
+    /// from a short keypad count as released.
+    pub fn tick(&mut self, keypad: &[u8]) -> Result<(), Error> {
+        self.update_timers();
+        self.execute(keypad)
+    }
+
+    /// Run a whole 60Hz frame: timers are updated once and `cycles` instructions are executed.
+    /// Stops at the first error.
+    pub fn run_frame(&mut self, keypad: &[u8], cycles: u32) -> Result<(), Error> {
+        self.update_timers();
+        for _ in 0..cycles {
+            self.execute(keypad)?;
+        }
+        Ok(())
+    }
+
+    /// Execute a single instruction, without touching the timers.
+    pub fn step(&mut self, keypad: &[u8]) -> Result<(), Error> {
+        self.execute(keypad)
+    }
+
+    /// Count down the timers, which is meant to happen 60 times per second.
+    pub fn update_timers(&mut self) {
+        self.rng.frame();
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
+    }
+
+    /// Whether the program is stuck on a jump to its own address, which is how
+    /// most chip8 programs (test ROMs included) signal they are done.
+    pub fn is_halted(&self) -> bool {
+        matches!(
+            self.current_instruction(),
+            Ok(Instruction::Op1nnn(addr)) if addr == self.program_counter
+        )
+    }
+
+    pub fn current_instruction(&self) -> Result<Instruction, Error> {
+        self.get_opcode()
+    }
+
+    /// The raw opcode stored at `addr`.
+    pub fn opcode_at(&self, addr: u16) -> Option<u16> {
+        let first = *self.memory.get(addr as usize)?;
+        let second = *self.memory.get(addr as usize + 1)?;
+        Some(((first as u16) << 8) | second as u16)
+    }
+
+    pub fn get_vram(&self) -> &[u8] {
+        &self.vram
+    }
+
+    pub fn get_memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    pub fn get_memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    pub fn get_registers(&self) -> &[u8] {
+        &self.registers
+    }
+
+    pub fn get_registers_mut(&mut self) -> &mut [u8] {
+        &mut self.registers
+    }
+
+    pub fn get_rng(&self) -> &RandomGenerator {
+        &self.rng
+    }
+
+    /// Use any generator for `CxKK`, including your own through `RandomGenerator::custom`.
+    pub fn set_rng(&mut self, rng: RandomGenerator) {
+        self.rng = rng;
+    }
+
+    pub fn get_stack(&self) -> &[u16] {
+        &self.stack[..(self.stack_size as usize).min(MAX_STACK_SIZE)]
+    }
+
+    /// Write the whole machine (memory, screen, registers, stack, timers, settings and the generator) to the
+    /// first `STATE_SIZE` bytes of `out`. `load_state` reads it back, in this emulator or in another one.
+    pub fn save_state(&self, out: &mut [u8]) -> Result<(), Error> {
+        let out = out.get_mut(..STATE_SIZE).ok_or(ErrorKind::InvalidBufferSize)?;
+        let mut writer = StateWriter { out };
+        writer.bytes(&STATE_MAGIC);
+        writer.bytes(&[STATE_VERSION]);
+        writer.bytes(&self.program_counter.to_le_bytes());
+        writer.bytes(&self.index.to_le_bytes());
+        writer.bytes(&[self.delay_timer, self.sound_timer]);
+        writer.bytes(&match self.waiting_key {
+            None => [0, 0, 0],
+            Some(key) => [1 + key.has_been_pressed as u8, key.register_index as u8, key.key_index as u8],
+        });
+        writer.bytes(&self.stack_size.to_le_bytes());
+        writer.bytes(&self.stack_limit.to_le_bytes());
+        let kind = self.rng.kind().map_or(STATE_CUSTOM_RANDOM, |kind| kind as u8);
+        writer.bytes(&[self.memory_policy as u8, kind]);
+        writer.bytes(&self.rng.state().to_le_bytes());
+        writer.bytes(&self.registers);
+        self.stack.iter().for_each(|addr| writer.bytes(&addr.to_le_bytes()));
+        writer.bytes(&self.memory);
+        writer.bytes(&self.vram);
+        Ok(())
+    }
+
+    /// Restore a state written by `save_state`. On `InvalidState` nothing is changed.
+    ///
+    /// Custom generators can't be written out, so a state saved with one keeps the custom generator this
+    /// emulator has as is, and is `InvalidState` for an emulator with a built-in one.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), Error> {
+        let invalid = || Error::new(ErrorKind::InvalidState);
+        if state.len() != STATE_SIZE || state[..STATE_MAGIC.len()] != STATE_MAGIC {
+            return Err(invalid());
+        }
+        let mut reader = StateReader { data: &state[STATE_MAGIC.len()..] };
+        if reader.u8() != STATE_VERSION {
+            return Err(invalid());
+        }
+        let mut loaded = self.clone();
+        loaded.program_counter = reader.u16();
+        loaded.index = reader.u16();
+        loaded.delay_timer = reader.u8();
+        loaded.sound_timer = reader.u8();
+        let (waiting, register_index, key_index) = (reader.u8(), reader.u8() as usize, reader.u8() as usize);
+        if register_index >= NUM_REGISTERS || key_index >= NUM_KEYS {
+            return Err(invalid());
+        }
+        loaded.waiting_key = match waiting {
+            0 => None,
+            1 | 2 => Some(WaitingKey {
+                register_index,
+                key_index,
+                has_been_pressed: waiting == 2,
+            }),
+            _ => return Err(invalid()),
+        };
+        loaded.stack_size = reader.u16();
+        loaded.stack_limit = reader.u16();
+        if loaded.stack_size as usize > MAX_STACK_SIZE || loaded.stack_limit as usize > MAX_STACK_SIZE {
+            return Err(invalid());
+        }
+        loaded.memory_policy = match reader.u8() {
+            0 => MemoryPolicy::Strict,
+            1 => MemoryPolicy::Wrap,
+            2 => MemoryPolicy::Clamp,
+            _ => return Err(invalid()),
+        };
+        let kind = match reader.u8() {
+            0 => Some(RandomKind::Pcg32),
+            1 => Some(RandomKind::Legacy),
+            2 => Some(RandomKind::Vip),
+            STATE_CUSTOM_RANDOM if self.rng.kind().is_none() => None,
+            _ => return Err(invalid()),
+        };
+        let rng_state = reader.u64();
+        if let Some(kind) = kind {
+            loaded.rng = RandomGenerator::from_state(kind, rng_state);
+        }
+        loaded.registers.copy_from_slice(reader.bytes(NUM_REGISTERS));
+        loaded.stack.iter_mut().for_each(|addr| *addr = reader.u16());
+        loaded.memory.copy_from_slice(reader.bytes(MEMORY_SIZE as usize));
+        loaded.vram.copy_from_slice(reader.bytes(VRAM_SIZE));
+        *self = loaded;
+        Ok(())
+    }
+}
+
+// saved states are little endian, and their size is checked before these are used
+struct StateWriter<'a> {
+    out: &'a mut [u8],
+}
+
+impl StateWriter<'_> {
+    fn bytes(&mut self, bytes: &[u8]) {
+        let (head, rest) = core::mem::take(&mut self.out).split_at_mut(bytes.len());
+        head.copy_from_slice(bytes);
+        self.out = rest;
+    }
+}
+
+struct StateReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    fn bytes(&mut self, len: usize) -> &'a [u8] {
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        head
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.bytes(1)[0]
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes([self.u8(), self.u8()])
+    }
+
+    fn u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.bytes(8));
+        u64::from_le_bytes(bytes)
     }
 }
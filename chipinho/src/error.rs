@@ -1,32 +1,161 @@
+use core::fmt;
 
-#[derive(Debug)]
-pub enum Error {
-    ParseInvalidInstruction(u16),
-    OutOfBoundsMemoryAccess(u16),
-    NotEnoughMemoryForProgram,
-    None
+// every kind is listed once, with its code and the meaning of its 16 bit payload, and both
+// directions of the u32 encoding are generated from this table
+macro_rules! error_kinds {
+    ($($(#[$meta:meta])* $kind:ident $(($data:ident))? = $code:literal,)*) => {
+        /// What went wrong, without the context of where it happened.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum ErrorKind {
+            $($(#[$meta])* $kind $((error_kinds!(@payload $data)))?,)*
+        }
+
+        impl ErrorKind {
+            /// The code used at the C/wasm boundary: `0x1TTTDDDD`, where `TTT` is the kind and
+            /// `DDDD` its payload.
+            pub fn code(&self) -> u32 {
+                match *self {
+                    $(ErrorKind::$kind $(($data))? => ERROR_CODE_BASE | $code << 16 $(| $data as u32)?,)*
+                }
+            }
+
+            /// The kind encoded by `code`, if there is one.
+            pub fn from_code(code: u32) -> Option<Self> {
+                if code & !0x0FFF_FFFF != ERROR_CODE_BASE {
+                    return None;
+                }
+                #[allow(unused_variables)]
+                let data = (code & 0xFFFF) as u16;
+                match (code & 0x0FFF_0000) >> 16 {
+                    $($code => Some(ErrorKind::$kind $((error_kinds!(@data data $data)))?),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+    (@payload $data:ident) => { u16 };
+    (@data $value:ident $data:ident) => { $value };
 }
 
-impl From<Error> for u32 {
-    fn from(value: Error) -> Self {
-         match value {
-            Error::ParseInvalidInstruction(instr) => 0x10010000 | instr as u32,
-            Error::OutOfBoundsMemoryAccess(addr) => 0x10020000 | addr as u32,
-            Error::NotEnoughMemoryForProgram => 0x10030000,
-            Error::None => 0x0
+const ERROR_CODE_BASE: u32 = 0x1000_0000;
+
+error_kinds! {
+    /// The opcode does not decode to any instruction.
+    ParseInvalidInstruction(opcode) = 0x1,
+    OutOfBoundsMemoryAccess(addr) = 0x2,
+    NotEnoughMemoryForProgram = 0x3,
+    InvalidBufferSize = 0x4,
+    /// The line number of the first entry that could not be parsed.
+    InvalidSymbolFile(line) = 0x5,
+    /// A call went deeper than the stack limit, which is the payload.
+    StackOverflow(limit) = 0x6,
+    /// A return with nothing on the stack.
+    StackUnderflow = 0x7,
+    /// `Ex9E`/`ExA1` with a register holding something other than a key (0 to F).
+    InvalidKey(key) = 0x8,
+    /// A saved state that is truncated, from another format version or holds impossible values.
+    InvalidState = 0x9,
+    /// A value passed in from C that names none of the options, like an unknown memory policy.
+    InvalidArgument = 0xA,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ErrorKind::ParseInvalidInstruction(opcode) => write!(f, "invalid instruction {:04X}", opcode),
+            ErrorKind::OutOfBoundsMemoryAccess(addr) => write!(f, "out of bounds memory access to 0x{:03X}", addr),
+            ErrorKind::NotEnoughMemoryForProgram => write!(f, "not enough memory for the program"),
+            ErrorKind::InvalidBufferSize => write!(f, "invalid buffer size"),
+            ErrorKind::InvalidSymbolFile(line) => write!(f, "invalid symbol file entry on line {}", line),
+            ErrorKind::StackOverflow(limit) => write!(f, "stack overflow, calls nested deeper than {}", limit),
+            ErrorKind::StackUnderflow => write!(f, "stack underflow, return without a call"),
+            ErrorKind::InvalidKey(key) => write!(f, "invalid key 0x{:02X}", key),
+            ErrorKind::InvalidState => write!(f, "invalid saved state"),
+            ErrorKind::InvalidArgument => write!(f, "invalid argument"),
         }
     }
 }
 
-impl Into<Error> for u32 {
-    fn into(self) -> Error {
-        let error_type : u16 = ((self & 0xFFFF0000) >> 16) as u16;
-        let data = (self & 0xFFFF) as u16;
-        match error_type {
-            0x1 => Error::ParseInvalidInstruction(data),
-            0x2 => Error::OutOfBoundsMemoryAccess(data),
-            0x3 => Error::NotEnoughMemoryForProgram,
-            _ => Error::None
+/// An error along with the instruction that caused it, when there is one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub pc: Option<u16>,
+    pub opcode: Option<u16>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind) -> Self {
+        Error {
+            kind,
+            pc: None,
+            opcode: None,
         }
     }
+
+    pub fn with_pc(self, pc: u16) -> Self {
+        Error { pc: Some(pc), ..self }
+    }
+
+    pub fn with_opcode(self, opcode: u16) -> Self {
+        Error {
+            opcode: Some(opcode),
+            ..self
+        }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Error::new(kind)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(pc) = self.pc {
+            write!(f, " at 0x{:03X}", pc)?;
+        }
+        match (self.kind, self.opcode) {
+            // already part of the message
+            (ErrorKind::ParseInvalidInstruction(_), _) | (_, None) => Ok(()),
+            (_, Some(opcode)) => write!(f, " (opcode {:04X})", opcode),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+/// Only the kind crosses the boundary, the context is lost.
+impl From<Error> for u32 {
+    fn from(error: Error) -> Self {
+        error.kind.code()
+    }
+}
+
+/// A code that is not 0 and does not match any `ErrorKind`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownErrorCode(pub u32);
+
+impl fmt::Display for UnknownErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown error code 0x{:08X}", self.0)
+    }
+}
+
+impl core::error::Error for UnknownErrorCode {}
+
+impl TryFrom<u32> for Error {
+    type Error = UnknownErrorCode;
+
+    fn try_from(code: u32) -> Result<Self, Self::Error> {
+        ErrorKind::from_code(code).map(Error::new).ok_or(UnknownErrorCode(code))
+    }
+}
+
+/// Turn the result of an emulator call into the code returned at the C/wasm boundary, where
+/// 0 means success.
+pub fn to_code<T>(result: Result<T, Error>) -> u32 {
+    result.err().map_or(0, u32::from)
 }
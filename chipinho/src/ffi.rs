@@ -0,0 +1,299 @@
+//! C interface over an opaque `Chipinho` handle, declared in `include/chipinho.h` (regenerate it with
+//! `make header`).
+//!
+//! Functions that can fail return an error code, 0 on success. Codes are `0x1TTTDDDD`, where `TTT` is
+//! one of the `CHIPINHO_ERROR_*` kinds and `DDDD` its payload (the opcode, address, stack limit or key).
+//! `chipinho_describe_error` turns them into text. Handles must not be shared between threads without
+//! locking, and none of the functions accept a null handle.
+use alloc::boxed::Box;
+use core::{ffi::c_char, fmt, slice};
+
+use crate::{
+    emulator::{Emulator, MemoryPolicy},
+    error::{self, Error, ErrorKind, UnknownErrorCode},
+    random::RandomKind,
+};
+
+pub const CHIPINHO_OK: u32 = 0;
+pub const CHIPINHO_ERROR_KIND_MASK: u32 = 0xFFFF0000;
+pub const CHIPINHO_ERROR_PAYLOAD_MASK: u32 = 0x0000FFFF;
+/// Payload: the opcode.
+pub const CHIPINHO_ERROR_INVALID_INSTRUCTION: u32 = 0x10010000;
+/// Payload: the address.
+pub const CHIPINHO_ERROR_OUT_OF_BOUNDS_MEMORY_ACCESS: u32 = 0x10020000;
+pub const CHIPINHO_ERROR_NOT_ENOUGH_MEMORY_FOR_PROGRAM: u32 = 0x10030000;
+/// Also returned for a null pointer with a non zero length.
+pub const CHIPINHO_ERROR_INVALID_BUFFER_SIZE: u32 = 0x10040000;
+/// Payload: the line number.
+pub const CHIPINHO_ERROR_INVALID_SYMBOL_FILE: u32 = 0x10050000;
+/// Payload: the stack limit.
+pub const CHIPINHO_ERROR_STACK_OVERFLOW: u32 = 0x10060000;
+pub const CHIPINHO_ERROR_STACK_UNDERFLOW: u32 = 0x10070000;
+/// Payload: the key.
+pub const CHIPINHO_ERROR_INVALID_KEY: u32 = 0x10080000;
+pub const CHIPINHO_ERROR_INVALID_STATE: u32 = 0x10090000;
+/// A `ChipinhoMemoryPolicy` or `ChipinhoRandomKind` value out of range.
+pub const CHIPINHO_ERROR_INVALID_ARGUMENT: u32 = 0x100A0000;
+
+// spelled out for the header, tests/ffi.rs checks them against `constants`
+pub const CHIPINHO_DISPLAY_WIDTH: usize = 64;
+pub const CHIPINHO_DISPLAY_HEIGHT: usize = 32;
+pub const CHIPINHO_VRAM_SIZE: usize = 2048;
+pub const CHIPINHO_MEMORY_SIZE: usize = 4096;
+pub const CHIPINHO_NUM_KEYS: usize = 16;
+pub const CHIPINHO_NUM_REGISTERS: usize = 16;
+pub const CHIPINHO_MAX_STACK_SIZE: usize = 32;
+
+/// An emulator, only handled through pointers from `chipinho_new`.
+pub struct Chipinho {
+    emulator: Emulator,
+}
+
+/// A copy of the machine state, filled by `chipinho_get_state`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChipinhoState {
+    pub program_counter: u16,
+    pub index: u16,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    /// whether `Fx0A` is waiting for a key
+    pub waiting_key: bool,
+    pub stack_size: u16,
+    pub stack_limit: u16,
+    pub registers: [u8; CHIPINHO_NUM_REGISTERS],
+    /// only the first `stack_size` entries are meaningful
+    pub stack: [u16; CHIPINHO_MAX_STACK_SIZE],
+}
+
+// a null pointer is fine for an empty buffer, anything else has to point to `len` bytes
+unsafe fn buffer<'a>(ptr: *const u8, len: usize) -> Result<&'a [u8], Error> {
+    match (ptr.is_null(), len) {
+        (true, 0) => Ok(&[]),
+        (true, _) => Err(ErrorKind::InvalidBufferSize.into()),
+        (false, _) => Ok(slice::from_raw_parts(ptr, len)),
+    }
+}
+
+// C enums are plain ints, so the values are checked before they become Rust enums
+fn memory_policy(value: u32) -> Result<MemoryPolicy, Error> {
+    [MemoryPolicy::Strict, MemoryPolicy::Wrap, MemoryPolicy::Clamp]
+        .into_iter()
+        .find(|policy| *policy as u32 == value)
+        .ok_or_else(|| ErrorKind::InvalidArgument.into())
+}
+
+fn random_kind(value: u32) -> Result<RandomKind, Error> {
+    [RandomKind::Pcg32, RandomKind::Legacy, RandomKind::Vip]
+        .into_iter()
+        .find(|kind| *kind as u32 == value)
+        .ok_or_else(|| ErrorKind::InvalidArgument.into())
+}
+
+/// Create an emulator with the font loaded, free it with `chipinho_free`.
+#[no_mangle]
+pub extern "C" fn chipinho_new() -> *mut Chipinho {
+    Box::into_raw(Box::new(Chipinho { emulator: Emulator::new() }))
+}
+
+/// # Safety
+/// `chipinho` must come from `chipinho_new` and not be used afterwards. Null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_free(chipinho: *mut Chipinho) {
+    if !chipinho.is_null() {
+        drop(Box::from_raw(chipinho));
+    }
+}
+
+/// Copy `len` bytes of program to 0x200 and point PC at it.
+///
+/// # Safety
+/// `chipinho` must be a live handle and `program` must point to `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_load_program(chipinho: *mut Chipinho, program: *const u8, len: usize) -> u32 {
+    let chipinho = &mut *chipinho;
+    error::to_code(buffer(program, len).and_then(|program| chipinho.emulator.load_program(program)))
+}
+
+/// Update the timers and execute one instruction. `keypad` holds one byte per key, non zero when pressed.
+///
+/// # Safety
+/// `chipinho` must be a live handle and `keypad` must point to `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_tick(chipinho: *mut Chipinho, keypad: *const u8, len: usize) -> u32 {
+    let chipinho = &mut *chipinho;
+    error::to_code(buffer(keypad, len).and_then(|keypad| chipinho.emulator.tick(keypad)))
+}
+
+/// Execute one instruction without touching the timers.
+///
+/// # Safety
+/// `chipinho` must be a live handle and `keypad` must point to `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_step(chipinho: *mut Chipinho, keypad: *const u8, len: usize) -> u32 {
+    let chipinho = &mut *chipinho;
+    error::to_code(buffer(keypad, len).and_then(|keypad| chipinho.emulator.step(keypad)))
+}
+
+/// Update the timers once and execute `cycles` instructions, stopping at the first error.
+///
+/// # Safety
+/// `chipinho` must be a live handle and `keypad` must point to `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_run_frame(
+    chipinho: *mut Chipinho,
+    keypad: *const u8,
+    len: usize,
+    cycles: u32,
+) -> u32 {
+    let chipinho = &mut *chipinho;
+    error::to_code(buffer(keypad, len).and_then(|keypad| chipinho.emulator.run_frame(keypad, cycles)))
+}
+
+/// # Safety
+/// `chipinho` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_update_timers(chipinho: *mut Chipinho) {
+    (*chipinho).emulator.update_timers();
+}
+
+/// The `CHIPINHO_VRAM_SIZE` bytes of the screen, row major with one byte (0 or 1) per pixel. The
+/// pointer stays valid as long as the handle.
+///
+/// # Safety
+/// `chipinho` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_vram(chipinho: *const Chipinho) -> *const u8 {
+    (*chipinho).emulator.get_vram().as_ptr()
+}
+
+/// The `CHIPINHO_MEMORY_SIZE` bytes of memory, writable. The pointer stays valid as long as the handle.
+///
+/// # Safety
+/// `chipinho` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_memory(chipinho: *mut Chipinho) -> *mut u8 {
+    (*chipinho).emulator.get_memory_mut().as_mut_ptr()
+}
+
+/// Whether the sound timer is running, the buzzer should sound while it is.
+///
+/// # Safety
+/// `chipinho` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_should_beep(chipinho: *const Chipinho) -> bool {
+    (*chipinho).emulator.should_beep()
+}
+
+/// # Safety
+/// `chipinho` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_sound_timer(chipinho: *const Chipinho) -> u8 {
+    (*chipinho).emulator.sound_timer
+}
+
+/// Copy the registers, timers and stack into `state`.
+///
+/// # Safety
+/// `chipinho` must be a live handle and `state` must point to a `ChipinhoState`.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_get_state(chipinho: *const Chipinho, state: *mut ChipinhoState) {
+    let emulator = &(*chipinho).emulator;
+    let mut copy = ChipinhoState {
+        program_counter: emulator.program_counter,
+        index: emulator.index,
+        delay_timer: emulator.delay_timer,
+        sound_timer: emulator.sound_timer,
+        waiting_key: emulator.waiting_key.is_some(),
+        stack_size: emulator.stack_size,
+        stack_limit: emulator.get_stack_limit(),
+        ..Default::default()
+    };
+    copy.registers.copy_from_slice(emulator.get_registers());
+    copy.stack[..emulator.get_stack().len()].copy_from_slice(emulator.get_stack());
+    *state = copy;
+}
+
+/// Limit how deep calls can nest, capped to `CHIPINHO_MAX_STACK_SIZE`.
+///
+/// # Safety
+/// `chipinho` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_set_stack_limit(chipinho: *mut Chipinho, limit: u16) {
+    (*chipinho).emulator.set_stack_limit(limit);
+}
+
+/// Pick what accesses past 4 KiB do, `policy` is a `ChipinhoMemoryPolicy`. Any other value is
+/// `CHIPINHO_ERROR_INVALID_ARGUMENT` and changes nothing.
+///
+/// # Safety
+/// `chipinho` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_set_memory_policy(chipinho: *mut Chipinho, policy: u32) -> u32 {
+    let chipinho = &mut *chipinho;
+    error::to_code(memory_policy(policy).map(|policy| chipinho.emulator.set_memory_policy(policy)))
+}
+
+/// Switch `CxKK` to one of the built-in generators, starting from `seed`. `kind` is a `ChipinhoRandomKind`,
+/// any other value is `CHIPINHO_ERROR_INVALID_ARGUMENT` and changes nothing.
+///
+/// # Safety
+/// `chipinho` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_set_random(chipinho: *mut Chipinho, kind: u32, seed: u64) -> u32 {
+    let chipinho = &mut *chipinho;
+    error::to_code(random_kind(kind).map(|kind| chipinho.emulator.set_random(kind, seed)))
+}
+
+/// Restart the current generator from `seed`.
+///
+/// # Safety
+/// `chipinho` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_set_seed(chipinho: *mut Chipinho, seed: u64) {
+    (*chipinho).emulator.set_seed(seed);
+}
+
+// formats into a C buffer, always leaving room for the terminating NUL
+struct CBuffer<'a> {
+    buffer: &'a mut [u8],
+    written: usize,
+    needed: usize,
+}
+
+impl fmt::Write for CBuffer<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let room = self.buffer.len().saturating_sub(self.written + 1);
+        let len = s.len().min(room);
+        self.buffer[self.written..self.written + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.written += len;
+        self.needed += s.len();
+        Ok(())
+    }
+}
+
+/// Write a NUL terminated description of `code` to `buffer`, truncated to `len - 1` characters. Returns the
+/// length of the full description like `snprintf`, so a result of `len` or more means it was truncated.
+///
+/// # Safety
+/// `buffer` must point to `len` bytes, it can be null when `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn chipinho_describe_error(code: u32, buffer: *mut c_char, len: usize) -> usize {
+    let buffer: &mut [u8] = if buffer.is_null() || len == 0 {
+        &mut []
+    } else {
+        slice::from_raw_parts_mut(buffer as *mut u8, len)
+    };
+    let mut out = CBuffer { buffer, written: 0, needed: 0 };
+    let _ = match code {
+        CHIPINHO_OK => fmt::write(&mut out, format_args!("ok")),
+        code => match ErrorKind::from_code(code) {
+            Some(kind) => fmt::write(&mut out, format_args!("{}", kind)),
+            None => fmt::write(&mut out, format_args!("{}", UnknownErrorCode(code))),
+        },
+    };
+    if let Some(end) = out.buffer.get_mut(out.written) {
+        *end = 0;
+    }
+    out.needed
+}
@@ -0,0 +1,91 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{constants::*, emulator::Emulator};
+
+/// RGBA pixels for a canvas `ImageData` or a texture, four bytes per vram pixel.
+///
+/// Call `update_from` with the emulator, `update` with a vram or `update_intensity` with
+/// `Phosphor::get_intensity` once per presented frame. In wasm, `rgba_ptr` and `rgba_len` view the
+/// pixels in place, with no copy.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Framebuffer {
+    /// lit pixels, as 0xRRGGBBAA
+    pub on: u32,
+    /// unlit pixels, as 0xRRGGBBAA
+    pub off: u32,
+
+    rgba: [u8; RGBA_SIZE],
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Framebuffer {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(on: u32, off: u32) -> Self {
+        let mut framebuffer = Framebuffer {
+            on,
+            off,
+            rgba: [0; RGBA_SIZE],
+        };
+        framebuffer.update(&[0; VRAM_SIZE]);
+        framebuffer
+    }
+
+    /// Paint the vram, any pixel other than 0 is lit.
+    pub fn update(&mut self, vram: &[u8]) {
+        let (on, off) = (self.on.to_be_bytes(), self.off.to_be_bytes());
+        self.rgba
+            .chunks_exact_mut(4)
+            .zip(vram)
+            .for_each(|(color, pixel)| color.copy_from_slice(if *pixel != 0 { &on } else { &off }));
+    }
+
+    /// Paint the emulator vram, from wasm this reads it in place instead of passing a copy in.
+    pub fn update_from(&mut self, emulator: &Emulator) {
+        self.update(emulator.get_vram());
+    }
+
+    /// Paint intensities (0 = off, 255 = fully lit), blending each channel between `off` and `on`.
+    pub fn update_intensity(&mut self, intensity: &[u8]) {
+        let (on, off) = (self.on.to_be_bytes(), self.off.to_be_bytes());
+        self.rgba
+            .chunks_exact_mut(4)
+            .zip(intensity)
+            .for_each(|(color, intensity)| {
+                color
+                    .iter_mut()
+                    .zip(on.iter().zip(off))
+                    .for_each(|(channel, (on, off))| *channel = blend(*on, off, *intensity));
+            });
+    }
+
+    /// Where the pixels start in wasm memory, to draw them with no copy:
+    /// `new ImageData(new Uint8ClampedArray(memory.buffer, fb.rgba_ptr(), fb.rgba_len()), 64)`.
+    #[cfg(feature = "wasm")]
+    pub fn rgba_ptr(&self) -> *const u8 {
+        self.rgba.as_ptr()
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn rgba_len(&self) -> usize {
+        self.rgba.len()
+    }
+}
+
+impl Framebuffer {
+    pub fn get_rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Framebuffer::new(FRAMEBUFFER_ON, FRAMEBUFFER_OFF)
+    }
+}
+
+fn blend(on: u8, off: u8, intensity: u8) -> u8 {
+    let delta = (on as i32 - off as i32) * intensity as i32 / u8::MAX as i32;
+    (off as i32 + delta) as u8
+}
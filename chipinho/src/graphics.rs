@@ -0,0 +1,69 @@
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::PixelColor,
+    primitives::Rectangle,
+};
+
+use crate::constants::*;
+
+/// Draws the vram into an embedded-graphics `DrawTarget`, like an SSD1306 OLED or a TFT panel.
+///
+/// Each chip8 pixel is a `scale` x `scale` square, placed from `origin`. `fit` picks both for a
+/// screen, e.g. a 128x64 SSD1306 gets scale 2 and is filled edge to edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Display<C> {
+    pub on: C,
+    pub off: C,
+    pub scale: u32,
+    pub origin: Point,
+}
+
+impl<C: PixelColor> Display<C> {
+    pub fn new(on: C, off: C) -> Self {
+        Display {
+            on,
+            off,
+            scale: 1,
+            origin: Point::zero(),
+        }
+    }
+
+    /// The biggest scale that fits in `size` (at least 1), centered.
+    pub fn fit(mut self, size: Size) -> Self {
+        let (width, height) = (DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32);
+        self.scale = (size.width / width).min(size.height / height).max(1);
+        self.origin = Point::new(
+            (size.width.saturating_sub(width * self.scale) / 2) as i32,
+            (size.height.saturating_sub(height * self.scale) / 2) as i32,
+        );
+        self
+    }
+
+    /// The area `draw` covers.
+    pub fn bounding_box(&self) -> Rectangle {
+        let size = Size::new(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32) * self.scale;
+        Rectangle::new(self.origin, size)
+    }
+
+    /// Draw `vram` (from `Emulator::get_vram`), any pixel other than 0 is lit. Pixels missing from
+    /// a short `vram` are drawn unlit.
+    pub fn draw<D>(&self, vram: &[u8], target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let area = self.bounding_box();
+        let scale = self.scale.max(1) as usize;
+        let (width, height) = (area.size.width as usize, area.size.height as usize);
+        let colors = (0..height).flat_map(|y| {
+            (0..width).map(move |x| {
+                let index = (y / scale) * DISPLAY_WIDTH as usize + x / scale;
+                match vram.get(index) {
+                    Some(pixel) if *pixel != 0 => self.on,
+                    _ => self.off,
+                }
+            })
+        });
+        target.fill_contiguous(&area, colors)
+    }
+}
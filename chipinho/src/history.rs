@@ -0,0 +1,163 @@
+use crate::constants::NUM_REGISTERS;
+#[cfg(feature = "alloc")]
+use crate::crash::CrashReport;
+use crate::emulator::Emulator;
+use crate::error::Error;
+
+const INDEX_CHANGED: u32 = 1 << NUM_REGISTERS;
+
+/// One executed instruction and what it did to the registers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Entry {
+    pub pc: u16,
+    pub opcode: u16,
+    // bit n is set when Vn changed, the bit after the registers is for I
+    changed: u32,
+    registers: [u8; NUM_REGISTERS],
+    index: u16,
+}
+
+impl Entry {
+    fn new(pc: u16, opcode: u16, registers: &[u8], index: u16, after: &Emulator) -> Self {
+        let changed = registers
+            .iter()
+            .zip(after.get_registers())
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .fold(0, |changed, (register, _)| changed | 1 << register);
+        let index_changed = if index != after.index { INDEX_CHANGED } else { 0 };
+        let mut registers = [0; NUM_REGISTERS];
+        registers.copy_from_slice(&after.get_registers()[..NUM_REGISTERS]);
+        Entry {
+            pc,
+            opcode,
+            changed: changed | index_changed,
+            registers,
+            index: after.index,
+        }
+    }
+
+    /// The registers the instruction wrote to, with their new values.
+    pub fn register_changes(&self) -> impl Iterator<Item = (usize, u8)> + '_ {
+        (0..NUM_REGISTERS)
+            .filter(|register| self.changed & 1 << register != 0)
+            .map(|register| (register, self.registers[register]))
+    }
+
+    /// The new value of I, if the instruction changed it.
+    pub fn index_change(&self) -> Option<u16> {
+        (self.changed & INDEX_CHANGED != 0).then_some(self.index)
+    }
+}
+
+/// Ring buffer keeping the last `N` entries.
+#[derive(Clone, Debug)]
+pub struct History<const N: usize> {
+    entries: [Entry; N],
+    next: usize,
+    len: usize,
+}
+
+impl<const N: usize> History<N> {
+    pub fn new() -> Self {
+        History {
+            entries: [Entry::default(); N],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, entry: Entry) {
+        if N == 0 {
+            return;
+        }
+        self.entries[self.next] = entry;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.next = 0;
+        self.len = 0;
+    }
+
+    /// Oldest entry first.
+    pub fn iter(&self) -> impl Iterator<Item = &Entry> + '_ {
+        let first = (self.next + N - self.len) % N.max(1);
+        (0..self.len).map(move |offset| &self.entries[(first + offset) % N])
+    }
+}
+
+impl<const N: usize> Default for History<N> {
+    fn default() -> Self {
+        History::new()
+    }
+}
+
+/// Runs an `Emulator` while keeping the last `N` executed instructions, so errors can be
+/// turned into a `CrashReport` with the history that led to them.
+#[derive(Clone)]
+pub struct Recorder<const N: usize> {
+    pub emulator: Emulator,
+    pub history: History<N>,
+}
+
+impl<const N: usize> Recorder<N> {
+    pub fn new(emulator: Emulator) -> Self {
+        Recorder {
+            emulator,
+            history: History::new(),
+        }
+    }
+
+    /// Same as `Emulator::step`, recording the instruction.
+    pub fn step(&mut self, keypad: &[u8]) -> Result<(), Error> {
+        let emulator = &mut self.emulator;
+        let mut pc = emulator.program_counter;
+        let mut registers = [0; NUM_REGISTERS];
+        registers.copy_from_slice(&emulator.get_registers()[..NUM_REGISTERS]);
+        let index = emulator.index;
+        let waiting = emulator.waiting_key.is_some();
+        let res = emulator.step(keypad);
+        if waiting {
+            // nothing runs until the key is released, then the next instruction does
+            if emulator.program_counter == pc {
+                return res;
+            }
+            pc += 2;
+        }
+        if res.is_ok() {
+            let opcode = emulator.opcode_at(pc).unwrap_or(0);
+            self.history.push(Entry::new(pc, opcode, &registers, index, emulator));
+        }
+        res
+    }
+
+    /// Same as `Emulator::run_frame`, recording every instruction.
+    pub fn run_frame(&mut self, keypad: &[u8], cycles: u32) -> Result<(), Error> {
+        self.emulator.update_timers();
+        for _ in 0..cycles {
+            self.step(keypad)?;
+        }
+        Ok(())
+    }
+
+    /// Same as `Emulator::tick`, recording the instruction.
+    pub fn tick(&mut self, keypad: &[u8]) -> Result<(), Error> {
+        self.emulator.update_timers();
+        self.step(keypad)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn crash_report(&self, error: Error) -> CrashReport {
+        CrashReport::new(&self.emulator, error, self.history.iter())
+    }
+}
@@ -1,5 +1,10 @@
-use crate::prelude::error::Error;
+use core::fmt;
 
+use crate::prelude::error::{Error, ErrorKind};
+#[cfg(feature = "alloc")]
+use crate::prelude::symbols::Symbols;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Instruction {
     Op0nnn(u16),
     Op00E0,
@@ -88,7 +93,7 @@ impl Instruction {
             (0xF, _, 0x3, 0x3) => Ok(Instruction::OpFx33(x)),
             (0xF, _, 0x5, 0x5) => Ok(Instruction::OpFx55(x)),
             (0xF, _, 0x6, 0x5) => Ok(Instruction::OpFx65(x)),
-            _ => Err(Error::ParseInvalidInstruction(opcode))
+            _ => Err(ErrorKind::ParseInvalidInstruction(opcode).into())
         }
     }
     pub fn to_str(&self) -> &'static str {
@@ -131,3 +136,78 @@ impl Instruction {
         }
     }
 }
+
+// disassembly, using the mnemonics from Cowgod's technical reference
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Instruction::Op0nnn(addr) => write!(f, "SYS 0x{:03X}", addr),
+            Instruction::Op00E0 => write!(f, "CLS"),
+            Instruction::Op00EE => write!(f, "RET"),
+            Instruction::Op1nnn(addr) => write!(f, "JP 0x{:03X}", addr),
+            Instruction::Op2nnn(addr) => write!(f, "CALL 0x{:03X}", addr),
+            Instruction::Op3xkk(x, value) => write!(f, "SE V{:X}, 0x{:02X}", x, value),
+            Instruction::Op4xkk(x, value) => write!(f, "SNE V{:X}, 0x{:02X}", x, value),
+            Instruction::Op5xy0(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::Op6xkk(x, value) => write!(f, "LD V{:X}, 0x{:02X}", x, value),
+            Instruction::Op7xkk(x, value) => write!(f, "ADD V{:X}, 0x{:02X}", x, value),
+            Instruction::Op8xy0(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::Op8xy1(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::Op8xy2(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::Op8xy3(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::Op8xy4(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::Op8xy5(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::Op8xy6(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::Op8xy7(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::Op8xyE(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::Op9xy0(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::OpAnnn(addr) => write!(f, "LD I, 0x{:03X}", addr),
+            Instruction::OpBnnn(addr) => write!(f, "JP V0, 0x{:03X}", addr),
+            Instruction::OpCxkk(x, value) => write!(f, "RND V{:X}, 0x{:02X}", x, value),
+            Instruction::OpDxyn(x, y, n) => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::OpEx9E(x) => write!(f, "SKP V{:X}", x),
+            Instruction::OpExA1(x) => write!(f, "SKNP V{:X}", x),
+            Instruction::OpFx07(x) => write!(f, "LD V{:X}, DT", x),
+            Instruction::OpFx0A(x) => write!(f, "LD V{:X}, K", x),
+            Instruction::OpFx15(x) => write!(f, "LD DT, V{:X}", x),
+            Instruction::OpFx18(x) => write!(f, "LD ST, V{:X}", x),
+            Instruction::OpFx1E(x) => write!(f, "ADD I, V{:X}", x),
+            Instruction::OpFx29(x) => write!(f, "LD F, V{:X}", x),
+            Instruction::OpFx33(x) => write!(f, "LD B, V{:X}", x),
+            Instruction::OpFx55(x) => write!(f, "LD [I], V{:X}", x),
+            Instruction::OpFx65(x) => write!(f, "LD V{:X}, [I]", x),
+        }
+    }
+}
+
+/// Disassembly that shows jump, call and index targets by their label, see `Instruction::with_symbols`.
+#[cfg(feature = "alloc")]
+pub struct Labeled<'a> {
+    instruction: &'a Instruction,
+    symbols: &'a Symbols,
+}
+
+#[cfg(feature = "alloc")]
+impl Instruction {
+    pub fn with_symbols<'a>(&'a self, symbols: &'a Symbols) -> Labeled<'a> {
+        Labeled { instruction: self, symbols }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for Labeled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (mnemonic, addr) = match *self.instruction {
+            Instruction::Op0nnn(addr) => ("SYS ", addr),
+            Instruction::Op1nnn(addr) => ("JP ", addr),
+            Instruction::Op2nnn(addr) => ("CALL ", addr),
+            Instruction::OpAnnn(addr) => ("LD I, ", addr),
+            Instruction::OpBnnn(addr) => ("JP V0, ", addr),
+            _ => return self.instruction.fmt(f),
+        };
+        match self.symbols.label(addr) {
+            Some(label) => write!(f, "{}{}", mnemonic, label),
+            None => self.instruction.fmt(f),
+        }
+    }
+}
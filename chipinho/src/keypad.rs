@@ -0,0 +1,76 @@
+use crate::constants::*;
+
+/// Where key presses come from on hardware: GPIO buttons, a scanned 4x4 matrix, a shift register...
+///
+/// `scan` fills the keypad `tick` and `run_frame` take. Closures reading one pin per key are
+/// inputs already, `MatrixKeypad` covers the usual membrane keypads.
+pub trait KeypadInput {
+    /// Whether `key` (0x0 to 0xF) is held right now.
+    fn is_pressed(&mut self, key: usize) -> bool;
+
+    /// Read all the keys at once.
+    fn scan(&mut self, keypad: &mut [u8; NUM_KEYS]) {
+        for (key, pressed) in keypad.iter_mut().enumerate() {
+            *pressed = self.is_pressed(key) as u8;
+        }
+    }
+}
+
+impl<F: FnMut(usize) -> bool> KeypadInput for F {
+    fn is_pressed(&mut self, key: usize) -> bool {
+        self(key)
+    }
+}
+
+/// The COSMAC VIP keypad, row by row, which 4x4 membrane keypads can be labelled as.
+pub const VIP_LAYOUT: [[usize; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+
+/// A 4x4 key matrix. `read_row` drives one row, reads the columns back and returns them as bits,
+/// bit 0 for the first column; what "driven" and "pressed" mean electrically is up to it.
+pub struct MatrixKeypad<F> {
+    read_row: F,
+    layout: [[usize; 4]; 4],
+}
+
+impl<F: FnMut(usize) -> u8> MatrixKeypad<F> {
+    pub fn new(read_row: F) -> Self {
+        MatrixKeypad {
+            read_row,
+            layout: VIP_LAYOUT,
+        }
+    }
+
+    /// Use another wiring, `layout[row][column]` is the key there.
+    pub fn with_layout(mut self, layout: [[usize; 4]; 4]) -> Self {
+        self.layout = layout;
+        self
+    }
+}
+
+impl<F: FnMut(usize) -> u8> KeypadInput for MatrixKeypad<F> {
+    fn is_pressed(&mut self, key: usize) -> bool {
+        let mut keypad = [0; NUM_KEYS];
+        self.scan(&mut keypad);
+        keypad.get(key).is_some_and(|pressed| *pressed != 0)
+    }
+
+    // one pass over the rows, instead of one per key
+    fn scan(&mut self, keypad: &mut [u8; NUM_KEYS]) {
+        keypad.fill(0);
+        for (row, keys) in self.layout.iter().enumerate() {
+            let columns = (self.read_row)(row);
+            for (column, key) in keys.iter().enumerate() {
+                if columns & (1 << column) != 0 {
+                    if let Some(pressed) = keypad.get_mut(*key) {
+                        *pressed = 1;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,32 @@
-// #![cfg_attr(not(target_family="wasm"), no_std)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 pub mod prelude;
 pub mod emulator;
 pub mod instruction;
 pub mod error;
 pub mod font;
+/// cbindgen:ignore
 pub mod constants;
+pub mod phosphor;
+pub mod framebuffer;
+#[cfg(feature = "embedded-graphics")]
+pub mod graphics;
+pub mod keypad;
+pub mod upscale;
+#[cfg(feature = "alloc")]
+pub mod testing;
+#[cfg(feature = "alloc")]
+pub mod symbols;
+pub mod history;
+#[cfg(feature = "alloc")]
+pub mod crash;
+pub mod random;
+pub mod audio;
+pub mod scheduler;
+#[cfg(feature = "std")]
+pub mod runner;
+#[cfg(feature = "ffi")]
+pub mod ffi;
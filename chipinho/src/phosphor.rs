@@ -0,0 +1,111 @@
+#[cfg(feature = "wasm")]
+use alloc::vec::Vec;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::constants::*;
+
+/// How `Phosphor` turns the raw vram into displayed intensities.
+///
+/// The meaning of `Phosphor::strength` depends on the mode:
+/// * `Off` - ignored, output is the vram itself
+/// * `Decay` - how much of the previous intensity survives each frame (0..=255)
+/// * `Blend` - how many frames are OR'ed together (1..=`PHOSPHOR_MAX_BLEND_FRAMES`)
+/// * `GhostFree` - ignored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(C)]
+pub enum PhosphorMode {
+    Off,
+    Decay,
+    Blend,
+    /// only fills pixels that were off for a single frame in between two lit frames,
+    /// so moving sprites leave no trail. Output lags one frame behind the vram.
+    GhostFree,
+}
+
+/// Anti-flicker post-processor over the emulator vram.
+///
+/// Call `update` once per presented frame with the current vram and draw
+/// `get_intensity`, a grayscale buffer with one byte (0 = off, 255 = fully lit) per pixel.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Phosphor {
+    pub mode: PhosphorMode,
+    pub strength: u8,
+
+    // last 8 frames of each pixel, most recent frame in the lowest bit
+    history: [u8; VRAM_SIZE],
+    intensity: [u8; VRAM_SIZE],
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Phosphor {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(mode: PhosphorMode, strength: u8) -> Self {
+        Phosphor {
+            mode,
+            strength,
+            history: [0; VRAM_SIZE],
+            intensity: [0; VRAM_SIZE],
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.history.iter_mut().for_each(|frames| *frames = 0);
+        self.intensity.iter_mut().for_each(|pixel| *pixel = 0);
+    }
+
+    pub fn update(&mut self, vram: &[u8]) {
+        let mode = self.mode;
+        let strength = self.strength;
+        let blend_mask: u8 = match strength.clamp(1, PHOSPHOR_MAX_BLEND_FRAMES) {
+            PHOSPHOR_MAX_BLEND_FRAMES => 0xFF,
+            frames => (1 << frames) - 1,
+        };
+        self.history
+            .iter_mut()
+            .zip(self.intensity.iter_mut())
+            .zip(vram)
+            .for_each(|((frames, intensity), pixel)| {
+                *frames = (*frames << 1) | (*pixel != 0) as u8;
+                let lit = *frames & 0b1 != 0;
+                *intensity = match mode {
+                    PhosphorMode::Off => lit_to_intensity(lit),
+                    PhosphorMode::Decay if lit => u8::MAX,
+                    PhosphorMode::Decay => ((*intensity as u16 * strength as u16) / u8::MAX as u16) as u8,
+                    PhosphorMode::Blend => lit_to_intensity(*frames & blend_mask != 0),
+                    PhosphorMode::GhostFree => {
+                        lit_to_intensity(*frames & 0b010 != 0 || *frames & 0b101 == 0b101)
+                    }
+                };
+            });
+    }
+
+    /// A copy of the intensities for JS, Rust code borrows them with `get_intensity`.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = get_intensity)]
+    pub fn copy_intensity(&self) -> Vec<u8> {
+        self.intensity.to_vec()
+    }
+}
+
+impl Phosphor {
+    pub fn get_intensity(&self) -> &[u8] {
+        &self.intensity
+    }
+}
+
+impl Default for Phosphor {
+    fn default() -> Self {
+        Phosphor::new(PhosphorMode::Decay, PHOSPHOR_DEFAULT_DECAY)
+    }
+}
+
+fn lit_to_intensity(lit: bool) -> u8 {
+    if lit {
+        u8::MAX
+    } else {
+        0
+    }
+}
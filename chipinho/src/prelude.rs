@@ -3,3 +3,23 @@ pub use crate::font;
 pub use crate::emulator;
 pub use crate::constants;
 pub use crate::instruction;
+pub use crate::phosphor;
+pub use crate::framebuffer;
+#[cfg(feature = "embedded-graphics")]
+pub use crate::graphics;
+pub use crate::keypad;
+pub use crate::upscale;
+#[cfg(feature = "alloc")]
+pub use crate::testing;
+#[cfg(feature = "alloc")]
+pub use crate::symbols;
+pub use crate::history;
+#[cfg(feature = "alloc")]
+pub use crate::crash;
+pub use crate::random;
+pub use crate::audio;
+pub use crate::scheduler;
+#[cfg(feature = "std")]
+pub use crate::runner;
+#[cfg(feature = "ffi")]
+pub use crate::ffi;
@@ -0,0 +1,263 @@
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use core::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::constants::*;
+
+/// Source of the random bytes used by `CxKK`.
+pub trait Rng {
+    /// Restart the sequence from `seed`, the same seed always gives the same bytes.
+    fn seed(&mut self, seed: u64);
+
+    /// The next random byte. `memory` is the emulator memory, for generators that read from it.
+    fn next_u8(&mut self, memory: &[u8]) -> u8;
+
+    /// Called once per 60Hz frame, along with the timers.
+    fn frame(&mut self) {}
+}
+
+/// PCG32 (XSH RR), the default: a long period and no visible bias.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pcg32 {
+    state: u64,
+}
+
+impl Pcg32 {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Pcg32 { state: 0 };
+        rng.seed(seed);
+        rng
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state.wrapping_mul(PCG_MULTIPLIER).wrapping_add(PCG_INCREMENT);
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        xorshifted.rotate_right((state >> 59) as u32)
+    }
+}
+
+impl Rng for Pcg32 {
+    fn seed(&mut self, seed: u64) {
+        self.state = 0;
+        self.next_u32();
+        self.state = self.state.wrapping_add(seed);
+        self.next_u32();
+    }
+
+    fn next_u8(&mut self, _memory: &[u8]) -> u8 {
+        (self.next_u32() >> 24) as u8
+    }
+}
+
+/// The 8 bit LCG `chipinho` used before generators could be picked, kept so older recordings
+/// replay the same. Its period is short and it never goes above `RANDOM_MODULE - 1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LegacyLcg {
+    last: u8,
+}
+
+impl LegacyLcg {
+    pub fn new(seed: u64) -> Self {
+        LegacyLcg { last: seed as u8 }
+    }
+}
+
+impl Rng for LegacyLcg {
+    fn seed(&mut self, seed: u64) {
+        self.last = seed as u8;
+    }
+
+    fn next_u8(&mut self, _memory: &[u8]) -> u8 {
+        self.last = (RANDOM_MULTIPLIER.wrapping_mul(self.last).wrapping_add(RANDOM_INCREMENT)) % RANDOM_MODULE;
+        self.last
+    }
+}
+
+/// The page at 0x100 of the COSMAC VIP CHIP-8 interpreter, which its `CxKK` routine reads as a table
+/// of random-looking bytes. The interpreter is not in the emulator memory, so it is kept here.
+pub const VIP_INTERPRETER_PAGE: [u8; 256] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x45, 0xA3, 0x98, 0x56, 0xD4, 0xF8, 0x81, 0xBC, 0xF8, 0x95, 0xAC,
+    0x22, 0xDC, 0x12, 0x56, 0xD4, 0x06, 0xB8, 0xD4, 0x06, 0xA8, 0xD4, 0x64, 0x0A, 0x01, 0xE6, 0x8A,
+    0xF4, 0xAA, 0x3B, 0x28, 0x9A, 0xFC, 0x01, 0xBA, 0xD4, 0xF8, 0x81, 0xBA, 0x06, 0xFA, 0x0F, 0xAA,
+    0x0A, 0xAA, 0xD4, 0xE6, 0x06, 0xBF, 0x93, 0xBE, 0xF8, 0x1B, 0xAE, 0x2A, 0x1A, 0xF8, 0x00, 0x5A,
+    0x0E, 0xF5, 0x3B, 0x4B, 0x56, 0x0A, 0xFC, 0x01, 0x5A, 0x30, 0x40, 0x4E, 0xF6, 0x3B, 0x3C, 0x9F,
+    0x56, 0x2A, 0x2A, 0xD4, 0x00, 0x22, 0x86, 0x52, 0xF8, 0xF0, 0xA7, 0x07, 0x5A, 0x87, 0xF3, 0x17,
+    0x1A, 0x3A, 0x5B, 0x12, 0xD4, 0x22, 0x86, 0x52, 0xF8, 0xF0, 0xA7, 0x0A, 0x57, 0x87, 0xF3, 0x17,
+    0x1A, 0x3A, 0x6B, 0x12, 0xD4, 0x15, 0x85, 0x22, 0x73, 0x95, 0x52, 0x25, 0x45, 0xA5, 0x86, 0xFA,
+    0x0F, 0xB5, 0xD4, 0x45, 0xE6, 0xF3, 0x3A, 0x82, 0x15, 0x15, 0xD4, 0x45, 0xE6, 0xF3, 0x3A, 0x88,
+    0xD4, 0x45, 0x07, 0x30, 0x8C, 0x45, 0x07, 0x30, 0x84, 0xE6, 0x62, 0x26, 0x45, 0xA3, 0x36, 0x88,
+    0xD4, 0x3E, 0x88, 0xD4, 0xF8, 0xF0, 0xA7, 0xE7, 0x45, 0xF4, 0xA5, 0x86, 0xFA, 0x0F, 0x3B, 0xB2,
+    0xFC, 0x01, 0xB5, 0xD4, 0x45, 0x56, 0xD4, 0x45, 0xE6, 0xF4, 0x56, 0xD4, 0x45, 0xFA, 0x0F, 0x3A,
+    0xC4, 0x07, 0x56, 0xD4, 0xAF, 0x22, 0xF8, 0xD3, 0x73, 0x8F, 0xF9, 0xF0, 0x52, 0xE6, 0x07, 0xD2,
+    0x56, 0xF8, 0xFF, 0xA6, 0xF8, 0x00, 0x7E, 0x56, 0xD4, 0x19, 0x89, 0xAE, 0x93, 0xBE, 0x99, 0xEE,
+    0xF4, 0x56, 0x76, 0xE6, 0xF4, 0xB9, 0x56, 0x45, 0xF2, 0x56, 0xD4, 0x45, 0xAA, 0x86, 0xFA, 0x0F,
+    0xBA, 0xD4, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xE0, 0x00, 0x4B,
+];
+
+/// The COSMAC VIP interpreter routine (at 0x1D9 in the page above), on its R9 register: each call
+/// increments R9, adds the byte at `0x100 + R9.0` to R9.1 and folds the sum into itself, which becomes
+/// both the random byte and the new R9.1. The 60Hz interrupt increments R9 too, so values also depend
+/// on timing, like on the VIP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VipRandom {
+    r9: u16,
+}
+
+impl VipRandom {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = VipRandom { r9: 0 };
+        rng.seed(seed);
+        rng
+    }
+}
+
+impl Rng for VipRandom {
+    /// The seed is the starting R9.
+    fn seed(&mut self, seed: u64) {
+        self.r9 = seed as u16;
+    }
+
+    fn next_u8(&mut self, _memory: &[u8]) -> u8 {
+        self.r9 = self.r9.wrapping_add(1);
+        let [high, low] = self.r9.to_be_bytes();
+        // ADD, then SHRC brings the carry in from the top and ADD folds the sum back in
+        let (sum, carry) = high.overflowing_add(VIP_INTERPRETER_PAGE[low as usize]);
+        let value = ((carry as u8) << 7 | sum >> 1).wrapping_add(sum);
+        self.r9 = u16::from_be_bytes([value, low]);
+        value
+    }
+
+    fn frame(&mut self) {
+        self.r9 = self.r9.wrapping_add(1);
+    }
+}
+
+/// A generator of your own, installed with `RandomGenerator::custom`. Every `Rng` that is
+/// `Clone + Send + Sync` is one.
+#[cfg(feature = "alloc")]
+pub trait CustomRng: Rng + Send + Sync {
+    fn clone_box(&self) -> Box<dyn CustomRng>;
+}
+
+#[cfg(feature = "alloc")]
+impl<R: Rng + Clone + Send + Sync + 'static> CustomRng for R {
+    fn clone_box(&self) -> Box<dyn CustomRng> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Clone for Box<dyn CustomRng> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Debug for dyn CustomRng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CustomRng")
+    }
+}
+
+/// The built-in generators, for picking one without the Rust types (from C and wasm).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(C)]
+pub enum RandomKind {
+    #[default]
+    Pcg32,
+    Legacy,
+    Vip,
+}
+
+/// The generator held by an `Emulator`.
+#[derive(Clone, Debug)]
+pub enum RandomGenerator {
+    Pcg32(Pcg32),
+    Legacy(LegacyLcg),
+    Vip(VipRandom),
+    /// Any other `Rng`. Saved states only record that one was in use, see `Emulator::load_state`.
+    #[cfg(feature = "alloc")]
+    Custom(Box<dyn CustomRng>),
+}
+
+impl RandomGenerator {
+    pub fn new(kind: RandomKind, seed: u64) -> Self {
+        match kind {
+            RandomKind::Pcg32 => RandomGenerator::Pcg32(Pcg32::new(seed)),
+            RandomKind::Legacy => RandomGenerator::Legacy(LegacyLcg::new(seed)),
+            RandomKind::Vip => RandomGenerator::Vip(VipRandom::new(seed)),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn custom<R: Rng + Clone + Send + Sync + 'static>(rng: R) -> Self {
+        RandomGenerator::Custom(Box::new(rng))
+    }
+
+    /// Which built-in generator this is, `None` for a custom one.
+    pub fn kind(&self) -> Option<RandomKind> {
+        match self {
+            RandomGenerator::Pcg32(_) => Some(RandomKind::Pcg32),
+            RandomGenerator::Legacy(_) => Some(RandomKind::Legacy),
+            RandomGenerator::Vip(_) => Some(RandomKind::Vip),
+            #[cfg(feature = "alloc")]
+            RandomGenerator::Custom(_) => None,
+        }
+    }
+
+    /// Everything a built-in generator holds, `from_state` gives back the same generator. Used by saved
+    /// states, custom generators have none.
+    pub fn state(&self) -> u64 {
+        match self {
+            RandomGenerator::Pcg32(rng) => rng.state,
+            RandomGenerator::Legacy(rng) => rng.last as u64,
+            RandomGenerator::Vip(rng) => rng.r9 as u64,
+            #[cfg(feature = "alloc")]
+            RandomGenerator::Custom(_) => 0,
+        }
+    }
+
+    pub fn from_state(kind: RandomKind, state: u64) -> Self {
+        match kind {
+            RandomKind::Pcg32 => RandomGenerator::Pcg32(Pcg32 { state }),
+            // seeding these two stores the seed as is
+            RandomKind::Legacy | RandomKind::Vip => RandomGenerator::new(kind, state),
+        }
+    }
+
+    fn rng(&mut self) -> &mut dyn Rng {
+        match self {
+            RandomGenerator::Pcg32(rng) => rng,
+            RandomGenerator::Legacy(rng) => rng,
+            RandomGenerator::Vip(rng) => rng,
+            #[cfg(feature = "alloc")]
+            RandomGenerator::Custom(rng) => rng.as_mut(),
+        }
+    }
+}
+
+impl Default for RandomGenerator {
+    fn default() -> Self {
+        RandomGenerator::new(RandomKind::default(), DEFAULT_RANDOM_SEED)
+    }
+}
+
+impl Rng for RandomGenerator {
+    fn seed(&mut self, seed: u64) {
+        self.rng().seed(seed)
+    }
+
+    fn next_u8(&mut self, memory: &[u8]) -> u8 {
+        self.rng().next_u8(memory)
+    }
+
+    fn frame(&mut self) {
+        self.rng().frame()
+    }
+}
@@ -0,0 +1,193 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::{audio::Beeper, constants::*, emulator::Emulator, error::Error, scheduler::*};
+
+/// What the frontend asks of the emulator thread.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Start `rom` over from a fresh emulator and run it. On error the previous program carries on.
+    Load(Vec<u8>),
+    KeyDown(usize),
+    KeyUp(usize),
+    Pause,
+    Resume,
+    /// Run one more frame, paused or not.
+    Step,
+    /// Answered with `Event::State`.
+    SaveState,
+    LoadState(Vec<u8>),
+    /// In percent of real time, see `Scheduler::set_speed`.
+    SetSpeed(u32),
+}
+
+/// What the emulator thread publishes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The vram, after each update that ran frames and after loading a program or a state.
+    Frame(Vec<u8>),
+    /// `samples_per_frame` samples of the beeper for every frame run, silent ones included,
+    /// ready to queue on an audio device. Only sent with a `Beeper`.
+    Audio(Vec<i16>),
+    /// A failed load, or a fault in the program, which pauses it.
+    Error(Error),
+    State(Vec<u8>),
+}
+
+/// Owns an emulator on a background thread, so GUI frontends never block on emulation: send it
+/// `Command`s and drain `events` each time around the UI loop.
+///
+/// The thread sleeps until a command comes in or the scheduler has a frame due, and stops when the
+/// `Runner` is dropped. Nothing runs until a program is loaded.
+pub struct Runner {
+    commands: Option<Sender<Command>>,
+    events: Receiver<Event>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Runner {
+    /// Start the thread, timed by `scheduler`, with `beeper` making the audio chunks if there is one.
+    pub fn spawn<C>(scheduler: Scheduler<C>, beeper: Option<Beeper>) -> Self
+    where
+        C: Clock + Send + 'static,
+    {
+        let (commands, command_receiver) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+        let mut worker = Worker {
+            emulator: Emulator::new(),
+            keypad: [0; NUM_KEYS],
+            scheduler,
+            beeper,
+            events: event_sender,
+            redraw: false,
+        };
+        worker.scheduler.pause();
+        let thread = thread::spawn(move || worker.run(command_receiver));
+        Runner {
+            commands: Some(commands),
+            events,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn send(&self, command: Command) {
+        if let Some(commands) = &self.commands {
+            // the thread only stops once the runner is dropped
+            let _ = commands.send(command);
+        }
+    }
+
+    pub fn events(&self) -> &Receiver<Event> {
+        &self.events
+    }
+}
+
+impl Drop for Runner {
+    fn drop(&mut self) {
+        // hanging up wakes the thread, which then stops
+        self.commands = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct Worker<C> {
+    emulator: Emulator,
+    keypad: [u8; NUM_KEYS],
+    scheduler: Scheduler<C>,
+    beeper: Option<Beeper>,
+    events: Sender<Event>,
+    // a load changed the screen, sent with the frames of the following update
+    redraw: bool,
+}
+
+impl<C: Clock> Worker<C> {
+    fn run(&mut self, commands: Receiver<Command>) {
+        loop {
+            let command = match self.scheduler.micros_until_next_frame() {
+                Some(micros) => commands.recv_timeout(Duration::from_micros(micros)),
+                None => commands.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match command {
+                Ok(command) => self.handle(command),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            // everything sent meanwhile applies before the frames run
+            while let Ok(command) = commands.try_recv() {
+                self.handle(command);
+            }
+            self.update();
+        }
+    }
+
+    fn handle(&mut self, command: Command) {
+        match command {
+            Command::Load(rom) => {
+                let mut emulator = Emulator::new();
+                match emulator.load_program(&rom) {
+                    Ok(()) => {
+                        self.emulator = emulator;
+                        self.scheduler.reset();
+                        self.scheduler.resume();
+                        self.redraw = true;
+                    }
+                    Err(err) => self.send(Event::Error(err)),
+                }
+            }
+            Command::KeyDown(key) | Command::KeyUp(key) => {
+                if let Some(pressed) = self.keypad.get_mut(key) {
+                    *pressed = matches!(command, Command::KeyDown(_)) as u8;
+                }
+            }
+            Command::Pause => self.scheduler.pause(),
+            Command::Resume => self.scheduler.resume(),
+            Command::Step => self.scheduler.advance_frame(),
+            Command::SaveState => {
+                let mut state = vec![0; STATE_SIZE];
+                match self.emulator.save_state(&mut state) {
+                    Ok(()) => self.send(Event::State(state)),
+                    Err(err) => self.send(Event::Error(err)),
+                }
+            }
+            Command::LoadState(state) => match self.emulator.load_state(&state) {
+                Ok(()) => self.redraw = true,
+                Err(err) => self.send(Event::Error(err)),
+            },
+            Command::SetSpeed(percent) => self.scheduler.set_speed(percent),
+        }
+    }
+
+    fn update(&mut self) {
+        let frames = self.scheduler.frames_due();
+        let redraw = core::mem::take(&mut self.redraw) || frames.should_draw();
+        for _ in 0..frames.ran {
+            let result = self.emulator.run_frame(&self.keypad, self.scheduler.cycles_per_frame);
+            if let Some(beeper) = &mut self.beeper {
+                let mut samples = vec![0; beeper.samples_per_frame()];
+                beeper.fill(self.emulator.should_beep(), &mut samples);
+                self.send(Event::Audio(samples));
+            }
+            if let Err(err) = result {
+                self.scheduler.pause();
+                self.send_frame();
+                self.send(Event::Error(err));
+                return;
+            }
+        }
+        if redraw {
+            self.send_frame();
+        }
+    }
+
+    fn send_frame(&self) {
+        self.send(Event::Frame(self.emulator.get_vram().to_vec()));
+    }
+
+    fn send(&self, event: Event) {
+        // nobody listening is not an error, the runner may be on its way out
+        let _ = self.events.send(event);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{constants::*, emulator::Emulator, error::Error};
+
+// the accumulator counts microseconds times `TIMER_FREQUENCY`, so a frame is exactly this many units
+const FRAME_UNITS: i64 = 1_000_000;
+// how early a frame may run, in the same units
+const JITTER_UNITS: i64 = SCHEDULER_JITTER_MICROS as i64 * TIMER_FREQUENCY as i64;
+
+/// Where the scheduler gets the time from, in microseconds since any fixed point. Time going backwards
+/// counts as no time passing.
+///
+/// Closures returning the time are clocks, `ManualClock` is set by hand (tests, or timestamps handed
+/// over by the host like `requestAnimationFrame`'s) and `SystemClock` reads `std::time::Instant`.
+pub trait Clock {
+    fn now_micros(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> Clock for F {
+    fn now_micros(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// A clock that only moves when told to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ManualClock {
+    pub micros: u64,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        ManualClock::default()
+    }
+
+    pub fn set(&mut self, micros: u64) {
+        self.micros = micros;
+    }
+
+    pub fn advance(&mut self, micros: u64) {
+        self.micros += micros;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_micros(&mut self) -> u64 {
+        self.micros
+    }
+}
+
+/// The monotonic clock of the host.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock {
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock {
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now_micros(&mut self) -> u64 {
+        self.start.elapsed().as_micros() as u64
+    }
+}
+
+/// What an `update` did.
+///
+/// When more than one frame ran, the ones before the last were never shown: that is the frame skipping
+/// that lets a slow host keep the game at full speed. Frames past the catch-up limit are `dropped`
+/// instead, so the game slows down rather than fast forwarding after a stall.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Frames {
+    pub ran: u32,
+    pub dropped: u32,
+}
+
+impl Frames {
+    /// Whether the screen may have changed since the last update.
+    pub fn should_draw(&self) -> bool {
+        self.ran > 0
+    }
+}
+
+/// Runs an emulator in real time: each `update` reads the clock and runs the 60Hz frames
+/// (`cycles_per_frame` instructions and a timer tick each) that are due since the last one.
+///
+/// `speed` scales time, in percent: 200 is turbo, 50 slow motion. While paused no time passes,
+/// and `advance_frame` queues single frames to step through.
+#[derive(Clone, Copy, Debug)]
+pub struct Scheduler<C> {
+    pub clock: C,
+    pub cycles_per_frame: u32,
+    /// most frames run by one `update`
+    pub max_catch_up_frames: u32,
+
+    speed: u32,
+    paused: bool,
+    last: Option<u64>,
+    // negative after a frame ran a little early, which the next frames make up for
+    accumulator: i64,
+    queued_frames: u32,
+}
+
+impl<C: Clock> Scheduler<C> {
+    pub fn new(clock: C) -> Self {
+        Scheduler {
+            clock,
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            max_catch_up_frames: SCHEDULER_MAX_CATCH_UP_FRAMES,
+            speed: 100,
+            paused: false,
+            last: None,
+            accumulator: 0,
+            queued_frames: 0,
+        }
+    }
+
+    pub fn with_cycles_per_frame(mut self, cycles: u32) -> Self {
+        self.cycles_per_frame = cycles;
+        self
+    }
+
+    pub fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    /// Run at `percent` of real time, 0 stops time like a pause.
+    pub fn set_speed(&mut self, percent: u32) {
+        self.speed = percent;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.last = None;
+        self.accumulator = 0;
+    }
+
+    /// Carry on from now, the time spent paused is not caught up on.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Run one more frame on the next `update`, paused or not.
+    pub fn advance_frame(&mut self) {
+        self.queued_frames += 1;
+    }
+
+    /// Forget the time since the last update, e.g. after loading a program.
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.accumulator = 0;
+        self.queued_frames = 0;
+    }
+
+    /// Run the frames due now. The first update after creating, resuming or resetting only
+    /// starts the clock. Stops at the first error, with the frames before it run.
+    pub fn update(&mut self, emulator: &mut Emulator, keypad: &[u8]) -> Result<Frames, Error> {
+        let frames = self.frames_due();
+        for _ in 0..frames.ran {
+            emulator.run_frame(keypad, self.cycles_per_frame)?;
+        }
+        Ok(frames)
+    }
+
+    /// Like `update` but without an emulator, for hosts that run the frames themselves (e.g. through
+    /// a `history::Recorder`): `ran` is how many frames of `cycles_per_frame` instructions to run now.
+    pub fn frames_due(&mut self) -> Frames {
+        let mut due = core::mem::take(&mut self.queued_frames);
+        let mut dropped = 0;
+        if !self.paused {
+            let now = self.clock.now_micros();
+            if let Some(last) = self.last.replace(now) {
+                let elapsed = self.units(now.saturating_sub(last));
+                self.accumulator = self.accumulator.saturating_add(elapsed);
+            }
+            // hosts wake up a little early or late, frames due that close are run now instead of next time,
+            // borrowing the time they were early by from the next ones so the rate stays at 60Hz
+            let frames = self.accumulator.saturating_add(JITTER_UNITS) / FRAME_UNITS;
+            self.accumulator -= frames * FRAME_UNITS;
+            let limit = self.max_catch_up_frames.max(1) as i64;
+            if frames > limit {
+                dropped = u32::try_from(frames - limit).unwrap_or(u32::MAX);
+                self.accumulator = 0;
+            }
+            due = due.saturating_add(frames.min(limit) as u32);
+        }
+        Frames { ran: due, dropped }
+    }
+
+    /// How long from now until the next frame is due, for hosts that sleep in between. `None` while paused.
+    pub fn micros_until_next_frame(&mut self) -> Option<u64> {
+        if self.paused || self.speed == 0 {
+            return None;
+        }
+        let elapsed = match self.last {
+            Some(last) => {
+                let now = self.clock.now_micros();
+                self.units(now.saturating_sub(last))
+            }
+            None => 0,
+        };
+        let missing = (FRAME_UNITS - self.accumulator.saturating_add(elapsed)).max(0) as u64;
+        Some((missing * 100).div_ceil(TIMER_FREQUENCY as u64 * self.speed as u64))
+    }
+
+    // `elapsed` microseconds scaled by the speed, in accumulator units. Saturates instead of overflowing,
+    // whatever the clock and the speed
+    fn units(&self, elapsed: u64) -> i64 {
+        let units = elapsed as u128 * TIMER_FREQUENCY as u128 * self.speed as u128 / 100;
+        i64::try_from(units).unwrap_or(i64::MAX)
+    }
+}
@@ -0,0 +1,160 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, ErrorKind};
+
+/// Labels for program addresses, so tools can print `0x2A4 <draw_player+2>` instead of
+/// a bare `0x2A4`.
+///
+/// Symbol files have one label per line, either as `name = 0x2A4` or Octo style as
+/// `name 0x2A4`. Addresses can also be decimal, and `#` starts a comment.
+#[derive(Clone, Debug, Default)]
+pub struct Symbols {
+    labels: BTreeMap<u16, String>,
+}
+
+/// An address followed by the label it falls under, if any.
+pub struct Location<'a> {
+    symbols: &'a Symbols,
+    addr: u16,
+}
+
+/// Why a symbol file couldn't be loaded, shown as `<path>: <reason>`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum LoadError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, Error),
+}
+
+fn parse_address(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+impl Symbols {
+    pub fn new() -> Self {
+        Symbols::default()
+    }
+
+    /// Parse a symbol file, failing with `ErrorKind::InvalidSymbolFile` and the line number
+    /// of the first invalid entry.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut symbols = Symbols::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, addr) = match line.split_once('=') {
+                Some((name, addr)) => (name.trim(), addr.trim()),
+                None => line.split_once(char::is_whitespace).unwrap_or((line, "")),
+            };
+            let invalid = Error::new(ErrorKind::InvalidSymbolFile((number + 1) as u16));
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(invalid);
+            }
+            symbols.insert(name, parse_address(addr.trim()).ok_or(invalid)?);
+        }
+        Ok(symbols)
+    }
+
+    /// Read and parse the symbol file at `path`.
+    #[cfg(feature = "std")]
+    pub fn load(path: &Path) -> Result<Self, LoadError> {
+        let text = std::fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+        Symbols::parse(&text).map_err(|e| LoadError::Parse(path.to_path_buf(), e))
+    }
+
+    /// The symbols for the ROM at `rom`: the file at `explicit` if given, otherwise the ROM path with a
+    /// `.sym` extension when it exists, and no symbols when it doesn't.
+    #[cfg(feature = "std")]
+    pub fn load_for_rom(rom: &Path, explicit: Option<&Path>) -> Result<Self, LoadError> {
+        match explicit {
+            Some(path) => Symbols::load(path),
+            None if rom.with_extension("sym").exists() => Symbols::load(&rom.with_extension("sym")),
+            None => Ok(Symbols::new()),
+        }
+    }
+
+    /// Add a label, an address keeps the first label it was given.
+    pub fn insert(&mut self, name: &str, addr: u16) {
+        self.labels.entry(addr).or_insert_with(|| String::from(name));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// The label placed exactly at `addr`.
+    pub fn label(&self, addr: u16) -> Option<&str> {
+        self.labels.get(&addr).map(String::as_str)
+    }
+
+    pub fn address(&self, name: &str) -> Option<u16> {
+        self.labels
+            .iter()
+            .find(|(_, label)| label.as_str() == name)
+            .map(|(addr, _)| *addr)
+    }
+
+    /// The closest label at or before `addr`, with the offset from it.
+    pub fn lookup(&self, addr: u16) -> Option<(&str, u16)> {
+        self.labels
+            .range(..=addr)
+            .next_back()
+            .map(|(label_addr, label)| (label.as_str(), addr - label_addr))
+    }
+
+    pub fn location(&self, addr: u16) -> Location<'_> {
+        Location { symbols: self, addr }
+    }
+
+    /// Like the `Display` output of the error, with addresses shown as locations.
+    pub fn describe_error(&self, error: &Error) -> String {
+        let mut description = match error.kind {
+            ErrorKind::OutOfBoundsMemoryAccess(addr) => {
+                format!("out of bounds memory access to {}", self.location(addr))
+            }
+            kind => kind.to_string(),
+        };
+        if let Some(pc) = error.pc {
+            description += &format!(" at {}", self.location(pc));
+        }
+        match (error.kind, error.opcode) {
+            (ErrorKind::ParseInvalidInstruction(_), _) | (_, None) => {}
+            (_, Some(opcode)) => description += &format!(" (opcode {:04X})", opcode),
+        }
+        description
+    }
+}
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:03X}", self.addr)?;
+        match self.symbols.lookup(self.addr) {
+            Some((label, 0)) => write!(f, " <{}>", label),
+            Some((label, offset)) => write!(f, " <{}+{}>", label, offset),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            LoadError::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LoadError {}
@@ -0,0 +1,133 @@
+//! Helpers to write tests against the screen using ascii art, where `#` is a lit
+//! pixel and `.` is an unlit one.
+//!
+//! ```
+//! use chipinho::{assert_screen, emulator::Emulator};
+//!
+//! let mut emulator = Emulator::new();
+//! // draw the font sprite for "0" at (1, 0)
+//! emulator.load_program(&[0x60, 0x01, 0xA0, 0x00, 0xD0, 0x15]).unwrap();
+//! for _ in 0..3 {
+//!     emulator.tick(&[0; 16]).unwrap();
+//! }
+//! assert_screen!(emulator, "
+//!     .####
+//!     .#..#
+//!     .#..#
+//!     .#..#
+//!     .####
+//! ");
+//! ```
+//!
+//! Other test helpers live in submodules: [`programs`] has ROMs for frontend tests and [`fuzz`]
+//! the body of the fuzz target.
+
+pub mod fuzz;
+pub mod programs;
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::{
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+    emulator::Emulator,
+};
+
+// how many mismatching pixels are listed before giving up
+const MAX_LISTED_MISMATCHES: usize = 16;
+
+pub fn screen_to_ascii(emulator: &Emulator) -> String {
+    vram_to_ascii(emulator.get_vram())
+}
+
+pub fn vram_to_ascii(vram: &[u8]) -> String {
+    vram.chunks(DISPLAY_WIDTH as usize)
+        .take(DISPLAY_HEIGHT as usize)
+        .map(|row| row.iter().map(|pixel| if *pixel != 0 { '#' } else { '.' }).collect::<String>() + "\n")
+        .collect()
+}
+
+/// Compare the vram against an ascii art block. Lines of `expected` are trimmed and
+/// blank ones are skipped, so the block can be indented along with the code.
+///
+/// With `origin`, only the area covered by the block starting at (x, y) is compared.
+/// Without it, the block is compared against the top left corner and every
+/// pixel outside of it must be unlit.
+///
+/// On mismatch, returns a description listing the wrong pixels by coordinate
+/// followed by the compared area, where `+` is a pixel lit but expected off and
+/// `-` a pixel off but expected lit.
+pub fn compare_screen(vram: &[u8], origin: Option<(usize, usize)>, expected: &str) -> Result<(), String> {
+    let rows: Vec<&str> = expected.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    if let Some(invalid) = rows.iter().flat_map(|row| row.chars()).find(|c| *c != '#' && *c != '.') {
+        return Err(format!("invalid character {:?} in expected screen, use '#' and '.'", invalid));
+    }
+    let (x0, y0) = origin.unwrap_or((0, 0));
+    let (width, height) = (rows.iter().map(|row| row.len()).max().unwrap_or(0), rows.len());
+    if x0 + width > DISPLAY_WIDTH as usize || y0 + height > DISPLAY_HEIGHT as usize {
+        return Err(format!(
+            "expected screen of {}x{} at ({}, {}) does not fit in the {}x{} display",
+            width, height, x0, y0, DISPLAY_WIDTH, DISPLAY_HEIGHT
+        ));
+    }
+    // the whole screen is compared without an origin
+    let (width, height) = match origin {
+        Some(_) => (width, height),
+        None => (DISPLAY_WIDTH as usize, DISPLAY_HEIGHT as usize),
+    };
+
+    let mut mismatches = Vec::new();
+    let mut area = String::new();
+    for y in y0..y0 + height {
+        let expected_row = rows.get(y - y0).map(|row| row.as_bytes()).unwrap_or(&[]);
+        for x in x0..x0 + width {
+            let lit = vram.get(y * DISPLAY_WIDTH as usize + x).is_some_and(|pixel| *pixel != 0);
+            let expected_lit = expected_row.get(x - x0) == Some(&b'#');
+            area.push(match (expected_lit, lit) {
+                (false, true) => '+',
+                (true, false) => '-',
+                (_, true) => '#',
+                (_, false) => '.',
+            });
+            if lit != expected_lit {
+                mismatches.push((x, y, expected_lit));
+            }
+        }
+        area.push('\n');
+    }
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+
+    let mut report = format!("{} pixels differ from the expected screen:\n", mismatches.len());
+    for (x, y, expected_lit) in mismatches.iter().take(MAX_LISTED_MISMATCHES) {
+        let (expected, found) = if *expected_lit { ("lit", "off") } else { ("off", "lit") };
+        writeln!(report, "  ({}, {}): expected {}, found {}", x, y, expected, found).unwrap();
+    }
+    if mismatches.len() > MAX_LISTED_MISMATCHES {
+        writeln!(report, "  ... and {} more", mismatches.len() - MAX_LISTED_MISMATCHES).unwrap();
+    }
+    writeln!(report, "compared area from ({}, {}) ('+' lit but expected off, '-' off but expected lit):", x0, y0).unwrap();
+    report.push_str(&area);
+    Err(report)
+}
+
+/// Assert the screen of an `Emulator` matches an ascii art block, see `compare_screen`.
+///
+/// `assert_screen!(emulator, "...")` checks the whole screen, while
+/// `assert_screen!(emulator, (x, y), "...")` only checks the area under the block.
+#[macro_export]
+macro_rules! assert_screen {
+    ($emulator:expr, ($x:expr, $y:expr), $expected:expr $(,)?) => {
+        if let Err(report) = $crate::testing::compare_screen($emulator.get_vram(), Some(($x, $y)), $expected) {
+            panic!("assertion failed: screen does not match\n{}", report);
+        }
+    };
+    ($emulator:expr, $expected:expr $(,)?) => {
+        if let Err(report) = $crate::testing::compare_screen($emulator.get_vram(), None, $expected) {
+            panic!("assertion failed: screen does not match\n{}", report);
+        }
+    };
+}
@@ -0,0 +1,32 @@
+//! The fuzz target body, shared with the robustness tests so they replay the same inputs.
+
+use crate::{
+    emulator::{Emulator, MemoryPolicy},
+    error::Error,
+};
+
+/// Feed arbitrary bytes to the emulator for at most `ticks` ticks, as the fuzzer does. The
+/// low 5 bits of the first byte are the keypad length and the top ones pick the memory policy,
+/// the following bytes fill the keypad and the rest is the program. The keypad is rotated
+/// every tick so keys get pressed and released.
+///
+/// Errors are fine and returned, the emulator must only never panic.
+pub fn exercise(data: &[u8], ticks: usize) -> Result<(), Error> {
+    let (setup, data) = data.split_first().map_or((0, data), |(setup, rest)| (*setup, rest));
+    let (keypad, program) = data.split_at(((setup & 0x1F) as usize).min(data.len()));
+    let mut keypad = keypad.to_vec();
+    let mut emulator = Emulator::new();
+    emulator.set_memory_policy(match setup >> 5 {
+        0..=2 => MemoryPolicy::Strict,
+        3..=5 => MemoryPolicy::Wrap,
+        _ => MemoryPolicy::Clamp,
+    });
+    emulator.load_program(program)?;
+    for _ in 0..ticks {
+        emulator.tick(&keypad)?;
+        if !keypad.is_empty() {
+            keypad.rotate_left(1);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,18 @@
+//! Small programs shared by the tests of the frontends and bindings.
+
+/// A program for frontend tests: it draws one 4 pixel wide sprite row at (8, 8), then keeps the
+/// sound timer going while key 5 is held.
+///
+/// ```text
+/// 0x200: LD V0, 8
+/// 0x202: LD I, 0x20E
+/// 0x204: DRW V0, V0, 1
+/// 0x206: LD V1, 5
+/// 0x208: SKNP V1
+/// 0x20A: LD ST, V0
+/// 0x20C: JP 0x208
+/// 0x20E: sprite
+/// ```
+pub const BEEPER_PROGRAM: [u8; 16] = [
+    0x60, 0x08, 0xA2, 0x0E, 0xD0, 0x01, 0x61, 0x05, 0xE1, 0xA1, 0xF0, 0x18, 0x12, 0x08, 0xF0, 0x00,
+];
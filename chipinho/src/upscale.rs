@@ -0,0 +1,211 @@
+use core::fmt;
+
+/// A buffer holding fewer pixels than the filter reads or writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferTooSmall {
+    pub needed: usize,
+    pub len: usize,
+}
+
+impl fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "buffer of {} pixels is too small, {} needed", self.len, self.needed)
+    }
+}
+
+impl core::error::Error for BufferTooSmall {}
+
+/// Pixel-art upscaling filters for framebuffers such as the emulator vram
+/// (`DISPLAY_WIDTH` x `DISPLAY_HEIGHT`, one byte per pixel).
+///
+/// Filters only compare pixels for equality, so they work both on the monochrome vram
+/// and on grayscale buffers like `Phosphor::get_intensity`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub enum Filter {
+    Scale2x,
+    Scale3x,
+    Epx,
+}
+
+impl Filter {
+    pub fn factor(&self) -> usize {
+        match *self {
+            Filter::Scale2x => 2,
+            Filter::Scale3x => 3,
+            Filter::Epx => 2,
+        }
+    }
+}
+
+/// Upscale `src` (`width` x `height`) into `dst`, which must hold
+/// `width * factor` x `height * factor` pixels.
+pub fn upscale(
+    filter: Filter,
+    src: &[u8],
+    width: usize,
+    height: usize,
+    dst: &mut [u8],
+) -> Result<(), BufferTooSmall> {
+    match filter {
+        Filter::Scale2x => scale2x(src, width, height, dst),
+        Filter::Scale3x => scale3x(src, width, height, dst),
+        Filter::Epx => epx(src, width, height, dst),
+    }
+}
+
+pub fn scale2x(src: &[u8], width: usize, height: usize, dst: &mut [u8]) -> Result<(), BufferTooSmall> {
+    check_buffers(src, width, height, dst, 2)?;
+    for y in 0..height {
+        for x in 0..width {
+            let n = Neighbours::at(src, width, height, x, y);
+            let (a, b, c, d, p) = (n.up, n.right, n.left, n.down, n.center);
+            let block = [
+                if c == a && c != d && a != b { a } else { p },
+                if a == b && a != c && b != d { b } else { p },
+                if d == c && d != b && c != a { c } else { p },
+                if b == d && b != a && d != c { d } else { p },
+            ];
+            write_block(dst, width, x, y, 2, &block);
+        }
+    }
+    Ok(())
+}
+
+pub fn epx(src: &[u8], width: usize, height: usize, dst: &mut [u8]) -> Result<(), BufferTooSmall> {
+    check_buffers(src, width, height, dst, 2)?;
+    for y in 0..height {
+        for x in 0..width {
+            let n = Neighbours::at(src, width, height, x, y);
+            let (a, b, c, d, p) = (n.up, n.right, n.left, n.down, n.center);
+            let sides = [a, b, c, d];
+            // three or more identical neighbours means we are inside a flat area
+            let flat = sides
+                .iter()
+                .any(|side| sides.iter().filter(|other| *other == side).count() >= 3);
+            let block = if flat {
+                [p; 4]
+            } else {
+                [
+                    if c == a { a } else { p },
+                    if a == b { b } else { p },
+                    if d == c { c } else { p },
+                    if b == d { d } else { p },
+                ]
+            };
+            write_block(dst, width, x, y, 2, &block);
+        }
+    }
+    Ok(())
+}
+
+pub fn scale3x(src: &[u8], width: usize, height: usize, dst: &mut [u8]) -> Result<(), BufferTooSmall> {
+    check_buffers(src, width, height, dst, 3)?;
+    for y in 0..height {
+        for x in 0..width {
+            let n = Neighbours::at(src, width, height, x, y);
+            // same naming as the Scale3x reference: A B C / D E F / G H I
+            let (a, b, c) = (n.up_left, n.up, n.up_right);
+            let (d, e, f) = (n.left, n.center, n.right);
+            let (g, h, i) = (n.down_left, n.down, n.down_right);
+            let block = [
+                if d == b && b != f && d != h { d } else { e },
+                if (d == b && b != f && d != h && e != c) || (b == f && b != d && f != h && e != a) { b } else { e },
+                if b == f && b != d && f != h { f } else { e },
+                if (d == b && b != f && d != h && e != g) || (d == h && d != b && h != f && e != a) { d } else { e },
+                e,
+                if (b == f && b != d && f != h && e != i) || (h == f && d != h && b != f && e != c) { f } else { e },
+                if d == h && d != b && h != f { d } else { e },
+                if (d == h && d != b && h != f && e != i) || (h == f && d != h && b != f && e != g) { h } else { e },
+                if h == f && d != h && b != f { f } else { e },
+            ];
+            write_block(dst, width, x, y, 3, &block);
+        }
+    }
+    Ok(())
+}
+
+/// CRT-style scanline mask: darken every other row of `buffer` by `strength` (0..=255).
+///
+/// Meant for grayscale buffers (0 = off, 255 = fully lit), usually after upscaling.
+pub fn scanlines(buffer: &mut [u8], width: usize, height: usize, strength: u8) -> Result<(), BufferTooSmall> {
+    check_size(buffer, width, height, 1)?;
+    let keep = (u8::MAX - strength) as u16;
+    buffer
+        .chunks_exact_mut(width)
+        .take(height)
+        .skip(1)
+        .step_by(2)
+        .flatten()
+        .for_each(|pixel| *pixel = ((*pixel as u16 * keep) / u8::MAX as u16) as u8);
+    Ok(())
+}
+
+struct Neighbours {
+    up_left: u8,
+    up: u8,
+    up_right: u8,
+    left: u8,
+    center: u8,
+    right: u8,
+    down_left: u8,
+    down: u8,
+    down_right: u8,
+}
+
+impl Neighbours {
+    // pixels outside of the buffer repeat the closest edge pixel
+    fn at(src: &[u8], width: usize, height: usize, x: usize, y: usize) -> Self {
+        let left = x.saturating_sub(1);
+        let right = (x + 1).min(width - 1);
+        let up = y.saturating_sub(1);
+        let down = (y + 1).min(height - 1);
+        let pixel = |px: usize, py: usize| src[py * width + px];
+        Neighbours {
+            up_left: pixel(left, up),
+            up: pixel(x, up),
+            up_right: pixel(right, up),
+            left: pixel(left, y),
+            center: pixel(x, y),
+            right: pixel(right, y),
+            down_left: pixel(left, down),
+            down: pixel(x, down),
+            down_right: pixel(right, down),
+        }
+    }
+}
+
+fn check_buffers(
+    src: &[u8],
+    width: usize,
+    height: usize,
+    dst: &[u8],
+    factor: usize,
+) -> Result<(), BufferTooSmall> {
+    check_size(src, width, height, 1)?;
+    check_size(dst, width, height, factor)
+}
+
+// sizes too big to even count are too big for any buffer
+fn check_size(buffer: &[u8], width: usize, height: usize, factor: usize) -> Result<(), BufferTooSmall> {
+    let needed = [height, factor, factor].iter().try_fold(width, |size, n| size.checked_mul(*n));
+    match needed {
+        Some(needed) if buffer.len() >= needed => Ok(()),
+        needed => Err(BufferTooSmall {
+            needed: needed.unwrap_or(usize::MAX),
+            len: buffer.len(),
+        }),
+    }
+}
+
+// write a `factor` x `factor` block (row major) for the source pixel at (x, y)
+fn write_block(dst: &mut [u8], width: usize, x: usize, y: usize, factor: usize, block: &[u8]) {
+    let dst_width = width * factor;
+    block
+        .chunks_exact(factor)
+        .enumerate()
+        .for_each(|(row, pixels)| {
+            let begin = (y * factor + row) * dst_width + x * factor;
+            dst[begin..begin + factor].copy_from_slice(pixels);
+        });
+}
@@ -0,0 +1,32 @@
+use chipinho::audio::Beeper;
+use chipinho::constants::BEEPER_VOLUME;
+
+#[test]
+fn plays_a_square_wave_while_on() {
+    let mut beeper = Beeper::new(44_100);
+    assert_eq!(beeper.samples_per_frame(), 735);
+    let mut samples = [0; 735];
+    beeper.fill(true, &mut samples);
+    assert!(samples.iter().all(|sample| sample.abs() == BEEPER_VOLUME));
+    // 440Hz is a little over 100 samples per period, half of them high
+    let high = samples[..100].iter().filter(|sample| **sample > 0).count();
+    assert!((49..=51).contains(&high), "{}", high);
+    let edges = samples.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    assert_eq!(edges, 2 * 735 * 440 / 44_100);
+
+    beeper.fill(false, &mut samples);
+    assert!(samples.iter().all(|sample| *sample == 0));
+}
+
+#[test]
+fn chunks_join_without_gaps() {
+    let mut whole = [0; 800];
+    Beeper::new(48_000).fill(true, &mut whole);
+
+    let mut beeper = Beeper::new(48_000);
+    let mut chunks = [0; 800];
+    for chunk in chunks.chunks_mut(123) {
+        beeper.fill(true, chunk);
+    }
+    assert_eq!(chunks, whole);
+}
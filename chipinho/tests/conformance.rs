@@ -0,0 +1,210 @@
+//! Boots the test ROMs at `test_files/` and compares the screen they settle on against the
+//! golden images at `tests/golden/`.
+//!
+//! Run with `CHIPINHO_BLESS=1` to (re)write the golden images from the current output.
+
+use std::path::PathBuf;
+
+use chipinho::constants::{DEFAULT_CYCLES_PER_FRAME, NUM_KEYS};
+use chipinho::emulator::Emulator;
+use chipinho::testing::{compare_screen, screen_to_ascii};
+
+// the ROMs read this address to skip their menus
+const MENU_CHOICE_ADDR: usize = 0x1FF;
+const CHIP8_PLATFORM: u8 = 1;
+
+const MAX_FRAMES: u64 = 3000;
+// how many frames the screen needs to stay the same to be considered a result
+const STABLE_FRAMES: u64 = 120;
+const PRESS_FRAMES: u64 = 5;
+
+// the quirks ROM ends the line of each quirk with a 3x3 check mark or cross at this column
+const QUIRK_RESULT_X: usize = 59;
+const QUIRK_PASSED: &str = "
+    #.#
+    ##.
+    #..
+";
+const QUIRK_FAILED: &str = "
+    #.#
+    .#.
+    #.#
+";
+
+// (quirk, row of its result, whether it passes) on the CHIP-8 platform
+const CHIP8_QUIRKS: [(&str, usize, bool); 6] = [
+    ("VF RESET", 2, true),
+    ("MEMORY", 7, true),
+    // known failure: drawing does not wait for the next frame like on the VIP, so the ROM reports
+    // the display wait quirk as OFF. The golden image keeps the cross until that is emulated
+    ("DISP.WAIT", 12, false),
+    ("CLIPPING", 17, true),
+    ("SHIFTING", 22, true),
+    ("JUMPING", 27, true),
+];
+
+struct Case {
+    name: &'static str,
+    rom: &'static str,
+    // platform for the quirks test, opcode under test for the keypad test
+    menu_choice: Option<u8>,
+    cycles_per_frame: u32,
+    // run exactly this many frames instead of waiting for the screen to settle
+    frames: Option<u64>,
+    // (frame, key) pairs, each key is held for `PRESS_FRAMES` frames
+    presses: &'static [(u64, usize)],
+}
+
+impl Case {
+    const fn new(name: &'static str, rom: &'static str) -> Self {
+        Case {
+            name,
+            rom,
+            menu_choice: None,
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            frames: None,
+            presses: &[],
+        }
+    }
+
+    const fn menu_choice(mut self, choice: u8) -> Self {
+        self.menu_choice = Some(choice);
+        self
+    }
+
+    const fn frames(mut self, frames: u64) -> Self {
+        self.frames = Some(frames);
+        self
+    }
+
+    const fn presses(mut self, presses: &'static [(u64, usize)]) -> Self {
+        self.presses = presses;
+        self
+    }
+
+    fn run(&self) -> Result<Emulator, String> {
+        let rom_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../test_files").join(self.rom);
+        let program = std::fs::read(&rom_path).map_err(|e| format!("{}: {}", rom_path.display(), e))?;
+
+        let mut emulator = Emulator::new();
+        if emulator.load_program(&program).is_err() {
+            return Err(format!("could not load {}", self.rom));
+        }
+        if let Some(choice) = self.menu_choice {
+            emulator.get_memory_mut()[MENU_CHOICE_ADDR] = choice;
+        }
+
+        let mut keypad = [0; NUM_KEYS];
+        let mut last_screen = emulator.get_vram().to_vec();
+        let mut stable_since = 0;
+        for frame in 0..self.frames.unwrap_or(MAX_FRAMES) {
+            keypad.iter_mut().enumerate().for_each(|(key, pressed)| {
+                *pressed = self
+                    .presses
+                    .iter()
+                    .any(|(at, pressed_key)| *pressed_key == key && (*at..*at + PRESS_FRAMES).contains(&frame))
+                    as u8;
+            });
+            if let Err(err) = emulator.run_frame(&keypad, self.cycles_per_frame) {
+                return Err(format!("{}: {} on frame {}", self.rom, err, frame));
+            }
+            if self.frames.is_some() {
+                continue;
+            }
+            if emulator.is_halted() {
+                return Ok(emulator);
+            }
+            if emulator.get_vram() != last_screen.as_slice() {
+                last_screen = emulator.get_vram().to_vec();
+                stable_since = frame;
+            } else if frame - stable_since >= STABLE_FRAMES && !self.presses.iter().any(|(at, _)| *at > frame) {
+                return Ok(emulator);
+            }
+        }
+        match self.frames {
+            Some(_) => Ok(emulator),
+            None => Err(format!("{}: screen did not settle after {} frames", self.rom, MAX_FRAMES)),
+        }
+    }
+}
+
+fn check(case: Case) -> Emulator {
+    let emulator = case.run().unwrap_or_else(|err| panic!("{}", err));
+    let actual = screen_to_ascii(&emulator);
+    let golden_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.txt", case.name));
+
+    if std::env::var_os("CHIPINHO_BLESS").is_some() {
+        std::fs::write(&golden_path, &actual).unwrap();
+        return emulator;
+    }
+    let expected = std::fs::read_to_string(&golden_path)
+        .unwrap_or_else(|e| panic!("{}: {} (run with CHIPINHO_BLESS=1 to create it)", golden_path.display(), e));
+    if let Err(report) = compare_screen(emulator.get_vram(), None, &expected) {
+        panic!(
+            "{} (pc 0x{:04X}) does not match {}\n{}",
+            case.rom,
+            emulator.program_counter,
+            golden_path.display(),
+            report
+        );
+    }
+    emulator
+}
+
+#[test]
+fn chip8_logo() {
+    check(Case::new("chip8-logo", "1-chip8-logo.ch8"));
+}
+
+#[test]
+fn ibm_logo() {
+    check(Case::new("ibm-logo", "2-ibm-logo.ch8"));
+}
+
+#[test]
+fn corax_plus() {
+    check(Case::new("corax-plus", "3-corax+.ch8"));
+}
+
+#[test]
+fn flags() {
+    check(Case::new("flags", "4-flags.ch8"));
+}
+
+#[test]
+fn quirks_chip8() {
+    let emulator = check(Case::new("quirks-chip8", "5-quirks.ch8").menu_choice(CHIP8_PLATFORM));
+    // also checked on their own, so blessing can't turn a passing quirk into a failing one
+    for (quirk, y, passes) in CHIP8_QUIRKS {
+        let (expected, outcome) = if passes { (QUIRK_PASSED, "pass") } else { (QUIRK_FAILED, "fail") };
+        if let Err(report) = compare_screen(emulator.get_vram(), Some((QUIRK_RESULT_X, y)), expected) {
+            panic!(
+                "{} was expected to {}, update CHIP8_QUIRKS if that changed on purpose\n{}",
+                quirk, outcome, report
+            );
+        }
+    }
+}
+
+#[test]
+fn keypad_ex9e() {
+    check(Case::new("keypad-ex9e", "6-keypad.ch8").menu_choice(1).presses(&[(200, 0x5), (220, 0xA)]));
+}
+
+#[test]
+fn keypad_exa1() {
+    check(Case::new("keypad-exa1", "6-keypad.ch8").menu_choice(2).presses(&[(200, 0x5), (220, 0xA)]));
+}
+
+#[test]
+fn keypad_fx0a() {
+    check(Case::new("keypad-fx0a", "6-keypad.ch8").menu_choice(3).presses(&[(200, 0x5)]));
+}
+
+#[test]
+fn beep() {
+    // the beep test keeps animating, so there is no stable screen to wait for
+    check(Case::new("beep", "7-beep.ch8").frames(300));
+}
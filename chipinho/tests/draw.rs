@@ -0,0 +1,91 @@
+//! `Dxyn` behaviour at the edges of the screen, checked with `assert_screen!`.
+
+use chipinho::assert_screen;
+use chipinho::constants::{NUM_KEYS, NUM_REGISTERS};
+use chipinho::emulator::Emulator;
+
+// draw the font sprite for "0" at (V0, V1) = (x, y)
+fn draw_zero(x: u8, y: u8) -> Vec<u8> {
+    vec![0x60, x, 0x61, y, 0xA0, 0x00, 0xD0, 0x15]
+}
+
+fn run(program: &[u8]) -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.load_program(program).unwrap();
+    // every instruction in these programs takes two bytes
+    for _ in 0..program.len() / 2 {
+        emulator.tick(&[0; NUM_KEYS]).unwrap();
+    }
+    emulator
+}
+
+#[test]
+fn draws_sprite() {
+    let emulator = run(&draw_zero(0, 0));
+    assert_screen!(emulator, "
+        ####
+        #..#
+        #..#
+        #..#
+        ####
+    ");
+    assert_eq!(emulator.get_registers()[NUM_REGISTERS - 1], 0);
+}
+
+#[test]
+fn wraps_starting_position() {
+    // 66 and 33 are past the edges, so the sprite starts at (2, 1)
+    let emulator = run(&draw_zero(66, 33));
+    assert_screen!(emulator, "
+        ......
+        ..####
+        ..#..#
+        ..#..#
+        ..#..#
+        ..####
+    ");
+}
+
+#[test]
+fn clips_at_right_edge() {
+    let emulator = run(&draw_zero(62, 0));
+    assert_screen!(emulator, (60, 0), "
+        ..##
+        ..#.
+        ..#.
+        ..#.
+        ..##
+    ");
+    // nothing wraps around to the left side
+    assert_screen!(emulator, (0, 0), "
+        ..
+        ..
+        ..
+        ..
+        ..
+    ");
+}
+
+#[test]
+fn clips_at_bottom_edge() {
+    let emulator = run(&draw_zero(0, 30));
+    assert_screen!(emulator, (0, 29), "
+        ....
+        ####
+        #..#
+    ");
+    assert_screen!(emulator, (0, 0), "
+        ....
+        ....
+        ....
+    ");
+}
+
+#[test]
+fn collision_erases_and_sets_vf() {
+    let mut program = draw_zero(0, 0);
+    program.extend_from_slice(&[0xD0, 0x15]);
+    let emulator = run(&program);
+    assert_screen!(emulator, "");
+    assert_eq!(emulator.get_registers()[NUM_REGISTERS - 1], 1);
+}
@@ -0,0 +1,87 @@
+use chipinho::constants::{MAX_STACK_SIZE, VIP_STACK_SIZE};
+use chipinho::emulator::Emulator;
+use chipinho::error::{Error, ErrorKind, UnknownErrorCode};
+
+#[test]
+fn carries_the_failing_instruction() {
+    let mut emulator = Emulator::new();
+    emulator.load_program(&[0x12, 0x04, 0x00, 0x00, 0xF0, 0xFF]).unwrap();
+    emulator.step(&[0; 16]).unwrap();
+    let error = emulator.step(&[0; 16]).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::ParseInvalidInstruction(0xF0FF));
+    assert_eq!(error.pc, Some(0x204));
+    assert_eq!(error.to_string(), "invalid instruction F0FF at 0x204");
+    // the failing instruction is not skipped
+    assert_eq!(emulator.program_counter, 0x204);
+}
+
+#[test]
+fn stops_at_the_stack_limit() {
+    let mut emulator = Emulator::new();
+    // 0x200: CALL 0x200
+    emulator.load_program(&[0x22, 0x00]).unwrap();
+    emulator.set_stack_limit(VIP_STACK_SIZE as u16);
+    for _ in 0..VIP_STACK_SIZE {
+        emulator.step(&[0; 16]).unwrap();
+    }
+    let error = emulator.step(&[0; 16]).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::StackOverflow(12));
+    assert_eq!(error.to_string(), "stack overflow, calls nested deeper than 12 at 0x200 (opcode 2200)");
+    assert_eq!(emulator.get_stack().len(), VIP_STACK_SIZE);
+
+    emulator.set_stack_limit(1000);
+    assert_eq!(emulator.get_stack_limit(), MAX_STACK_SIZE as u16);
+}
+
+#[test]
+fn fails_to_return_without_a_call() {
+    let mut emulator = Emulator::new();
+    // 0x200: RET
+    emulator.load_program(&[0x00, 0xEE]).unwrap();
+    let error = emulator.step(&[0; 16]).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::StackUnderflow);
+    assert_eq!((error.pc, error.opcode), (Some(0x200), Some(0x00EE)));
+    assert_eq!(emulator.stack_size, 0);
+}
+
+#[test]
+fn round_trips_through_codes() {
+    let kinds = [
+        ErrorKind::ParseInvalidInstruction(0xF0FF),
+        ErrorKind::OutOfBoundsMemoryAccess(0x1000),
+        ErrorKind::NotEnoughMemoryForProgram,
+        ErrorKind::InvalidBufferSize,
+        ErrorKind::InvalidSymbolFile(12),
+        ErrorKind::StackOverflow(16),
+        ErrorKind::StackUnderflow,
+        ErrorKind::InvalidKey(0x42),
+        ErrorKind::InvalidState,
+        ErrorKind::InvalidArgument,
+    ];
+    for kind in kinds {
+        let code = u32::from(Error::new(kind).with_pc(0x200));
+        assert_eq!(Error::try_from(code), Ok(Error::new(kind)));
+    }
+    assert_eq!(u32::from(Error::new(ErrorKind::ParseInvalidInstruction(0xF0FF))), 0x1001_F0FF);
+    assert_eq!(Error::try_from(0), Err(UnknownErrorCode(0)));
+    assert_eq!(Error::try_from(0x1099_0000), Err(UnknownErrorCode(0x1099_0000)));
+}
+
+#[test]
+fn decodes_the_kind_below_the_marker_bit() {
+    // the kind is bits 16 to 27, masking in the 0x1 marker above it made every code unknown
+    assert_eq!(ErrorKind::from_code(0x1004_0000), Some(ErrorKind::InvalidBufferSize));
+    assert_eq!(ErrorKind::from_code(0x1002_0FFF), Some(ErrorKind::OutOfBoundsMemoryAccess(0x0FFF)));
+    assert_eq!(ErrorKind::from_code(0x1104_0000), None);
+    assert_eq!(ErrorKind::from_code(0x0004_0000), None);
+    assert_eq!(ErrorKind::from_code(0x3004_0000), None);
+    assert_eq!(ErrorKind::from_code(0x9001_F0FF), None);
+}
+
+#[test]
+fn codes_at_the_boundary() {
+    let mut emulator = Emulator::new();
+    assert_eq!(emulator.load_program_code(&[0; 4096]), 0x1003_0000);
+    assert_eq!(emulator.load_program_code(&[0xF0, 0xFF]), 0);
+    assert_eq!(emulator.tick_code(&[0; 16]), 0x1001_F0FF);
+}
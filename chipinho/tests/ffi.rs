@@ -0,0 +1,86 @@
+#![cfg(feature = "ffi")]
+//! Run with `cargo test -p chipinho --features ffi`, needs a C compiler (`$CC`, or `cc`).
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chipinho::constants::*;
+use chipinho::error::ErrorKind;
+use chipinho::ffi::*;
+
+#[test]
+fn error_constants_match_the_kinds() {
+    let kinds = [
+        (CHIPINHO_ERROR_INVALID_INSTRUCTION, ErrorKind::ParseInvalidInstruction(0)),
+        (CHIPINHO_ERROR_OUT_OF_BOUNDS_MEMORY_ACCESS, ErrorKind::OutOfBoundsMemoryAccess(0)),
+        (CHIPINHO_ERROR_NOT_ENOUGH_MEMORY_FOR_PROGRAM, ErrorKind::NotEnoughMemoryForProgram),
+        (CHIPINHO_ERROR_INVALID_BUFFER_SIZE, ErrorKind::InvalidBufferSize),
+        (CHIPINHO_ERROR_INVALID_SYMBOL_FILE, ErrorKind::InvalidSymbolFile(0)),
+        (CHIPINHO_ERROR_STACK_OVERFLOW, ErrorKind::StackOverflow(0)),
+        (CHIPINHO_ERROR_STACK_UNDERFLOW, ErrorKind::StackUnderflow),
+        (CHIPINHO_ERROR_INVALID_KEY, ErrorKind::InvalidKey(0)),
+        (CHIPINHO_ERROR_INVALID_STATE, ErrorKind::InvalidState),
+        (CHIPINHO_ERROR_INVALID_ARGUMENT, ErrorKind::InvalidArgument),
+    ];
+    for (code, kind) in kinds {
+        assert_eq!(code, kind.code(), "{}", kind);
+        assert_eq!(code & CHIPINHO_ERROR_PAYLOAD_MASK, 0);
+    }
+}
+
+#[test]
+fn size_constants_match_the_emulator() {
+    assert_eq!(CHIPINHO_DISPLAY_WIDTH, DISPLAY_WIDTH as usize);
+    assert_eq!(CHIPINHO_DISPLAY_HEIGHT, DISPLAY_HEIGHT as usize);
+    assert_eq!(CHIPINHO_VRAM_SIZE, VRAM_SIZE);
+    assert_eq!(CHIPINHO_MEMORY_SIZE, MEMORY_SIZE as usize);
+    assert_eq!(CHIPINHO_NUM_KEYS, NUM_KEYS);
+    assert_eq!(CHIPINHO_NUM_REGISTERS, NUM_REGISTERS);
+    assert_eq!(CHIPINHO_MAX_STACK_SIZE, MAX_STACK_SIZE);
+}
+
+#[test]
+fn describes_errors_into_c_buffers() {
+    let mut buffer = [0xAAu8; 10];
+    let code = ErrorKind::InvalidKey(0x1F).code();
+    let len = unsafe { chipinho_describe_error(code, buffer.as_mut_ptr().cast(), buffer.len()) };
+    assert_eq!(len, "invalid key 0x1F".len());
+    assert_eq!(&buffer, b"invalid k\0");
+    assert_eq!(unsafe { chipinho_describe_error(code, std::ptr::null_mut(), 0) }, len);
+}
+
+// the cdylib is built next to the test binaries
+fn library_dir() -> PathBuf {
+    env::current_exe().unwrap().parent().unwrap().to_path_buf()
+}
+
+#[test]
+fn c_program_runs_against_the_header() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let libraries = library_dir();
+    let output = libraries.join("chipinho-ffi-test");
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(compiler)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-pedantic"])
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg(manifest.join("tests/ffi/test.c"))
+        .arg("-o")
+        .arg(&output)
+        .arg("-L")
+        .arg(&libraries)
+        .arg(format!("-Wl,-rpath,{}", libraries.display()))
+        .arg("-lchipinho")
+        .status()
+        .expect("a C compiler is needed for this test");
+    assert!(status.success());
+
+    // cargo puts target/<profile> on the library path, where a build without `ffi` may have left its cdylib
+    let run = Command::new(&output)
+        .env("LD_LIBRARY_PATH", &libraries)
+        .env("DYLD_LIBRARY_PATH", &libraries)
+        .output()
+        .unwrap();
+    assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "ok\n");
+}
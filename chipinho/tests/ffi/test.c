@@ -0,0 +1,129 @@
+// exercises the C interface through the generated header, built and run by tests/ffi.rs
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "chipinho.h"
+
+static int failures = 0;
+
+#define CHECK(condition)                                                                      \
+    do {                                                                                      \
+        if (!(condition)) {                                                                   \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition);     \
+            failures++;                                                                       \
+        }                                                                                     \
+    } while (0)
+
+static void runs_a_program(void) {
+    static const uint8_t program[] = {
+        0x61, 0x05, // 0x200: V1 = 5
+        0xA2, 0x12, // 0x202: I = 0x212
+        0x60, 0x00, // 0x204: V0 = 0
+        0xD0, 0x01, // 0x206: draw 1 row at (V0, V0)
+        0x62, 0x03, // 0x208: V2 = 3
+        0xF2, 0x18, // 0x20A: sound timer = V2
+        0x22, 0x10, // 0x20C: call 0x210
+        0x12, 0x0E, // 0x20E: jump to itself
+        0x00, 0xEE, // 0x210: return
+        0xF0, 0x00, // 0x212: sprite
+    };
+    uint8_t keypad[CHIPINHO_NUM_KEYS] = {0};
+    Chipinho *chipinho = chipinho_new();
+    CHECK(chipinho_load_program(chipinho, program, sizeof(program)) == CHIPINHO_OK);
+    for (int i = 0; i < 7; i++) {
+        CHECK(chipinho_step(chipinho, keypad, sizeof(keypad)) == CHIPINHO_OK);
+    }
+
+    ChipinhoState state;
+    chipinho_get_state(chipinho, &state);
+    CHECK(state.program_counter == 0x210);
+    CHECK(state.stack_size == 1);
+    CHECK(state.stack[0] == 0x20E);
+    CHECK(state.registers[1] == 5);
+    CHECK(state.sound_timer == 3);
+    CHECK(chipinho_should_beep(chipinho));
+
+    const uint8_t *vram = chipinho_vram(chipinho);
+    static const uint8_t row[8] = {1, 1, 1, 1, 0, 0, 0, 0};
+    CHECK(memcmp(vram, row, sizeof(row)) == 0);
+    CHECK(chipinho_memory(chipinho)[0x200] == 0x61);
+
+    CHECK(chipinho_run_frame(chipinho, keypad, sizeof(keypad), 10) == CHIPINHO_OK);
+    chipinho_get_state(chipinho, &state);
+    CHECK(state.program_counter == 0x20E);
+    CHECK(state.stack_size == 0);
+    CHECK(chipinho_sound_timer(chipinho) == 2);
+    chipinho_free(chipinho);
+}
+
+static void reports_errors(void) {
+    static const uint8_t invalid[] = {0xF0, 0xFF};
+    static const uint8_t recursion[] = {0x22, 0x00};
+    char text[64];
+    Chipinho *chipinho = chipinho_new();
+
+    CHECK(chipinho_load_program(chipinho, invalid, sizeof(invalid)) == CHIPINHO_OK);
+    uint32_t code = chipinho_tick(chipinho, NULL, 0);
+    CHECK((code & CHIPINHO_ERROR_KIND_MASK) == CHIPINHO_ERROR_INVALID_INSTRUCTION);
+    CHECK((code & CHIPINHO_ERROR_PAYLOAD_MASK) == 0xF0FF);
+    CHECK(chipinho_describe_error(code, text, sizeof(text)) == strlen("invalid instruction F0FF"));
+    CHECK(strcmp(text, "invalid instruction F0FF") == 0);
+    // truncated like snprintf
+    CHECK(chipinho_describe_error(code, text, 8) == strlen("invalid instruction F0FF"));
+    CHECK(strcmp(text, "invalid") == 0);
+    chipinho_describe_error(0x1234, text, sizeof(text));
+    CHECK(strcmp(text, "unknown error code 0x00001234") == 0);
+
+    CHECK(chipinho_tick(chipinho, NULL, CHIPINHO_NUM_KEYS) == CHIPINHO_ERROR_INVALID_BUFFER_SIZE);
+    CHECK(chipinho_load_program(chipinho, NULL, 0) == CHIPINHO_OK);
+
+    uint8_t big[CHIPINHO_MEMORY_SIZE] = {0};
+    CHECK(chipinho_load_program(chipinho, big, sizeof(big)) == CHIPINHO_ERROR_NOT_ENOUGH_MEMORY_FOR_PROGRAM);
+
+    CHECK(chipinho_load_program(chipinho, recursion, sizeof(recursion)) == CHIPINHO_OK);
+    chipinho_set_stack_limit(chipinho, 2);
+    CHECK(chipinho_run_frame(chipinho, NULL, 0, 10) == (CHIPINHO_ERROR_STACK_OVERFLOW | 2));
+    ChipinhoState state;
+    chipinho_get_state(chipinho, &state);
+    CHECK(state.stack_size == 2);
+    CHECK(state.stack_limit == 2);
+    chipinho_free(chipinho);
+    chipinho_free(NULL);
+}
+
+static void picks_policies(void) {
+    // 0x200: I = 0xFFF, 0x202: V0 = random, 0x204: store V0 and V1 at I
+    static const uint8_t program[] = {0xAF, 0xFF, 0xC0, 0xFF, 0xF1, 0x55};
+    Chipinho *chipinho = chipinho_new();
+    CHECK(chipinho_load_program(chipinho, program, sizeof(program)) == CHIPINHO_OK);
+    CHECK(chipinho_set_memory_policy(chipinho, ChipinhoMemoryPolicy_Wrap) == CHIPINHO_OK);
+    CHECK(chipinho_set_random(chipinho, ChipinhoRandomKind_Legacy, 7) == CHIPINHO_OK);
+    CHECK(chipinho_run_frame(chipinho, NULL, 0, 3) == CHIPINHO_OK);
+    uint8_t first = chipinho_memory(chipinho)[0xFFF];
+
+    CHECK(chipinho_load_program(chipinho, program, sizeof(program)) == CHIPINHO_OK);
+    chipinho_set_seed(chipinho, 7);
+    CHECK(chipinho_set_memory_policy(chipinho, ChipinhoMemoryPolicy_Strict) == CHIPINHO_OK);
+    // plain ints out of range are rejected, leaving Strict and the legacy generator in place
+    CHECK(chipinho_set_memory_policy(chipinho, 3) == CHIPINHO_ERROR_INVALID_ARGUMENT);
+    CHECK(chipinho_set_memory_policy(chipinho, UINT32_MAX) == CHIPINHO_ERROR_INVALID_ARGUMENT);
+    CHECK(chipinho_set_random(chipinho, 42, 99) == CHIPINHO_ERROR_INVALID_ARGUMENT);
+    CHECK(chipinho_run_frame(chipinho, NULL, 0, 3) == (CHIPINHO_ERROR_OUT_OF_BOUNDS_MEMORY_ACCESS | 0x1000));
+    ChipinhoState state;
+    chipinho_get_state(chipinho, &state);
+    CHECK(state.registers[0] == first);
+    chipinho_free(chipinho);
+}
+
+int main(void) {
+    runs_a_program();
+    reports_errors();
+    picks_policies();
+    if (failures > 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return EXIT_FAILURE;
+    }
+    printf("ok\n");
+    return EXIT_SUCCESS;
+}
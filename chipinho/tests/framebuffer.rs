@@ -0,0 +1,65 @@
+use chipinho::constants::*;
+use chipinho::emulator::Emulator;
+use chipinho::error::ErrorKind;
+use chipinho::framebuffer::Framebuffer;
+
+#[test]
+fn paints_the_vram_as_rgba() {
+    let mut framebuffer = Framebuffer::new(0x33FF66FF, 0x0A1A0FFF);
+    assert_eq!(framebuffer.get_rgba().len(), RGBA_SIZE);
+    assert!(framebuffer.get_rgba().chunks(4).all(|color| color == [0x0A, 0x1A, 0x0F, 0xFF]));
+
+    let mut vram = [0; VRAM_SIZE];
+    vram[1] = 1;
+    framebuffer.update(&vram);
+    assert_eq!(framebuffer.get_rgba()[..8], [0x0A, 0x1A, 0x0F, 0xFF, 0x33, 0xFF, 0x66, 0xFF]);
+
+    // 0x200: DRW V0, V0, 1 with I at the 0 glyph, whose top row is 0xF0
+    let mut emulator = Emulator::new();
+    emulator.load_program(&[0xD0, 0x01]).unwrap();
+    emulator.step(&[0; NUM_KEYS]).unwrap();
+    framebuffer.update_from(&emulator);
+    let lit = framebuffer.get_rgba().chunks(4).filter(|color| color[0] == 0x33).count();
+    assert_eq!(lit, 4);
+}
+
+#[test]
+fn blends_intensities_between_the_colors() {
+    let mut framebuffer = Framebuffer::new(0xFF0000FF, 0x0000FF00);
+    framebuffer.update_intensity(&[0, 255, 128]);
+    assert_eq!(
+        framebuffer.get_rgba()[..12],
+        [0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0x00, 0xFF, 0x80, 0x00, 0x7F, 0x80]
+    );
+}
+
+#[test]
+fn runs_frames_returning_codes() {
+    let mut emulator = Emulator::new();
+    // 0x200: JP 0x200
+    emulator.load_program(&[0x12, 0x00]).unwrap();
+    emulator.delay_timer = 10;
+    assert_eq!(emulator.run_frame_code(&[0; NUM_KEYS], 5), 0);
+    assert_eq!(emulator.delay_timer, 9);
+
+    emulator.load_program(&[0xFF, 0xFF]).unwrap();
+    let code = emulator.run_frame_code(&[0; NUM_KEYS], 5);
+    assert_eq!(code & 0xFFFF0000, ErrorKind::ParseInvalidInstruction(0).code());
+}
+
+// run with `cargo test -p chipinho --features wasm`
+#[cfg(feature = "wasm")]
+#[test]
+fn views_point_into_the_emulator() {
+    let mut emulator = Emulator::new();
+    emulator.load_program(&[0x00, 0xE0]).unwrap();
+    assert_eq!(emulator.vram_ptr(), emulator.get_vram().as_ptr());
+    assert_eq!(emulator.vram_len(), VRAM_SIZE);
+    assert_eq!(emulator.memory_len(), MEMORY_SIZE as usize);
+    unsafe { *emulator.memory_ptr().add(0x201) = 0xEE };
+    assert_eq!(emulator.get_memory()[0x200..0x202], [0x00, 0xEE]);
+
+    let framebuffer = Framebuffer::default();
+    assert_eq!(framebuffer.rgba_ptr(), framebuffer.get_rgba().as_ptr());
+    assert_eq!(framebuffer.rgba_len(), RGBA_SIZE);
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "embedded-graphics")]
+//! Run with `cargo test -p chipinho --features embedded-graphics`.
+use chipinho::constants::*;
+use chipinho::emulator::Emulator;
+use chipinho::graphics::Display;
+use embedded_graphics::mock_display::MockDisplay;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+
+// a 128x64 monochrome panel, like an SSD1306
+struct Panel {
+    pixels: [[BinaryColor; 128]; 64],
+}
+
+impl OriginDimensions for Panel {
+    fn size(&self) -> Size {
+        Size::new(128, 64)
+    }
+}
+
+impl DrawTarget for Panel {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I: IntoIterator<Item = Pixel<BinaryColor>>>(&mut self, pixels: I) -> Result<(), Self::Error> {
+        for Pixel(point, color) in pixels {
+            self.pixels[point.y as usize][point.x as usize] = color;
+        }
+        Ok(())
+    }
+}
+
+// 0x200: DRW V0, V0, 2 with I at the 0 glyph, 0xF0 then 0x90
+fn emulator() -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.load_program(&[0xD0, 0x02]).unwrap();
+    emulator.step(&[0; NUM_KEYS]).unwrap();
+    emulator
+}
+
+#[test]
+fn draws_the_vram_on_a_mock_display() {
+    let mut display = MockDisplay::new();
+    Display::new(BinaryColor::On, BinaryColor::Off)
+        .draw(emulator().get_vram(), &mut display)
+        .unwrap();
+    let mut pattern = vec![".".repeat(64); 64];
+    pattern[0] = format!("####{}", ".".repeat(60));
+    pattern[1] = format!("#..#{}", ".".repeat(60));
+    // the mock display is 64x64, chip8 only covers the top half
+    pattern[32..].iter_mut().for_each(|row| *row = " ".repeat(64));
+    let pattern: Vec<&str> = pattern.iter().map(String::as_str).collect();
+    display.assert_pattern(&pattern);
+}
+
+#[test]
+fn fills_an_ssd1306_at_twice_the_size() {
+    let mut panel = Panel {
+        pixels: [[BinaryColor::On; 128]; 64],
+    };
+    let display = Display::new(BinaryColor::On, BinaryColor::Off).fit(panel.size());
+    assert_eq!(display.scale, 2);
+    assert_eq!(display.bounding_box(), Rectangle::new(Point::zero(), Size::new(128, 64)));
+    display.draw(emulator().get_vram(), &mut panel).unwrap();
+
+    let lit: Vec<(usize, usize)> = (0..64)
+        .flat_map(|y| (0..128).map(move |x| (x, y)))
+        .filter(|(x, y)| panel.pixels[*y][*x] == BinaryColor::On)
+        .collect();
+    // 6 chip8 pixels, 4 each
+    assert_eq!(lit.len(), 6 * 4);
+    assert!(lit.contains(&(7, 1)) && lit.contains(&(6, 3)) && !lit.contains(&(2, 3)));
+}
+
+#[test]
+fn centers_on_screens_that_do_not_match() {
+    let display = Display::new(BinaryColor::On, BinaryColor::Off).fit(Size::new(128, 32));
+    assert_eq!(display.scale, 1);
+    assert_eq!(display.bounding_box(), Rectangle::new(Point::new(32, 0), Size::new(64, 32)));
+
+    let display = Display::new(BinaryColor::On, BinaryColor::Off).fit(Size::new(320, 240));
+    assert_eq!(display.scale, 5);
+    assert_eq!(display.origin, Point::new(0, 40));
+}
@@ -0,0 +1,78 @@
+use chipinho::emulator::Emulator;
+use chipinho::error::{Error, ErrorKind};
+use chipinho::history::Recorder;
+
+const KEYPAD: [u8; 16] = [0; 16];
+
+// 0x200: LD V0, 0x05
+// 0x202: LD I, 0x20A
+// 0x204: CALL 0x208
+// 0x206: JP 0x204
+// 0x208: ADD V1, 0x01
+// 0x20A: invalid
+const PROGRAM: [u8; 12] = [0x60, 0x05, 0xA2, 0x0A, 0x22, 0x08, 0x12, 0x04, 0x71, 0x01, 0xF0, 0xFF];
+
+fn run_until_error<const N: usize>(recorder: &mut Recorder<N>) -> Error {
+    for _ in 0..100 {
+        if let Err(err) = recorder.step(&KEYPAD) {
+            return err;
+        }
+    }
+    panic!("the program did not fail");
+}
+
+#[test]
+fn records_instructions_and_register_changes() {
+    let mut emulator = Emulator::new();
+    emulator.load_program(&PROGRAM).unwrap();
+    let mut recorder: Recorder<8> = Recorder::new(emulator);
+    let error = run_until_error(&mut recorder);
+    assert_eq!(error.kind, ErrorKind::ParseInvalidInstruction(0xF0FF));
+    assert_eq!((error.pc, error.opcode), (Some(0x20A), Some(0xF0FF)));
+
+    let entries: Vec<_> = recorder.history.iter().collect();
+    let pcs: Vec<u16> = entries.iter().map(|entry| entry.pc).collect();
+    assert_eq!(pcs, [0x200, 0x202, 0x204, 0x208]);
+    assert_eq!(entries[0].opcode, 0x6005);
+    assert_eq!(entries[0].register_changes().collect::<Vec<_>>(), [(0, 0x05)]);
+    assert_eq!(entries[1].index_change(), Some(0x20A));
+    assert_eq!(entries[2].register_changes().count(), 0);
+    assert_eq!(entries[3].register_changes().collect::<Vec<_>>(), [(1, 0x01)]);
+}
+
+#[test]
+fn keeps_only_the_last_entries() {
+    let mut emulator = Emulator::new();
+    emulator.load_program(&PROGRAM).unwrap();
+    let mut recorder: Recorder<2> = Recorder::new(emulator);
+    run_until_error(&mut recorder);
+    let pcs: Vec<u16> = recorder.history.iter().map(|entry| entry.pc).collect();
+    assert_eq!(pcs, [0x204, 0x208]);
+    assert_eq!(recorder.history.len(), 2);
+}
+
+#[test]
+fn reports_the_crash() {
+    let mut emulator = Emulator::new();
+    emulator.load_program(&PROGRAM).unwrap();
+    let mut recorder: Recorder<8> = Recorder::new(emulator);
+    let error = run_until_error(&mut recorder);
+    let report = recorder.crash_report(error);
+    assert_eq!(report.program_counter, 0x20A);
+    assert_eq!(report.opcode, Some(0xF0FF));
+    assert_eq!(report.stack, [0x206]);
+    assert_eq!(report.memory_at_pc.start, 0x1FA);
+    assert_eq!(report.memory_at_pc.bytes.len(), 32);
+
+    let text = report.to_string();
+    assert!(text.starts_with("crash: invalid instruction F0FF at 0x20A\n"));
+    assert!(text.contains("  0x20A:>F0 FF"));
+    assert!(text.contains("  0x200                    6005  LD V0, 0x05          V0=0x05\n"));
+
+    let mut json = String::new();
+    report.write_json(&mut json).unwrap();
+    let error = "{\"error\":{\"code\":268562687,\"description\":\"invalid instruction F0FF at 0x20A\"}";
+    assert!(json.starts_with(error));
+    assert!(json.contains("\"stack\":[518]"));
+    assert!(json.contains("{\"pc\":514,\"opcode\":41482,\"changes\":{\"I\":522}}"));
+}
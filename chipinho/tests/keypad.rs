@@ -0,0 +1,60 @@
+use chipinho::constants::NUM_KEYS;
+use chipinho::emulator::Emulator;
+use chipinho::keypad::{KeypadInput, MatrixKeypad, VIP_LAYOUT};
+
+#[test]
+fn reads_one_pin_per_key() {
+    let mut input = |key: usize| key == 0x5 || key == 0xF;
+    let mut keypad = [0; NUM_KEYS];
+    input.scan(&mut keypad);
+    assert_eq!(keypad, [0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    assert!(input.is_pressed(0xF));
+}
+
+#[test]
+fn scans_a_matrix_row_by_row() {
+    let mut driven = Vec::new();
+    {
+        // the second column of the second row (5) and the last of the last (F)
+        let mut matrix = MatrixKeypad::new(|row| {
+            driven.push(row);
+            match row {
+                1 => 0b0010,
+                3 => 0b1000,
+                _ => 0,
+            }
+        });
+        let mut keypad = [1; NUM_KEYS];
+        matrix.scan(&mut keypad);
+        assert_eq!(keypad, [0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert!(matrix.is_pressed(0x5));
+        assert!(!matrix.is_pressed(0x6));
+    }
+    assert_eq!(driven[..4], [0, 1, 2, 3]);
+}
+
+#[test]
+fn follows_other_wirings() {
+    // rotated half a turn
+    let mut layout = VIP_LAYOUT;
+    layout.reverse();
+    layout.iter_mut().for_each(|row| row.reverse());
+    let mut matrix = MatrixKeypad::new(|row| (row == 0) as u8).with_layout(layout);
+    assert!(matrix.is_pressed(0xF));
+    assert!(!matrix.is_pressed(0x1));
+}
+
+#[test]
+fn drives_the_emulator() {
+    // 0x200: LD V1, 5
+    // 0x202: SKP V1
+    // 0x204: JP 0x202
+    // 0x206: JP 0x206
+    let mut emulator = Emulator::new();
+    emulator.load_program(&[0x61, 0x05, 0xE1, 0x9E, 0x12, 0x02, 0x12, 0x06]).unwrap();
+    let mut keypad = [0; NUM_KEYS];
+    let mut matrix = MatrixKeypad::new(|row| if row == 1 { 0b0010 } else { 0 });
+    matrix.scan(&mut keypad);
+    emulator.run_frame(&keypad, 4).unwrap();
+    assert!(emulator.is_halted());
+}
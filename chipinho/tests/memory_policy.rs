@@ -0,0 +1,86 @@
+use chipinho::constants::{MEMORY_SIZE, NUM_KEYS};
+use chipinho::emulator::{Emulator, MemoryPolicy};
+use chipinho::error::ErrorKind;
+use chipinho::{assert_screen, font::FONT_SET};
+
+const KEYPAD: [u8; NUM_KEYS] = [0; NUM_KEYS];
+
+fn run(policy: MemoryPolicy, program: &[u8], steps: usize) -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.set_memory_policy(policy);
+    emulator.load_program(program).unwrap();
+    for _ in 0..steps {
+        emulator.step(&KEYPAD).unwrap();
+    }
+    emulator
+}
+
+#[test]
+fn strict_is_the_default() {
+    assert_eq!(Emulator::new().get_memory_policy(), MemoryPolicy::Strict);
+}
+
+#[test]
+fn fetches_wrap_around() {
+    // JP 0xFFF, where 0x12 followed by the first font byte (0xF0) makes JP 0x2F0
+    for policy in [MemoryPolicy::Wrap, MemoryPolicy::Strict, MemoryPolicy::Clamp] {
+        let mut emulator = run(policy, &[0x1F, 0xFF], 1);
+        emulator.get_memory_mut()[0xFFF] = 0x12;
+        let res = emulator.step(&KEYPAD);
+        match policy {
+            MemoryPolicy::Wrap => assert_eq!(emulator.program_counter, 0x2F0),
+            _ => assert_eq!(res.unwrap_err().kind, ErrorKind::OutOfBoundsMemoryAccess(0xFFF)),
+        }
+    }
+}
+
+#[test]
+fn bulk_transfers_wrap_around() {
+    // LD I, 0xFFE; LD V3, [I]
+    let emulator = run(MemoryPolicy::Wrap, &[0xAF, 0xFE, 0xF3, 0x65], 2);
+    let memory = emulator.get_memory();
+    assert_eq!(emulator.get_registers()[..4], [memory[0xFFE], memory[0xFFF], FONT_SET[0], FONT_SET[1]]);
+    assert_eq!(emulator.index, 0x1002);
+
+    // LD I, 0xFFF; LD V0, 123; LD B, V0
+    let emulator = run(MemoryPolicy::Wrap, &[0xAF, 0xFF, 0x60, 123, 0xF0, 0x33], 3);
+    let memory = emulator.get_memory();
+    assert_eq!([memory[0xFFF], memory[0x000], memory[0x001]], [1, 2, 3]);
+}
+
+#[test]
+fn clamped_transfers_skip_the_end() {
+    // LD V0, 0xAA; LD V1, 0xBB; LD V2, 0xCC; LD I, 0xFFE; LD [I], V2
+    let emulator = run(MemoryPolicy::Clamp, &[0x60, 0xAA, 0x61, 0xBB, 0x62, 0xCC, 0xAF, 0xFE, 0xF2, 0x55], 5);
+    let memory = emulator.get_memory();
+    assert_eq!(memory[0xFFE..], [0xAA, 0xBB]);
+    assert_eq!(memory[0], FONT_SET[0]);
+
+    // LD V2, 0xCC; LD I, 0xFFF; LD V2, [I]
+    let emulator = run(MemoryPolicy::Clamp, &[0x62, 0xCC, 0xAF, 0xFF, 0xF2, 0x65], 3);
+    assert_eq!(emulator.get_registers()[2], 0xCC);
+}
+
+#[test]
+fn sprites_wrap_or_clamp() {
+    // LD I, 0xFFF; DRW V0, V0, 3, with 0xFF as the first sprite row
+    let program = [0xAF, 0xFF, 0xD0, 0x03];
+    let mut emulator = run(MemoryPolicy::Wrap, &program, 1);
+    emulator.get_memory_mut()[0xFFF] = 0xFF;
+    emulator.step(&KEYPAD).unwrap();
+    // then the first two rows of the "0" font sprite
+    assert_screen!(emulator, (0, 0), "
+        ########
+        ####....
+        #..#....
+    ");
+
+    let mut emulator = run(MemoryPolicy::Clamp, &program, 1);
+    emulator.get_memory_mut()[0xFFF] = 0xFF;
+    emulator.step(&KEYPAD).unwrap();
+    assert_screen!(emulator, "########");
+
+    let mut emulator = run(MemoryPolicy::Strict, &program, 1);
+    let error = emulator.step(&KEYPAD).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::OutOfBoundsMemoryAccess(MEMORY_SIZE));
+}
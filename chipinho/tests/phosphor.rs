@@ -0,0 +1,81 @@
+use chipinho::constants::*;
+use chipinho::phosphor::{Phosphor, PhosphorMode};
+
+const LIT: [u8; VRAM_SIZE] = [1; VRAM_SIZE];
+const OFF: [u8; VRAM_SIZE] = [0; VRAM_SIZE];
+
+// the intensity of the first pixel after each frame, `true` for a lit one
+fn run(phosphor: &mut Phosphor, frames: &[bool]) -> Vec<u8> {
+    frames
+        .iter()
+        .map(|lit| {
+            phosphor.update(if *lit { &LIT } else { &OFF });
+            phosphor.get_intensity()[0]
+        })
+        .collect()
+}
+
+#[test]
+fn decays_by_strength_each_frame() {
+    let mut phosphor = Phosphor::new(PhosphorMode::Decay, 128);
+    assert_eq!(run(&mut phosphor, &[true, false, false, false, false]), [255, 128, 64, 32, 16]);
+    // lighting up again is immediate
+    assert_eq!(run(&mut phosphor, &[true]), [255]);
+
+    phosphor.strength = 0;
+    assert_eq!(run(&mut phosphor, &[false]), [0]);
+    phosphor.strength = u8::MAX;
+    assert_eq!(run(&mut phosphor, &[true, false, false]), [255, 255, 255]);
+}
+
+#[test]
+fn blends_the_last_frames() {
+    let mut phosphor = Phosphor::new(PhosphorMode::Blend, 3);
+    assert_eq!(run(&mut phosphor, &[true, false, false, false, true]), [255, 255, 255, 0, 255]);
+}
+
+#[test]
+fn clamps_the_blend_window() {
+    // no frames is the current one only
+    let mut phosphor = Phosphor::new(PhosphorMode::Blend, 0);
+    assert_eq!(run(&mut phosphor, &[true, false, true]), [255, 0, 255]);
+
+    let mut phosphor = Phosphor::new(PhosphorMode::Blend, u8::MAX);
+    let mut frames = [false; 9];
+    frames[0] = true;
+    let intensities = run(&mut phosphor, &frames);
+    assert_eq!(intensities[..PHOSPHOR_MAX_BLEND_FRAMES as usize], [255; PHOSPHOR_MAX_BLEND_FRAMES as usize]);
+    assert_eq!(intensities[PHOSPHOR_MAX_BLEND_FRAMES as usize], 0);
+}
+
+#[test]
+fn ghost_free_fills_one_frame_gaps_a_frame_late() {
+    let mut phosphor = Phosphor::new(PhosphorMode::GhostFree, 0);
+    assert_eq!(
+        run(&mut phosphor, &[true, false, true, true, false, false, false]),
+        [0, 255, 255, 255, 255, 0, 0]
+    );
+    // two frames off is a real gap
+    phosphor.reset();
+    assert_eq!(run(&mut phosphor, &[true, false, false, true]), [0, 255, 0, 0]);
+}
+
+#[test]
+fn ghost_free_leaves_no_trail_behind_moving_sprites() {
+    // a pixel wide sprite moving right a pixel per frame
+    let mut phosphor = Phosphor::new(PhosphorMode::GhostFree, 0);
+    for x in 0..4 {
+        let mut vram = OFF;
+        vram[x] = 1;
+        phosphor.update(&vram);
+        let lit: Vec<usize> = (0..8).filter(|x| phosphor.get_intensity()[*x] != 0).collect();
+        let expected: Vec<usize> = x.checked_sub(1).into_iter().collect();
+        assert_eq!(lit, expected, "frame {}", x);
+    }
+}
+
+#[test]
+fn passes_the_vram_through_when_off() {
+    let mut phosphor = Phosphor::new(PhosphorMode::Off, 200);
+    assert_eq!(run(&mut phosphor, &[true, false, true]), [255, 0, 255]);
+}
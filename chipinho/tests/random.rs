@@ -0,0 +1,137 @@
+use chipinho::constants::NUM_KEYS;
+use chipinho::emulator::Emulator;
+use chipinho::random::{LegacyLcg, Pcg32, RandomGenerator, RandomKind, Rng, VipRandom, VIP_INTERPRETER_PAGE};
+
+// counts up from the seed, jumping 100 every frame
+#[derive(Clone)]
+struct Counter {
+    next: u8,
+}
+
+impl Rng for Counter {
+    fn seed(&mut self, seed: u64) {
+        self.next = seed as u8;
+    }
+
+    fn next_u8(&mut self, _memory: &[u8]) -> u8 {
+        self.next = self.next.wrapping_add(1);
+        self.next
+    }
+
+    fn frame(&mut self) {
+        self.next = self.next.wrapping_add(100);
+    }
+}
+
+// 0x200: RND V0, 0xFF
+// 0x202: JP 0x200
+const PROGRAM: [u8; 4] = [0xC0, 0xFF, 0x12, 0x00];
+
+// the values of V0 after each of the first `count` RND instructions
+fn draw(emulator: &mut Emulator, count: usize) -> Vec<u8> {
+    emulator.load_program(&PROGRAM).unwrap();
+    (0..count)
+        .map(|_| {
+            emulator.step(&[0; NUM_KEYS]).unwrap();
+            let value = emulator.get_registers()[0];
+            emulator.step(&[0; NUM_KEYS]).unwrap();
+            value
+        })
+        .collect()
+}
+
+#[test]
+fn seeds_make_runs_reproducible() {
+    let mut first = Emulator::new();
+    let mut second = Emulator::new();
+    first.set_seed(42);
+    second.set_seed(42);
+    assert_eq!(draw(&mut first, 64), draw(&mut second, 64));
+
+    second.set_seed(43);
+    assert_ne!(draw(&mut first, 64), draw(&mut second, 64));
+}
+
+#[test]
+fn defaults_to_pcg32_covering_every_byte() {
+    let mut emulator = Emulator::new();
+    assert_eq!(emulator.get_random_kind(), Some(RandomKind::Pcg32));
+    let mut seen = [false; 256];
+    for value in draw(&mut emulator, 4096) {
+        seen[value as usize] = true;
+    }
+    assert!(seen.iter().all(|seen| *seen));
+}
+
+#[test]
+fn legacy_keeps_the_old_sequence() {
+    let mut emulator = Emulator::new();
+    emulator.set_random(RandomKind::Legacy, 123);
+    let mut last: u8 = 123;
+    let expected: Vec<u8> = (0..16)
+        .map(|_| {
+            last = 42u8.wrapping_mul(last).wrapping_add(31) % 253;
+            last
+        })
+        .collect();
+    assert_eq!(draw(&mut emulator, 16), expected);
+    assert!(draw(&mut emulator, 1024).iter().all(|value| *value < 253));
+}
+
+#[test]
+fn vip_follows_the_interpreter_routine() {
+    // the routine itself is in the page, at 0x1D9: INC R9, GLO R9, PLO RE, GHI R3, PHI RE...
+    assert_eq!(VIP_INTERPRETER_PAGE[0xD9..0xDE], [0x19, 0x89, 0xAE, 0x93, 0xBE]);
+
+    let mut emulator = Emulator::new();
+    emulator.set_random(RandomKind::Vip, 0x00D8);
+    // R9 = 0x00D9: 0x00 + 0x19 = 0x19, folded into 0x0C + 0x19 = 0x25, which becomes R9.1
+    // R9 = 0x25DA: 0x25 + 0x89 = 0xAE, folded into 0x57 + 0xAE = 0x05
+    // R9 = 0x05DB: 0x05 + 0xAE = 0xB3, folded into 0x59 + 0xB3 = 0x0C
+    // so RNDs within one frame differ, the page moves them along
+    assert_eq!(draw(&mut emulator, 3), [0x25, 0x05, 0x0C]);
+    // the interrupt increments R9 as well, skipping 0x1DC
+    emulator.update_timers();
+    assert_eq!(draw(&mut emulator, 1), [0x2F]);
+
+    // and the carry of the first ADD comes back in at the top: R9 = 0xF0D9, 0xF0 + 0x19 = 0x09 carry 1,
+    // folded into 0x84 + 0x09 = 0x8D
+    let mut rng = VipRandom::new(0xF0D8);
+    assert_eq!(rng.next_u8(&[]), 0x8D);
+}
+
+#[test]
+fn generators_can_be_used_directly() {
+    let mut generator = RandomGenerator::Legacy(LegacyLcg::new(1));
+    assert_eq!(generator.next_u8(&[]), 73);
+    generator.seed(1);
+    assert_eq!(generator.next_u8(&[]), 73);
+
+    let mut emulator = Emulator::new();
+    emulator.set_rng(RandomGenerator::Pcg32(Pcg32::new(7)));
+    let mut expected = Pcg32::new(7);
+    assert_eq!(draw(&mut emulator, 8), (0..8).map(|_| expected.next_u8(&[])).collect::<Vec<_>>());
+}
+
+#[test]
+fn custom_generators_drive_rnd() {
+    let mut emulator = Emulator::new();
+    emulator.set_rng(RandomGenerator::custom(Counter { next: 10 }));
+    assert_eq!(emulator.get_random_kind(), None);
+    assert_eq!(draw(&mut emulator, 3), [11, 12, 13]);
+    emulator.update_timers();
+    assert_eq!(draw(&mut emulator, 1), [114]);
+
+    // copies of the emulator carry on separately
+    let mut copy = emulator.clone();
+    assert_eq!(draw(&mut copy, 2), [115, 116]);
+    assert_eq!(draw(&mut emulator, 2), [115, 116]);
+
+    emulator.set_seed(200);
+    assert_eq!(draw(&mut emulator, 1), [201]);
+
+    // RND masks the byte with kk, 0xC00F
+    emulator.load_program(&[0xC0, 0x0F]).unwrap();
+    emulator.step(&[0; NUM_KEYS]).unwrap();
+    assert_eq!(emulator.get_registers()[0], 202 & 0x0F);
+}
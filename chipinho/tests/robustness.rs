@@ -0,0 +1,105 @@
+// `tick` must never panic, whatever the program and the keypad. The fuzzer at `fuzz/` looks
+// for such inputs, this runs the same harness on a fixed set of them so regressions show up
+// in `cargo test`.
+
+use chipinho::constants::{MEMORY_SIZE, NUM_KEYS, PROGRAM_BEGIN_ADDR};
+use chipinho::emulator::Emulator;
+use chipinho::error::ErrorKind;
+use chipinho::testing::fuzz::exercise;
+
+const TICKS: usize = 2000;
+
+// xorshift, good enough to come up with programs
+struct Generator(u64);
+
+impl Generator {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+fn load(program: &[u8]) -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.load_program(program).unwrap();
+    emulator
+}
+
+#[test]
+fn survives_random_programs() {
+    let mut generator = Generator(0x5EED_C8C8_0000_0001);
+    for _ in 0..1500 {
+        let len = generator.next() as usize % 512;
+        let _ = exercise(&generator.bytes(len), TICKS);
+    }
+}
+
+#[test]
+fn survives_random_instructions_near_the_end_of_memory() {
+    let mut generator = Generator(0xC0FF_EE00_1234_5678);
+    for run in 0..1500u32 {
+        // I near the end of memory, then random instructions touching it, under every memory policy
+        let mut data = vec![((run % 8) << 5) as u8 | NUM_KEYS as u8];
+        data.extend(generator.bytes(NUM_KEYS));
+        data.extend([0xAF, 0xFA]);
+        data.extend(generator.bytes(64));
+        let _ = exercise(&data, TICKS);
+    }
+}
+
+#[test]
+fn survives_any_keypad_length() {
+    // LD V1, 0x0F; SKP V1; SKNP V1; LD V0, K; JP 0x200
+    let program = [0x61, 0x0F, 0xE1, 0x9E, 0xE1, 0xA1, 0xF0, 0x0A, 0x12, 0x00];
+    for keypad_len in 0..=NUM_KEYS + 2 {
+        let mut data = vec![keypad_len as u8];
+        data.extend((0..keypad_len).map(|key| (key % 3 == 0) as u8));
+        data.extend(program);
+        assert_eq!(exercise(&data, TICKS), Ok(()));
+    }
+}
+
+#[test]
+fn rejects_registers_that_are_not_keys() {
+    // LD V0, 0x10; SKP V0
+    let mut emulator = load(&[0x60, 0x10, 0xE0, 0x9E]);
+    emulator.tick(&[0; NUM_KEYS]).unwrap();
+    let error = emulator.tick(&[0; NUM_KEYS]).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::InvalidKey(0x10));
+}
+
+#[test]
+fn reports_memory_accesses_past_the_end() {
+    // LD I, 0xFFF then each instruction reading or writing past it
+    let instructions = [
+        ([0xD0, 0x15], "DRW"),
+        ([0xF0, 0x33], "LD B"),
+        ([0xF2, 0x55], "LD [I]"),
+        ([0xF2, 0x65], "LD Vx"),
+    ];
+    for (opcode, name) in instructions {
+        let mut emulator = load(&[0xAF, 0xFF, opcode[0], opcode[1]]);
+        emulator.tick(&[0; NUM_KEYS]).unwrap();
+        let error = emulator.tick(&[0; NUM_KEYS]).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::OutOfBoundsMemoryAccess(MEMORY_SIZE), "{}", name);
+        assert_eq!(emulator.program_counter, PROGRAM_BEGIN_ADDR + 2, "{}", name);
+    }
+}
+
+#[test]
+fn wraps_the_index_register() {
+    // LD I, 0xFFF; LD V0, 0xFF; then ADD I, V0 until I wraps around
+    let mut program = vec![0xAF, 0xFF, 0x60, 0xFF];
+    program.extend([0xF0, 0x1E].repeat(300));
+    let mut emulator = load(&program);
+    for _ in 0..302 {
+        emulator.tick(&[0; NUM_KEYS]).unwrap();
+    }
+    assert_eq!(emulator.index, (0xFFF + 300 * 0xFF) as u16);
+}
@@ -0,0 +1,110 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chipinho::audio::Beeper;
+use chipinho::constants::*;
+use chipinho::error::ErrorKind;
+use chipinho::runner::{Command, Event, Runner};
+use chipinho::scheduler::Scheduler;
+use chipinho::testing::programs::BEEPER_PROGRAM;
+
+const FRAME: u64 = 16_667;
+
+// time only moves when a test says so, and the thread reads it on the command that follows
+fn runner(beeper: Option<Beeper>) -> (Runner, Arc<AtomicU64>) {
+    let time = Arc::new(AtomicU64::new(0));
+    let clock = {
+        let time = time.clone();
+        move || time.load(Ordering::SeqCst)
+    };
+    (Runner::spawn(Scheduler::new(clock), beeper), time)
+}
+
+fn next(runner: &Runner) -> Event {
+    runner.events().recv_timeout(Duration::from_secs(5)).expect("no event")
+}
+
+fn lit(event: Event) -> usize {
+    match event {
+        Event::Frame(vram) => vram.iter().filter(|pixel| **pixel != 0).count(),
+        other => panic!("expected a frame, got {:?}", other),
+    }
+}
+
+#[test]
+fn runs_frames_and_plays_the_beeper() {
+    // 100 samples per frame
+    let (runner, time) = runner(Some(Beeper::new(6000)));
+    runner.send(Command::Load(BEEPER_PROGRAM.to_vec()));
+    assert_eq!(lit(next(&runner)), 0);
+
+    runner.send(Command::KeyDown(0x5));
+    time.store(2 * FRAME, Ordering::SeqCst);
+    for _ in 0..2 {
+        match next(&runner) {
+            Event::Audio(samples) => {
+                assert_eq!(samples.len(), 100);
+                assert!(samples.contains(&BEEPER_VOLUME));
+            }
+            other => panic!("expected audio, got {:?}", other),
+        }
+    }
+    assert_eq!(lit(next(&runner)), 4);
+
+    runner.send(Command::KeyUp(0x5));
+    time.store(3 * FRAME, Ordering::SeqCst);
+    runner.send(Command::SetSpeed(100));
+    assert!(matches!(next(&runner), Event::Audio(_)));
+    assert_eq!(lit(next(&runner)), 4);
+}
+
+#[test]
+fn pauses_steps_and_reports_errors() {
+    let (runner, time) = runner(None);
+    runner.send(Command::Load(vec![0; 4000]));
+    assert_eq!(next(&runner), Event::Error(ErrorKind::NotEnoughMemoryForProgram.into()));
+
+    runner.send(Command::Load(BEEPER_PROGRAM.to_vec()));
+    runner.send(Command::Pause);
+    assert_eq!(lit(next(&runner)), 0);
+    time.store(10 * FRAME, Ordering::SeqCst);
+    runner.send(Command::Step);
+    assert_eq!(lit(next(&runner)), 4);
+
+    runner.send(Command::Load(vec![0xFF, 0xFF]));
+    assert_eq!(lit(next(&runner)), 0);
+    time.store(11 * FRAME, Ordering::SeqCst);
+    runner.send(Command::Resume);
+    assert_eq!(lit(next(&runner)), 0);
+    match next(&runner) {
+        Event::Error(err) => assert_eq!(err.kind, ErrorKind::ParseInvalidInstruction(0xFFFF)),
+        other => panic!("expected an error, got {:?}", other),
+    }
+    // paused by the fault, so the state is the next thing out
+    time.store(20 * FRAME, Ordering::SeqCst);
+    runner.send(Command::SaveState);
+    assert!(matches!(next(&runner), Event::State(_)));
+}
+
+#[test]
+fn saves_and_loads_states() {
+    let (runner, time) = runner(None);
+    runner.send(Command::Load(BEEPER_PROGRAM.to_vec()));
+    assert_eq!(lit(next(&runner)), 0);
+    runner.send(Command::SaveState);
+    let state = match next(&runner) {
+        Event::State(state) => state,
+        other => panic!("expected a state, got {:?}", other),
+    };
+    assert_eq!(state.len(), STATE_SIZE);
+
+    time.store(FRAME, Ordering::SeqCst);
+    runner.send(Command::Resume);
+    assert_eq!(lit(next(&runner)), 4);
+
+    runner.send(Command::LoadState(state[..10].to_vec()));
+    assert_eq!(next(&runner), Event::Error(ErrorKind::InvalidState.into()));
+    runner.send(Command::LoadState(state));
+    assert_eq!(lit(next(&runner)), 0);
+}
@@ -0,0 +1,169 @@
+use chipinho::constants::*;
+use chipinho::emulator::Emulator;
+use chipinho::scheduler::{Frames, ManualClock, Scheduler};
+
+const FRAME: u64 = 16_667;
+const KEYPAD: [u8; NUM_KEYS] = [0; NUM_KEYS];
+
+// 0x200: ADD V0, 1
+// 0x202: JP 0x200
+// so V0 counts up 5 per frame of 10 instructions, and the delay timer counts frames down
+fn counter() -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    emulator.delay_timer = 255;
+    emulator
+}
+
+fn frames_run(emulator: &Emulator) -> u8 {
+    255 - emulator.delay_timer
+}
+
+fn ran(ran: u32) -> Result<Frames, chipinho::error::Error> {
+    Ok(Frames { ran, dropped: 0 })
+}
+
+#[test]
+fn runs_60_frames_a_second() {
+    let mut emulator = counter();
+    let mut scheduler = Scheduler::new(ManualClock::new());
+    assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(0));
+    // a 144Hz display, then a 30Hz one
+    for _ in 0..144 {
+        scheduler.clock.advance(1_000_000 / 144);
+        scheduler.update(&mut emulator, &KEYPAD).unwrap();
+    }
+    assert_eq!(frames_run(&emulator), 60);
+    for _ in 0..30 {
+        scheduler.clock.advance(1_000_000 / 30);
+        assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(2));
+    }
+    assert_eq!(frames_run(&emulator), 120);
+    assert_eq!(emulator.get_registers()[0], (120 * 5 % 256) as u8);
+}
+
+#[test]
+fn tolerates_hosts_waking_up_a_little_early() {
+    let mut emulator = counter();
+    let mut scheduler = Scheduler::new(ManualClock::new());
+    scheduler.update(&mut emulator, &KEYPAD).unwrap();
+    scheduler.clock.advance(FRAME - 300);
+    assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(1));
+    scheduler.clock.advance(FRAME + 300);
+    assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(1));
+    // being late made up for being early, so the next frame is a whole one away (give or take rounding)
+    assert_eq!(scheduler.micros_until_next_frame(), Some(FRAME - 1));
+    scheduler.clock.advance(10_000);
+    assert_eq!(scheduler.micros_until_next_frame(), Some(FRAME - 1 - 10_000));
+}
+
+#[test]
+fn keeps_60hz_with_hosts_always_waking_up_early() {
+    // every wakeup is within the jitter tolerance, but the time borrowed adds up and is paid back
+    for interval in [16_300, 16_500, 16_900, 17_100] {
+        let mut scheduler = Scheduler::new(ManualClock::new());
+        let mut frames = scheduler.frames_due().ran;
+        while scheduler.clock.micros < 10_000_000 {
+            scheduler.clock.advance(interval);
+            let due = scheduler.frames_due();
+            assert_eq!(due.dropped, 0);
+            frames += due.ran;
+        }
+        assert!((599..=601).contains(&frames), "{} frames waking every {}us", frames, interval);
+    }
+}
+
+#[test]
+fn drops_frames_past_the_catch_up_limit() {
+    let mut emulator = counter();
+    let mut scheduler = Scheduler::new(ManualClock::new());
+    scheduler.update(&mut emulator, &KEYPAD).unwrap();
+    // the host stalled for a second
+    scheduler.clock.advance(1_000_000);
+    assert_eq!(
+        scheduler.update(&mut emulator, &KEYPAD),
+        Ok(Frames { ran: SCHEDULER_MAX_CATCH_UP_FRAMES, dropped: 60 - SCHEDULER_MAX_CATCH_UP_FRAMES })
+    );
+    scheduler.clock.advance(FRAME);
+    assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(1));
+    // and a clock going backwards is no time at all
+    scheduler.clock.set(0);
+    assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(0));
+}
+
+#[test]
+fn survives_any_speed_and_clock() {
+    let mut scheduler = Scheduler::new(ManualClock::new());
+    scheduler.set_speed(u32::MAX);
+    scheduler.frames_due();
+    scheduler.clock.advance(100_000_000);
+    assert_eq!(scheduler.micros_until_next_frame(), Some(0));
+    let frames = scheduler.frames_due();
+    assert_eq!(frames.ran, SCHEDULER_MAX_CATCH_UP_FRAMES);
+    assert!(frames.dropped > 1_000_000);
+
+    // a clock jumping to the end of time
+    scheduler.clock.set(u64::MAX);
+    assert_eq!(scheduler.micros_until_next_frame(), Some(0));
+    assert_eq!(scheduler.frames_due(), Frames { ran: SCHEDULER_MAX_CATCH_UP_FRAMES, dropped: u32::MAX });
+    assert!(scheduler.micros_until_next_frame().is_some());
+}
+
+#[test]
+fn scales_time_by_speed() {
+    let mut emulator = counter();
+    let mut scheduler = Scheduler::new(ManualClock::new());
+    scheduler.update(&mut emulator, &KEYPAD).unwrap();
+    scheduler.set_speed(200);
+    for _ in 0..30 {
+        scheduler.clock.advance(FRAME);
+        assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(2));
+    }
+    scheduler.set_speed(25);
+    let before = frames_run(&emulator);
+    for _ in 0..60 {
+        scheduler.clock.advance(FRAME);
+        scheduler.update(&mut emulator, &KEYPAD).unwrap();
+    }
+    assert_eq!(frames_run(&emulator) - before, 15);
+    assert_eq!(scheduler.micros_until_next_frame().map(|micros| micros > 2 * FRAME), Some(true));
+}
+
+#[test]
+fn pauses_and_advances_frame_by_frame() {
+    let mut emulator = counter();
+    let mut scheduler = Scheduler::new(ManualClock::new()).with_cycles_per_frame(2);
+    scheduler.update(&mut emulator, &KEYPAD).unwrap();
+    scheduler.pause();
+    assert!(scheduler.is_paused());
+    scheduler.clock.advance(10 * FRAME);
+    assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(0));
+    assert_eq!(scheduler.micros_until_next_frame(), None);
+
+    scheduler.advance_frame();
+    scheduler.clock.advance(10 * FRAME);
+    assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(1));
+    assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(0));
+    assert_eq!(emulator.get_registers()[0], 1);
+
+    // the paused time is not caught up on
+    scheduler.resume();
+    assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(0));
+    scheduler.clock.advance(FRAME);
+    assert_eq!(scheduler.update(&mut emulator, &KEYPAD), ran(1));
+    assert_eq!(frames_run(&emulator), 2);
+}
+
+#[test]
+fn stops_at_errors() {
+    let mut emulator = Emulator::new();
+    emulator.load_program(&[0xFF, 0xFF]).unwrap();
+    let mut now = 0;
+    // any closure returning microseconds is a clock
+    let mut scheduler = Scheduler::new(move || {
+        now += FRAME;
+        now
+    });
+    scheduler.update(&mut emulator, &KEYPAD).unwrap();
+    assert!(scheduler.update(&mut emulator, &KEYPAD).is_err());
+}
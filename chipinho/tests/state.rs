@@ -0,0 +1,120 @@
+use chipinho::constants::{NUM_KEYS, STATE_SIZE};
+use chipinho::emulator::{Emulator, MemoryPolicy};
+use chipinho::error::ErrorKind;
+use chipinho::random::{Pcg32, RandomGenerator, RandomKind};
+
+// 0x200: CALL 0x206
+// 0x202: RND V0, 0xFF
+// 0x204: JP 0x202
+// 0x206: LD DT, V1 (V1 = 0 until set below)
+// 0x208: DRW V0, V0, 1
+// 0x20A: RET
+const PROGRAM: [u8; 12] = [0x22, 0x06, 0xC0, 0xFF, 0x12, 0x02, 0xF1, 0x15, 0xD0, 0x01, 0x00, 0xEE];
+
+fn save(emulator: &Emulator) -> Vec<u8> {
+    let mut state = vec![0; STATE_SIZE];
+    emulator.save_state(&mut state).unwrap();
+    state
+}
+
+fn running() -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.load_program(&PROGRAM).unwrap();
+    emulator.set_random(RandomKind::Vip, 0x1234);
+    emulator.set_memory_policy(MemoryPolicy::Wrap);
+    emulator.get_registers_mut()[1] = 30;
+    for _ in 0..3 {
+        emulator.tick(&[0; NUM_KEYS]).unwrap();
+    }
+    emulator
+}
+
+#[test]
+fn restores_the_whole_machine() {
+    let mut original = running();
+    let state = save(&original);
+
+    let mut restored = Emulator::new();
+    restored.load_state(&state).unwrap();
+    assert_eq!(save(&restored), state);
+    assert_eq!(restored.program_counter, 0x20A);
+    assert_eq!(restored.get_stack(), &[0x202]);
+    assert_eq!(restored.delay_timer, 29);
+    assert_eq!(restored.get_memory_policy(), MemoryPolicy::Wrap);
+    assert_eq!(restored.get_random_kind(), Some(RandomKind::Vip));
+    assert_eq!(restored.get_vram(), original.get_vram());
+
+    // both carry on the same way, random numbers included
+    for _ in 0..100 {
+        original.tick(&[0; NUM_KEYS]).unwrap();
+        restored.tick(&[0; NUM_KEYS]).unwrap();
+    }
+    assert_eq!(save(&restored), save(&original));
+}
+
+#[test]
+fn keeps_the_pcg32_sequence() {
+    let mut original = Emulator::new();
+    original.load_program(&PROGRAM).unwrap();
+    original.set_seed(99);
+    let mut restored = Emulator::new();
+    restored.load_state(&save(&original)).unwrap();
+    for _ in 0..20 {
+        original.step(&[0; NUM_KEYS]).unwrap();
+        restored.step(&[0; NUM_KEYS]).unwrap();
+        assert_eq!(restored.get_registers()[0], original.get_registers()[0]);
+    }
+}
+
+#[test]
+fn rejects_broken_states_without_changing_anything() {
+    let state = save(&running());
+    let mut emulator = Emulator::new();
+    let before = save(&emulator);
+
+    let mut broken = vec![
+        state[..STATE_SIZE - 1].to_vec(),
+        [state.as_slice(), &[0]].concat(),
+        state.clone(),
+        state.clone(),
+        state.clone(),
+    ];
+    broken[2][0] = b'X';
+    // the version byte, then the memory policy
+    broken[3][4] = 0xFF;
+    broken[4][18] = 3;
+    for state in broken {
+        assert_eq!(emulator.load_state(&state).unwrap_err().kind, ErrorKind::InvalidState);
+        assert_eq!(save(&emulator), before);
+    }
+}
+
+#[test]
+fn needs_room_to_save() {
+    let mut state = vec![0; STATE_SIZE - 1];
+    let error = Emulator::new().save_state(&mut state).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::InvalidBufferSize);
+}
+
+#[test]
+fn keeps_custom_generators_in_place() {
+    let mut original = running();
+    original.set_rng(RandomGenerator::custom(Pcg32::new(5)));
+    let state = save(&original);
+
+    // the state only says a custom generator was in use, the one already installed carries on
+    let mut restored = Emulator::new();
+    restored.set_rng(RandomGenerator::custom(Pcg32::new(5)));
+    restored.load_state(&state).unwrap();
+    assert_eq!(restored.get_random_kind(), None);
+    for _ in 0..100 {
+        original.tick(&[0; NUM_KEYS]).unwrap();
+        restored.tick(&[0; NUM_KEYS]).unwrap();
+    }
+    assert_eq!(save(&restored), save(&original));
+
+    // a built-in generator can't stand in for it
+    let mut emulator = Emulator::new();
+    assert_eq!(emulator.load_state(&state).unwrap_err().kind, ErrorKind::InvalidState);
+    assert_eq!(emulator.get_random_kind(), Some(RandomKind::Pcg32));
+}
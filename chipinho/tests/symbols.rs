@@ -0,0 +1,70 @@
+use chipinho::error::{Error, ErrorKind};
+use chipinho::instruction::Instruction;
+use chipinho::symbols::{LoadError, Symbols};
+
+const SYMBOLS: &str = "
+    # both formats can be mixed
+    main = 0x200
+    draw_player 0x2A4
+    sprite = 768   # decimal
+";
+
+#[test]
+fn parses_both_formats() {
+    let symbols = Symbols::parse(SYMBOLS).unwrap();
+    assert_eq!(symbols.label(0x200), Some("main"));
+    assert_eq!(symbols.label(0x2A4), Some("draw_player"));
+    assert_eq!(symbols.address("sprite"), Some(0x300));
+    assert_eq!(symbols.label(0x202), None);
+}
+
+#[test]
+fn reports_the_invalid_line() {
+    assert_eq!(Symbols::parse("main = 0x200\nloop\n").unwrap_err().kind, ErrorKind::InvalidSymbolFile(2));
+    assert_eq!(Symbols::parse("two words = 0x200").unwrap_err().kind, ErrorKind::InvalidSymbolFile(1));
+    assert_eq!(Symbols::parse("main = 0x10000").unwrap_err().kind, ErrorKind::InvalidSymbolFile(1));
+}
+
+#[test]
+fn formats_locations() {
+    let symbols = Symbols::parse(SYMBOLS).unwrap();
+    assert_eq!(symbols.location(0x1FE).to_string(), "0x1FE");
+    assert_eq!(symbols.location(0x200).to_string(), "0x200 <main>");
+    assert_eq!(symbols.location(0x2A8).to_string(), "0x2A8 <draw_player+4>");
+    let error = Error::new(ErrorKind::OutOfBoundsMemoryAccess(0x302)).with_pc(0x2A6).with_opcode(0xF065);
+    assert_eq!(
+        symbols.describe_error(&error),
+        "out of bounds memory access to 0x302 <sprite+2> at 0x2A6 <draw_player+2> (opcode F065)"
+    );
+}
+
+#[test]
+fn disassembles_with_labels() {
+    let symbols = Symbols::parse(SYMBOLS).unwrap();
+    let call = Instruction::parse(0x22A4).unwrap();
+    assert_eq!(call.with_symbols(&symbols).to_string(), "CALL draw_player");
+    assert_eq!(Instruction::parse(0xA300).unwrap().with_symbols(&symbols).to_string(), "LD I, sprite");
+    // only exact matches are replaced
+    assert_eq!(Instruction::parse(0x12A6).unwrap().with_symbols(&symbols).to_string(), "JP 0x2A6");
+    assert_eq!(Instruction::parse(0x6012).unwrap().with_symbols(&symbols).to_string(), "LD V0, 0x12");
+}
+
+#[test]
+fn loads_the_symbol_file_next_to_the_rom() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("symbols");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let rom = dir.join("game.ch8");
+    assert!(Symbols::load_for_rom(&rom, None).unwrap().is_empty());
+
+    std::fs::write(dir.join("game.sym"), SYMBOLS).unwrap();
+    assert_eq!(Symbols::load_for_rom(&rom, None).unwrap().label(0x200), Some("main"));
+
+    // an explicit file has to exist and wins over the one next to the rom
+    let other = dir.join("other.sym");
+    let err = Symbols::load_for_rom(&rom, Some(&other)).unwrap_err();
+    assert!(matches!(err, LoadError::Io(ref path, _) if *path == other));
+    std::fs::write(&other, "main = 0x200\nloop\n").unwrap();
+    let err = Symbols::load_for_rom(&rom, Some(&other)).unwrap_err();
+    assert_eq!(err.to_string(), format!("{}: {}", other.display(), ErrorKind::InvalidSymbolFile(2)));
+}
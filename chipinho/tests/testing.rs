@@ -0,0 +1,24 @@
+use chipinho::constants::VRAM_SIZE;
+use chipinho::testing::compare_screen;
+
+const BLANK: [u8; VRAM_SIZE] = [0; VRAM_SIZE];
+
+#[test]
+fn rejects_expected_screens_that_do_not_fit() {
+    let wide = ".".repeat(65);
+    let tall = ".\n".repeat(33);
+    for origin in [None, Some((0, 0))] {
+        assert_eq!(
+            compare_screen(&BLANK, origin, &wide),
+            Err(String::from("expected screen of 65x1 at (0, 0) does not fit in the 64x32 display"))
+        );
+        assert_eq!(
+            compare_screen(&BLANK, origin, &tall),
+            Err(String::from("expected screen of 1x33 at (0, 0) does not fit in the 64x32 display"))
+        );
+    }
+    assert!(compare_screen(&BLANK, Some((60, 0)), "....\n....").is_ok());
+    assert!(compare_screen(&BLANK, Some((61, 0)), "....").is_err());
+    // a block the size of the display still fits
+    assert_eq!(compare_screen(&BLANK, None, &format!("{}\n", ".".repeat(64)).repeat(32)), Ok(()));
+}
@@ -0,0 +1,131 @@
+use chipinho::upscale::{scale2x, scale3x, scanlines, upscale, BufferTooSmall, Filter};
+
+// rows of `#` (lit) and `.` (off), indented along with the code
+fn pixels(rows: &str) -> Vec<u8> {
+    rows.split_whitespace().flat_map(|row| row.chars()).map(|c| (c == '#') as u8).collect()
+}
+
+fn upscaled(filter: Filter, src: &str, width: usize) -> Vec<u8> {
+    let src = pixels(src);
+    let mut dst = vec![0; src.len() * filter.factor() * filter.factor()];
+    upscale(filter, &src, width, src.len() / width, &mut dst).unwrap();
+    dst
+}
+
+const DIAGONAL: &str = "
+    #...
+    ##..
+    ###.
+    ####
+";
+
+const LONE_PIXEL: &str = "
+    ...
+    .#.
+    ...
+";
+
+#[test]
+fn scale2x_smooths_diagonal_edges() {
+    let expected = pixels(
+        "
+        ##......
+        ###.....
+        ###.....
+        #####...
+        #####...
+        #######.
+        ########
+        ########
+        ",
+    );
+    assert_eq!(upscaled(Filter::Scale2x, DIAGONAL, 4), expected);
+    // EPX is the same algorithm under other rules
+    assert_eq!(upscaled(Filter::Epx, DIAGONAL, 4), expected);
+}
+
+#[test]
+fn scale3x_smooths_diagonal_edges() {
+    let expected = pixels(
+        "
+        ###.........
+        ####........
+        ####........
+        #####.......
+        ######......
+        #######.....
+        ########....
+        #########...
+        ###########.
+        ############
+        ############
+        ############
+        ",
+    );
+    assert_eq!(upscaled(Filter::Scale3x, DIAGONAL, 4), expected);
+}
+
+#[test]
+fn keeps_lone_pixels_square() {
+    let doubled = pixels(
+        "
+        ......
+        ......
+        ..##..
+        ..##..
+        ......
+        ......
+        ",
+    );
+    assert_eq!(upscaled(Filter::Scale2x, LONE_PIXEL, 3), doubled);
+    assert_eq!(upscaled(Filter::Epx, LONE_PIXEL, 3), doubled);
+    let tripled = pixels(
+        "
+        .........
+        .........
+        .........
+        ...###...
+        ...###...
+        ...###...
+        .........
+        .........
+        .........
+        ",
+    );
+    assert_eq!(upscaled(Filter::Scale3x, LONE_PIXEL, 3), tripled);
+}
+
+#[test]
+fn darkens_every_other_row() {
+    let mut buffer = [200; 8];
+    scanlines(&mut buffer, 2, 4, 128).unwrap();
+    assert_eq!(buffer, [200, 200, 99, 99, 200, 200, 99, 99]);
+
+    let mut buffer = [200; 4];
+    scanlines(&mut buffer, 2, 2, 0).unwrap();
+    assert_eq!(buffer, [200; 4]);
+    scanlines(&mut buffer, 2, 2, u8::MAX).unwrap();
+    assert_eq!(buffer, [200, 200, 0, 0]);
+}
+
+#[test]
+fn rejects_buffers_too_small() {
+    let src = pixels(DIAGONAL);
+    let mut dst = [0; 63];
+    assert_eq!(scale2x(&src, 4, 4, &mut dst), Err(BufferTooSmall { needed: 64, len: 63 }));
+    assert_eq!(scale3x(&src, 4, 4, &mut dst), Err(BufferTooSmall { needed: 144, len: 63 }));
+    assert_eq!(
+        upscale(Filter::Epx, &src[..15], 4, 4, &mut [0; 64]),
+        Err(BufferTooSmall { needed: 16, len: 15 })
+    );
+    assert_eq!(scanlines(&mut [0; 7], 2, 4, 128), Err(BufferTooSmall { needed: 8, len: 7 }));
+    // sizes that overflow are too big for anything
+    assert_eq!(
+        scanlines(&mut [0; 8], usize::MAX, 2, 128),
+        Err(BufferTooSmall { needed: usize::MAX, len: 8 })
+    );
+    assert_eq!(
+        BufferTooSmall { needed: 64, len: 63 }.to_string(),
+        "buffer of 63 pixels is too small, 64 needed"
+    );
+}
@@ -0,0 +1,8 @@
+//! Debug Adapter Protocol server running a ROM in an `Emulator`.
+//!
+//! Breakpoints can be set by address (instruction breakpoints) or by assembler source line
+//! when the launch configuration points to a line map, see `source_map::SourceMap`.
+
+pub mod protocol;
+pub mod session;
+pub mod source_map;
@@ -0,0 +1,60 @@
+use std::io::{self, BufReader};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use dap::protocol::read_message;
+use dap::session::{Control, Session};
+
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+
+// the client talks over stdin/stdout, launch arguments:
+//   program         path to the ROM
+//   sourceMap       line map, defaults to the ROM path with a `.map` extension if it exists
+//   symbols         symbol file, defaults to the ROM path with a `.sym` extension if it exists
+//   stopOnEntry     stop before the first instruction
+//   cyclesPerFrame  instructions executed per 60Hz frame (default `DEFAULT_CYCLES_PER_FRAME`)
+pub fn main() -> io::Result<()> {
+    // messages are read on their own thread so a running program can still be paused
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = BufReader::new(io::stdin());
+        while let Ok(Some(message)) = read_message(&mut stdin) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut session = Session::new(io::stdout());
+    let mut next_frame = Instant::now();
+    loop {
+        let message = if session.is_running() {
+            match receiver.recv_timeout(next_frame.saturating_duration_since(Instant::now())) {
+                Ok(message) => Some(message),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        } else {
+            match receiver.recv() {
+                Ok(message) => Some(message),
+                Err(_) => break,
+            }
+        };
+        match message {
+            Some(message) => {
+                if let Control::Exit = session.handle(&message)? {
+                    break;
+                }
+                if !session.is_running() {
+                    next_frame = Instant::now();
+                }
+            }
+            None => {
+                session.run()?;
+                next_frame += FRAME_TIME;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,485 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chipinho::constants::{DEFAULT_CYCLES_PER_FRAME, MEMORY_SIZE, NUM_KEYS, NUM_REGISTERS};
+use chipinho::emulator::Emulator;
+use chipinho::instruction::Instruction;
+use chipinho::symbols::Symbols;
+use serde_json::{json, Value};
+
+use crate::protocol::write_message;
+use crate::source_map::{normalize, SourceMap};
+
+// there is a single thread of execution
+const THREAD_ID: u64 = 1;
+const REGISTERS_REFERENCE: u64 = 1;
+const TIMERS_REFERENCE: u64 = 2;
+
+// what ends a run, besides breakpoints and errors
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Until {
+    Breakpoint,
+    // a single instruction
+    StepIn,
+    // back to the same call depth, stepping over calls
+    StepOver(u16),
+    // back to the caller
+    StepOut(u16),
+}
+
+pub enum Control {
+    Continue,
+    Exit,
+}
+
+/// A debug session over one ROM, fed with the client messages.
+pub struct Session<W: Write> {
+    writer: W,
+    seq: u64,
+    emulator: Option<Emulator>,
+    source_map: SourceMap,
+    symbols: Symbols,
+    stop_on_entry: bool,
+    cycles_per_frame: u32,
+    cycles: u32,
+    keypad: [u8; NUM_KEYS],
+    source_breakpoints: BTreeMap<PathBuf, Vec<u16>>,
+    instruction_breakpoints: BTreeSet<u16>,
+    running: Option<Until>,
+    // events that must follow the response of the request being handled
+    pending: Vec<Value>,
+}
+
+fn parse_address(reference: &str) -> Option<u16> {
+    let reference = reference.trim();
+    match reference.strip_prefix("0x").or_else(|| reference.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => reference.parse().ok(),
+    }
+}
+
+fn format_address(addr: u16) -> String {
+    format!("0x{:03X}", addr)
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, byte)| group | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn variable(name: &str, value: String) -> Value {
+    json!({ "name": name, "value": value, "variablesReference": 0 })
+}
+
+impl<W: Write> Session<W> {
+    pub fn new(writer: W) -> Self {
+        Session {
+            writer,
+            seq: 0,
+            emulator: None,
+            source_map: SourceMap::default(),
+            symbols: Symbols::new(),
+            stop_on_entry: false,
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            cycles: 0,
+            keypad: [0; NUM_KEYS],
+            source_breakpoints: BTreeMap::new(),
+            instruction_breakpoints: BTreeSet::new(),
+            running: None,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.writer, &message)
+    }
+
+    fn event(event: &str, body: Value) -> Value {
+        json!({ "type": "event", "event": event, "body": body })
+    }
+
+    fn stopped(&mut self, reason: &str, description: Option<String>) {
+        self.running = None;
+        let mut body = json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true });
+        if let Some(description) = description {
+            body["description"] = json!(description);
+            body["text"] = json!(description);
+        }
+        self.pending.push(Session::<W>::event("stopped", body));
+    }
+
+    fn flush_events(&mut self) -> io::Result<()> {
+        for event in std::mem::take(&mut self.pending) {
+            self.send(event)?;
+        }
+        Ok(())
+    }
+
+    pub fn handle(&mut self, message: &Value) -> io::Result<Control> {
+        if message["type"] != "request" {
+            return Ok(Control::Continue);
+        }
+        let command = message["command"].as_str().unwrap_or_default();
+        let args = &message["arguments"];
+        let result = self.request(command, args);
+        let mut response = json!({
+            "type": "response",
+            "request_seq": message["seq"],
+            "command": command,
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.send(response)?;
+        self.flush_events()?;
+        Ok(match command {
+            "disconnect" | "terminate" => Control::Exit,
+            _ => Control::Continue,
+        })
+    }
+
+    fn emulator(&self) -> Result<&Emulator, String> {
+        self.emulator.as_ref().ok_or_else(|| String::from("no program launched"))
+    }
+
+    fn request(&mut self, command: &str, args: &Value) -> Result<Value, String> {
+        match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsInstructionBreakpoints": true,
+                "supportsReadMemoryRequest": true,
+                "supportsDisassembleRequest": true,
+                "supportsTerminateRequest": true,
+            })),
+            "launch" => self.launch(args),
+            "configurationDone" => {
+                self.emulator()?;
+                if self.stop_on_entry {
+                    self.stopped("entry", None);
+                } else {
+                    self.running = Some(Until::Breakpoint);
+                }
+                Ok(json!({}))
+            }
+            "setBreakpoints" => self.set_breakpoints(args),
+            "setInstructionBreakpoints" => self.set_instruction_breakpoints(args),
+            "setExceptionBreakpoints" => Ok(json!({ "breakpoints": [] })),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+            "stackTrace" => self.stack_trace(args),
+            "scopes" => Ok(json!({ "scopes": [
+                { "name": "Registers", "variablesReference": REGISTERS_REFERENCE, "expensive": false },
+                { "name": "Timers", "variablesReference": TIMERS_REFERENCE, "expensive": false },
+            ] })),
+            "variables" => self.variables(args),
+            "readMemory" => self.read_memory(args),
+            "disassemble" => self.disassemble(args),
+            "continue" => self.resume(Until::Breakpoint).map(|_| json!({ "allThreadsContinued": true })),
+            "stepIn" => self.resume(Until::StepIn).map(|_| json!({})),
+            "next" => {
+                let depth = self.emulator()?.stack_size;
+                self.resume(Until::StepOver(depth)).map(|_| json!({}))
+            }
+            "stepOut" => {
+                let depth = self.emulator()?.stack_size;
+                if depth == 0 {
+                    return Err(String::from("not inside a subroutine"));
+                }
+                self.resume(Until::StepOut(depth)).map(|_| json!({}))
+            }
+            "pause" => {
+                if self.running.is_some() {
+                    self.stopped("pause", None);
+                }
+                Ok(json!({}))
+            }
+            "disconnect" | "terminate" => {
+                self.running = None;
+                Ok(json!({}))
+            }
+            _ => Err(format!("unsupported request '{}'", command)),
+        }
+    }
+
+    fn launch(&mut self, args: &Value) -> Result<Value, String> {
+        let program = args["program"].as_str().ok_or("missing 'program'")?;
+        let program = Path::new(program);
+        let rom = std::fs::read(program).map_err(|e| format!("{}: {}", program.display(), e))?;
+        let mut emulator = Emulator::new();
+        emulator
+            .load_program(&rom)
+            .map_err(|e| format!("{}: {}", program.display(), e))?;
+        // explicit files must exist, the default ones next to the ROM are optional
+        self.source_map = match args["sourceMap"].as_str() {
+            Some(path) => SourceMap::load(Path::new(path))?,
+            None => {
+                let path = program.with_extension("map");
+                if path.exists() {
+                    SourceMap::load(&path)?
+                } else {
+                    SourceMap::default()
+                }
+            }
+        };
+        self.symbols = Symbols::load_for_rom(program, args["symbols"].as_str().map(Path::new))
+            .map_err(|e| e.to_string())?;
+        self.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+        self.cycles_per_frame = args["cyclesPerFrame"]
+            .as_u64()
+            .map_or(DEFAULT_CYCLES_PER_FRAME, |cycles| cycles.clamp(1, u32::MAX as u64) as u32);
+        self.emulator = Some(emulator);
+        self.pending.push(Session::<W>::event("initialized", json!({})));
+        Ok(json!({}))
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Result<Value, String> {
+        let path = args["source"]["path"].as_str().ok_or("missing 'source.path'")?;
+        let path = normalize(Path::new(path));
+        let mut addrs = Vec::new();
+        let mut breakpoints = Vec::new();
+        for breakpoint in args["breakpoints"].as_array().into_iter().flatten() {
+            let line = breakpoint["line"].as_u64().unwrap_or(0) as u32;
+            match self.source_map.resolve(&path, line) {
+                Some((line, lines_addrs)) => {
+                    breakpoints.push(json!({ "verified": true, "line": line }));
+                    addrs.extend(lines_addrs);
+                }
+                None => breakpoints.push(json!({
+                    "verified": false,
+                    "line": line,
+                    "message": "no code at or after this line",
+                })),
+            }
+        }
+        self.source_breakpoints.insert(path, addrs);
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn set_instruction_breakpoints(&mut self, args: &Value) -> Result<Value, String> {
+        self.instruction_breakpoints.clear();
+        let mut breakpoints = Vec::new();
+        for breakpoint in args["breakpoints"].as_array().into_iter().flatten() {
+            // labels work as references too
+            let addr = breakpoint["instructionReference"]
+                .as_str()
+                .and_then(|reference| parse_address(reference).or_else(|| self.symbols.address(reference)))
+                .map(|addr| addr as i64 + breakpoint["offset"].as_i64().unwrap_or(0))
+                .filter(|addr| (0..MEMORY_SIZE as i64).contains(addr));
+            match addr {
+                Some(addr) => {
+                    self.instruction_breakpoints.insert(addr as u16);
+                    breakpoints.push(json!({
+                        "verified": true,
+                        "instructionReference": format_address(addr as u16),
+                    }));
+                }
+                None => breakpoints.push(json!({ "verified": false, "message": "invalid address" })),
+            }
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn frame(&self, id: usize, addr: u16) -> Value {
+        let name = match self.symbols.lookup(addr) {
+            Some((label, 0)) => String::from(label),
+            Some((label, offset)) => format!("{}+{}", label, offset),
+            None => format_address(addr),
+        };
+        let mut frame = json!({
+            "id": id,
+            "name": name,
+            "line": 0,
+            "column": 0,
+            "instructionPointerReference": format_address(addr),
+        });
+        if let Some(location) = self.source_map.location(addr) {
+            frame["source"] = json!({
+                "name": location.path.file_name().map(|name| name.to_string_lossy()),
+                "path": location.path.to_string_lossy(),
+            });
+            frame["line"] = json!(location.line);
+            frame["column"] = json!(1);
+        }
+        frame
+    }
+
+    fn stack_trace(&self, args: &Value) -> Result<Value, String> {
+        let emulator = self.emulator()?;
+        // the stack holds return addresses, callers are shown at their call instruction
+        let addrs: Vec<u16> = std::iter::once(emulator.program_counter)
+            .chain(emulator.get_stack().iter().rev().map(|addr| addr.wrapping_sub(2)))
+            .collect();
+        let start = args["startFrame"].as_u64().unwrap_or(0) as usize;
+        let levels = match args["levels"].as_u64() {
+            Some(levels) if levels > 0 => levels as usize,
+            _ => addrs.len(),
+        };
+        let frames: Vec<Value> = addrs
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(levels)
+            .map(|(id, addr)| self.frame(id, *addr))
+            .collect();
+        Ok(json!({ "stackFrames": frames, "totalFrames": addrs.len() }))
+    }
+
+    fn variables(&self, args: &Value) -> Result<Value, String> {
+        let emulator = self.emulator()?;
+        let variables: Vec<Value> = match args["variablesReference"].as_u64() {
+            Some(REGISTERS_REFERENCE) => (0..NUM_REGISTERS)
+                .map(|register| {
+                    let value = emulator.get_registers()[register];
+                    variable(&format!("V{:X}", register), format!("0x{:02X}", value))
+                })
+                .chain([
+                    {
+                        let mut index = variable("I", self.symbols.location(emulator.index).to_string());
+                        index["memoryReference"] = json!(format_address(emulator.index));
+                        index
+                    },
+                    variable("PC", self.symbols.location(emulator.program_counter).to_string()),
+                    variable("SP", emulator.stack_size.to_string()),
+                ])
+                .collect(),
+            Some(TIMERS_REFERENCE) => vec![
+                variable("DT", emulator.delay_timer.to_string()),
+                variable("ST", emulator.sound_timer.to_string()),
+            ],
+            _ => return Err(String::from("unknown variables reference")),
+        };
+        Ok(json!({ "variables": variables }))
+    }
+
+    fn read_memory(&self, args: &Value) -> Result<Value, String> {
+        let memory = self.emulator()?.get_memory();
+        let reference = args["memoryReference"]
+            .as_str()
+            .and_then(parse_address)
+            .ok_or("invalid memory reference")?;
+        // anything past the ends of memory is unreadable, however far
+        let addr = (reference as i64).saturating_add(args["offset"].as_i64().unwrap_or(0));
+        let count = args["count"].as_u64().unwrap_or(0);
+        let Some(begin) = usize::try_from(addr).ok().filter(|begin| *begin < memory.len()) else {
+            return Ok(json!({ "address": format!("0x{:03X}", addr.max(0)), "unreadableBytes": count }));
+        };
+        let end = begin.saturating_add(usize::try_from(count).unwrap_or(usize::MAX)).min(memory.len());
+        let bytes = &memory[begin..end];
+        Ok(json!({
+            "address": format_address(begin as u16),
+            "data": base64(bytes),
+            "unreadableBytes": count - bytes.len() as u64,
+        }))
+    }
+
+    fn disassemble(&self, args: &Value) -> Result<Value, String> {
+        let memory = self.emulator()?.get_memory();
+        let base = args["memoryReference"]
+            .as_str()
+            .and_then(parse_address)
+            .ok_or("invalid memory reference")? as i64;
+        let begin = base
+            .saturating_add(args["offset"].as_i64().unwrap_or(0))
+            .saturating_add(args["instructionOffset"].as_i64().unwrap_or(0).saturating_mul(2));
+        // every instruction in memory with as many again of padding around it, at most
+        let count = args["instructionCount"].as_u64().unwrap_or(0).min(MEMORY_SIZE as u64) as i64;
+        let instructions: Vec<Value> = (0..count)
+            .map(|i| begin.saturating_add(2 * i))
+            .map(|addr| {
+                let opcode = usize::try_from(addr)
+                    .ok()
+                    .and_then(|addr| memory.get(addr..addr.checked_add(2)?))
+                    .map(|bytes| (bytes[0] as u16) << 8 | bytes[1] as u16);
+                let Some(opcode) = opcode else {
+                    return json!({
+                        "address": format!("0x{:03X}", addr.max(0)),
+                        "instruction": "",
+                        "presentationHint": "invalid",
+                    });
+                };
+                let mut instruction = json!({
+                    "address": format_address(addr as u16),
+                    "instructionBytes": format!("{:02X} {:02X}", opcode >> 8, opcode & 0xFF),
+                    "instruction": Instruction::parse(opcode).map_or(String::from("??"), |instruction| {
+                        instruction.with_symbols(&self.symbols).to_string()
+                    }),
+                });
+                if let Some(label) = self.symbols.label(addr as u16) {
+                    instruction["symbol"] = json!(label);
+                }
+                if let Some(location) = self.source_map.location(addr as u16) {
+                    instruction["location"] = json!({ "path": location.path.to_string_lossy() });
+                    instruction["line"] = json!(location.line);
+                }
+                instruction
+            })
+            .collect();
+        Ok(json!({ "instructions": instructions }))
+    }
+
+    fn resume(&mut self, until: Until) -> Result<(), String> {
+        self.emulator()?;
+        self.running = Some(until);
+        Ok(())
+    }
+
+    fn is_breakpoint(&self, addr: u16) -> bool {
+        self.instruction_breakpoints.contains(&addr)
+            || self.source_breakpoints.values().any(|addrs| addrs.contains(&addr))
+    }
+
+    /// Run up to a frame worth of instructions, reporting why execution stopped if it did.
+    pub fn run(&mut self) -> io::Result<()> {
+        for _ in 0..self.cycles_per_frame {
+            let (Some(until), Some(emulator)) = (self.running, self.emulator.as_mut()) else {
+                break;
+            };
+            self.cycles += 1;
+            if self.cycles >= self.cycles_per_frame {
+                self.cycles = 0;
+                emulator.update_timers();
+            }
+            let res = emulator.step(&self.keypad);
+            let (pc, depth) = (emulator.program_counter, emulator.stack_size);
+            let halted = emulator.is_halted();
+            if let Err(err) = res {
+                let description = self.symbols.describe_error(&err);
+                self.stopped("exception", Some(description));
+            } else if match until {
+                Until::Breakpoint => false,
+                Until::StepIn => true,
+                Until::StepOver(from) => depth <= from,
+                Until::StepOut(from) => depth < from,
+            } {
+                self.stopped("step", None);
+            } else if self.is_breakpoint(pc) {
+                self.stopped("breakpoint", None);
+            } else if halted {
+                self.stopped("pause", Some(String::from("program halted on a jump to itself")));
+            }
+        }
+        self.flush_events()
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub line: u32,
+}
+
+/// Maps instruction addresses to assembler source lines.
+///
+/// The file has one `<address> <source>:<line>` entry per line, e.g. `0x204 game.8o:12`,
+/// relative sources are resolved from the directory of the map. Empty lines and lines
+/// starting with `#` are ignored.
+#[derive(Default)]
+pub struct SourceMap {
+    locations: BTreeMap<u16, Location>,
+}
+
+// paths are compared in their canonical form, so the client can use any spelling of them
+pub fn normalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl SourceMap {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        SourceMap::parse(&text, base).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str, base: &Path) -> Result<Self, String> {
+        let mut locations = BTreeMap::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || format!("line {}: invalid entry '{}'", number + 1, line);
+            let (addr, source) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let addr = addr.trim_start_matches("0x").trim_start_matches("0X");
+            let addr = u16::from_str_radix(addr, 16).map_err(|_| invalid())?;
+            let (path, source_line) = source.trim().rsplit_once(':').ok_or_else(invalid)?;
+            let location = Location {
+                path: normalize(&base.join(path)),
+                line: source_line.parse().map_err(|_| invalid())?,
+            };
+            locations.insert(addr, location);
+        }
+        Ok(SourceMap { locations })
+    }
+
+    pub fn location(&self, addr: u16) -> Option<&Location> {
+        self.locations.get(&addr)
+    }
+
+    /// Find the instructions for a breakpoint on `line`, moving it down to the next line
+    /// with code when there is none. Returns the line actually used.
+    pub fn resolve(&self, path: &Path, line: u32) -> Option<(u32, Vec<u16>)> {
+        let path = normalize(path);
+        let in_file = || self.locations.iter().filter(|(_, location)| location.path == path);
+        let line = in_file()
+            .map(|(_, location)| location.line)
+            .filter(|code_line| *code_line >= line)
+            .min()?;
+        let addrs = in_file()
+            .filter(|(_, location)| location.line == line)
+            .map(|(addr, _)| *addr)
+            .collect();
+        Some((line, addrs))
+    }
+}
@@ -0,0 +1,88 @@
+// Replays the recorded sessions in `tests/sessions`: `->` lines are requests sent to the
+// adapter (`type` and `seq` are filled in), `<-` lines are the messages expected back, in
+// order. Expected messages only need to be a subset of the actual ones, so paths and other
+// machine dependent fields can be left out.
+
+use std::fs;
+use std::io::{BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use dap::protocol::{read_message, write_message};
+use serde_json::Value;
+
+fn is_subset(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|actual| is_subset(value, actual))),
+        (Value::Array(expected), Value::Array(actual)) => {
+            expected.len() == actual.len() && expected.iter().zip(actual).all(|(e, a)| is_subset(e, a))
+        }
+        _ => expected == actual,
+    }
+}
+
+fn replay(name: &str) {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let transcript = fs::read_to_string(manifest_dir.join("tests/sessions").join(name)).unwrap();
+    let mut adapter = Command::new(env!("CARGO_BIN_EXE_chipinho-dap"))
+        .current_dir(manifest_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = adapter.stdin.take().unwrap();
+    let mut stdout = BufReader::new(adapter.stdout.take().unwrap());
+
+    let mut seq = 0;
+    for (number, line) in transcript.lines().enumerate() {
+        let line = line.trim();
+        let location = format!("{}:{}", name, number + 1);
+        if let Some(request) = line.strip_prefix("->") {
+            let mut request: Value = serde_json::from_str(request).expect(&location);
+            seq += 1;
+            request["type"] = "request".into();
+            request["seq"] = seq.into();
+            write_message(&mut stdin, &request).unwrap();
+        } else if let Some(expected) = line.strip_prefix("<-") {
+            let expected: Value = serde_json::from_str(expected).expect(&location);
+            let actual = read_message(&mut stdout).unwrap();
+            let actual = actual.unwrap_or_else(|| panic!("{}: the adapter closed its output", location));
+            assert!(is_subset(&expected, &actual), "{}: expected {}\ngot {}", location, expected, actual);
+        }
+    }
+    stdin.flush().unwrap();
+    drop(stdin);
+    assert!(adapter.wait().unwrap().success());
+}
+
+#[test]
+fn source_breakpoints() {
+    replay("source_breakpoints.txt");
+}
+
+#[test]
+fn instruction_stepping() {
+    replay("instruction_stepping.txt");
+}
+
+#[test]
+fn memory_and_disassembly() {
+    replay("memory_and_disassembly.txt");
+}
+
+#[test]
+fn symbols() {
+    replay("symbols.txt");
+}
+
+#[test]
+fn errors() {
+    replay("errors.txt");
+}
+
+#[test]
+fn hostile_requests() {
+    replay("hostile_requests.txt");
+}
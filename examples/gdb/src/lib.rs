@@ -0,0 +1,323 @@
+//! GDB remote serial protocol stub exposing an `Emulator` as a debugging target.
+//!
+//! Registers (in `g`/`G` order): V0-VF (8 bits), I (16 bits), PC (16 bits),
+//! SP (16 bits, the call stack depth), DT and ST (8 bits), multi byte values are little endian.
+//! The address space is the 4KiB of emulator memory.
+
+pub mod packet;
+
+use std::collections::BTreeSet;
+use std::io;
+use std::net::TcpStream;
+
+use chipinho::constants::{MAX_STACK_SIZE, NUM_KEYS, NUM_REGISTERS};
+use chipinho::emulator::Emulator;
+use chipinho::error::ErrorKind;
+
+use packet::{from_hex, parse_number, to_hex, Connection, Incoming, MAX_PACKET_SIZE};
+
+// signals reported in stop replies
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGSEGV: u8 = 11;
+
+// how many instructions run between checks for an interrupt from the client
+const INTERRUPT_POLL_INTERVAL: u32 = 1024;
+
+const REGISTERS: [(&str, usize); NUM_REGISTERS + 5] = [
+    ("v0", 8), ("v1", 8), ("v2", 8), ("v3", 8), ("v4", 8), ("v5", 8), ("v6", 8), ("v7", 8),
+    ("v8", 8), ("v9", 8), ("va", 8), ("vb", 8), ("vc", 8), ("vd", 8), ("ve", 8), ("vf", 8),
+    ("i", 16), ("pc", 16), ("sp", 16), ("dt", 8), ("st", 8),
+];
+const REG_I: usize = NUM_REGISTERS;
+const REG_PC: usize = NUM_REGISTERS + 1;
+const REG_SP: usize = NUM_REGISTERS + 2;
+const REG_DT: usize = NUM_REGISTERS + 3;
+const REG_ST: usize = NUM_REGISTERS + 4;
+
+pub struct Target {
+    pub emulator: Emulator,
+    pub breakpoints: BTreeSet<u16>,
+    pub keypad: [u8; NUM_KEYS],
+    // the timers count down once every this many instructions
+    pub cycles_per_frame: u32,
+    cycles: u32,
+}
+
+impl Target {
+    pub fn new(emulator: Emulator, cycles_per_frame: u32) -> Self {
+        Target {
+            emulator,
+            breakpoints: BTreeSet::new(),
+            keypad: [0; NUM_KEYS],
+            cycles_per_frame: cycles_per_frame.max(1),
+            cycles: 0,
+        }
+    }
+
+    fn read_register(&self, register: usize) -> Option<Vec<u8>> {
+        let emulator = &self.emulator;
+        let value: u16 = match register {
+            0..NUM_REGISTERS => emulator.get_registers()[register] as u16,
+            REG_I => emulator.index,
+            REG_PC => emulator.program_counter,
+            REG_SP => emulator.stack_size,
+            REG_DT => emulator.delay_timer as u16,
+            REG_ST => emulator.sound_timer as u16,
+            _ => return None,
+        };
+        let bytes = value.to_le_bytes();
+        Some(bytes[..REGISTERS[register].1 / 8].to_vec())
+    }
+
+    fn write_register(&mut self, register: usize, bytes: &[u8]) -> Option<()> {
+        if bytes.len() != REGISTERS.get(register)?.1 / 8 {
+            return None;
+        }
+        let value = bytes.iter().rev().fold(0u16, |value, byte| (value << 8) | *byte as u16);
+        let emulator = &mut self.emulator;
+        match register {
+            0..NUM_REGISTERS => emulator.get_registers_mut()[register] = value as u8,
+            REG_I => emulator.index = value,
+            REG_PC => emulator.program_counter = value,
+            REG_SP if value as usize <= MAX_STACK_SIZE => emulator.stack_size = value,
+            REG_DT => emulator.delay_timer = value as u8,
+            REG_ST => emulator.sound_timer = value as u8,
+            _ => return None,
+        }
+        Some(())
+    }
+
+    // returns the signal to report if the instruction stopped the program
+    fn step(&mut self) -> Option<u8> {
+        self.cycles += 1;
+        if self.cycles >= self.cycles_per_frame {
+            self.cycles = 0;
+            self.emulator.update_timers();
+        }
+        match self.emulator.step(&self.keypad) {
+            Ok(()) => None,
+            Err(err) => match err.kind {
+                ErrorKind::OutOfBoundsMemoryAccess(_) | ErrorKind::StackOverflow(_) | ErrorKind::StackUnderflow => {
+                    Some(SIGSEGV)
+                }
+                _ => Some(SIGILL),
+            },
+        }
+    }
+
+    fn resume(&mut self, connection: &mut Connection) -> io::Result<u8> {
+        let mut executed: u32 = 0;
+        loop {
+            if let Some(signal) = self.step() {
+                return Ok(signal);
+            }
+            if self.breakpoints.contains(&self.emulator.program_counter) {
+                return Ok(SIGTRAP);
+            }
+            executed = executed.wrapping_add(1);
+            if executed.is_multiple_of(INTERRUPT_POLL_INTERVAL) && connection.poll_interrupt()? {
+                return Ok(SIGINT);
+            }
+        }
+    }
+}
+
+fn target_xml() -> String {
+    let registers: String = REGISTERS
+        .iter()
+        .map(|(name, bits)| {
+            let kind = match *name {
+                "pc" => "code_ptr",
+                "i" => "data_ptr",
+                _ if *bits == 8 => "uint8",
+                _ => "uint16",
+            };
+            format!("<reg name=\"{}\" bitsize=\"{}\" type=\"{}\"/>", name, bits, kind)
+        })
+        .collect();
+    format!(
+        "<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\">\
+         <target version=\"1.0\"><feature name=\"org.chipinho.chip8\">{}</feature></target>",
+        registers
+    )
+}
+
+// `qXfer` replies are chunks of the document, `l` marks the last one
+fn transfer(document: &str, args: &str) -> Option<String> {
+    let (offset, length) = args.split_once(',')?;
+    let offset = (parse_number(offset)? as usize).min(document.len());
+    let length = parse_number(length)? as usize;
+    let chunk = &document[offset..(offset + length).min(document.len())];
+    let more = offset + chunk.len() < document.len();
+    Some(format!("{}{}", if more { "m" } else { "l" }, chunk))
+}
+
+fn parse_breakpoint(args: &str) -> Option<u16> {
+    // <type>,<addr>,<kind>, software and hardware breakpoints are the same here
+    let mut fields = args.split(',');
+    let kind = fields.next()?;
+    if kind != "0" && kind != "1" {
+        return None;
+    }
+    let addr = parse_number(fields.next()?)?;
+    u16::try_from(addr).ok()
+}
+
+fn read_memory(emulator: &Emulator, args: &str) -> Option<String> {
+    let (addr, length) = args.split_once(',')?;
+    let addr = parse_number(addr)? as usize;
+    let length = parse_number(length)? as usize;
+    let memory = emulator.get_memory();
+    // reads past the end return what is available
+    let bytes = memory.get(addr.min(memory.len())..(addr + length).min(memory.len()))?;
+    if bytes.is_empty() && length > 0 {
+        return None;
+    }
+    Some(to_hex(bytes))
+}
+
+fn write_memory(emulator: &mut Emulator, args: &str) -> Option<()> {
+    let (location, data) = args.split_once(':')?;
+    let (addr, length) = location.split_once(',')?;
+    let addr = parse_number(addr)? as usize;
+    let bytes = from_hex(data)?;
+    if bytes.len() != parse_number(length)? as usize {
+        return None;
+    }
+    emulator
+        .get_memory_mut()
+        .get_mut(addr..addr + bytes.len())?
+        .copy_from_slice(&bytes);
+    Some(())
+}
+
+enum Reply {
+    Send(String),
+    Resume { step: bool },
+    // stop serving after sending the reply, if any
+    Close(Option<&'static str>),
+}
+
+fn handle(target: &mut Target, packet: &str) -> Reply {
+    let ok = |done: Option<()>| Reply::Send(String::from(if done.is_some() { "OK" } else { "E01" }));
+    let or_error = |reply: Option<String>| Reply::Send(reply.unwrap_or_else(|| String::from("E01")));
+    // `c` and `s` may carry the address to resume from
+    let mut resume_at = |addr: &str, step: bool| {
+        if let Some(addr) = parse_number(addr) {
+            target.emulator.program_counter = addr as u16;
+        }
+        Reply::Resume { step }
+    };
+
+    let (command, args) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+    match command {
+        "?" => Reply::Send(format!("S{:02x}", SIGTRAP)),
+        "g" => Reply::Send(
+            (0..REGISTERS.len())
+                .filter_map(|register| target.read_register(register))
+                .map(|bytes| to_hex(&bytes))
+                .collect(),
+        ),
+        "G" => ok(from_hex(args).and_then(|bytes| {
+            let mut bytes = bytes.as_slice();
+            for (register, (_, bits)) in REGISTERS.iter().enumerate() {
+                let (value, rest) = bytes.split_at_checked(bits / 8)?;
+                target.write_register(register, value)?;
+                bytes = rest;
+            }
+            Some(())
+        })),
+        "p" => or_error(
+            parse_number(args)
+                .and_then(|register| target.read_register(register as usize))
+                .map(|bytes| to_hex(&bytes)),
+        ),
+        "P" => ok(args.split_once('=').and_then(|(register, value)| {
+            target.write_register(parse_number(register)? as usize, &from_hex(value)?)
+        })),
+        "m" => or_error(read_memory(&target.emulator, args)),
+        "M" => ok(write_memory(&mut target.emulator, args)),
+        "c" => resume_at(args, false),
+        "s" => resume_at(args, true),
+        "Z" => ok(parse_breakpoint(args).map(|addr| {
+            target.breakpoints.insert(addr);
+        })),
+        "z" => ok(parse_breakpoint(args).map(|addr| {
+            target.breakpoints.remove(&addr);
+        })),
+        // there is a single thread
+        "H" | "T" => Reply::Send(String::from("OK")),
+        "D" => Reply::Close(Some("OK")),
+        "k" => Reply::Close(None),
+        _ => match packet {
+            _ if packet.starts_with("qSupported") => {
+                Reply::Send(format!(
+                    "PacketSize={:x};qXfer:features:read+;swbreak+;hwbreak+;QStartNoAckMode+",
+                    MAX_PACKET_SIZE
+                ))
+            }
+            "QStartNoAckMode" => Reply::Send(String::from("OK")),
+            _ if packet.starts_with("qXfer:features:read:target.xml:") => {
+                or_error(transfer(&target_xml(), &packet["qXfer:features:read:target.xml:".len()..]))
+            }
+            "qAttached" => Reply::Send(String::from("1")),
+            "qC" => Reply::Send(String::from("QC1")),
+            "qfThreadInfo" => Reply::Send(String::from("m1")),
+            "qsThreadInfo" => Reply::Send(String::from("l")),
+            "vCont?" => Reply::Send(String::from("vCont;c;C;s;S")),
+            _ if packet.starts_with("vCont;") => {
+                // a single thread, so only the first action matters
+                let step = matches!(packet.as_bytes().get("vCont;".len()), Some(b's') | Some(b'S'));
+                Reply::Resume { step }
+            }
+            // empty replies tell the client a packet is not supported
+            _ => Reply::Send(String::new()),
+        },
+    }
+}
+
+/// Serve one client until it detaches, kills the target or disconnects.
+pub fn serve(stream: TcpStream, target: &mut Target) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let mut connection = Connection::new(stream);
+    while let Some(incoming) = connection.read()? {
+        let packet = match incoming {
+            Incoming::Packet(packet) => packet,
+            // interrupting while already stopped
+            Incoming::Interrupt => {
+                connection.send(&format!("S{:02x}", SIGINT))?;
+                continue;
+            }
+            Incoming::TooLarge => {
+                connection.send("E01")?;
+                continue;
+            }
+        };
+        match handle(target, &packet) {
+            Reply::Send(reply) => {
+                connection.send(&reply)?;
+                // the `OK` itself is still acknowledged
+                if packet == "QStartNoAckMode" {
+                    connection.disable_ack();
+                }
+            }
+            Reply::Resume { step } => {
+                let signal = if step {
+                    target.step().unwrap_or(SIGTRAP)
+                } else {
+                    target.resume(&mut connection)?
+                };
+                connection.send(&format!("S{:02x}", signal))?;
+            }
+            Reply::Close(reply) => {
+                if let Some(reply) = reply {
+                    connection.send(reply)?;
+                }
+                break;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::env;
+use std::net::TcpListener;
+
+use chipinho::constants::DEFAULT_CYCLES_PER_FRAME;
+use chipinho::emulator::Emulator;
+
+use gdb::{serve, Target};
+
+const DEFAULT_PORT: u16 = 1234;
+
+fn usage() -> String {
+    format!(
+        "usage: chipinho-gdb <rom> [--port <n>] [--cycles <n>]
+
+listens on 127.0.0.1:<port> (default {}) for gdb or lldb, e.g. `target remote :{}`.
+the timers count down once every <cycles> instructions (default {})",
+        DEFAULT_PORT, DEFAULT_PORT, DEFAULT_CYCLES_PER_FRAME
+    )
+}
+
+pub fn main() -> Result<(), String> {
+    let mut filename = None;
+    let mut port = DEFAULT_PORT;
+    let mut cycles = DEFAULT_CYCLES_PER_FRAME;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                port = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(usage)?
+            }
+            "--cycles" => {
+                cycles = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(usage)?
+            }
+            _ if filename.is_none() && !arg.starts_with("--") => filename = Some(arg),
+            _ => return Err(usage()),
+        }
+    }
+    let filename = filename.ok_or_else(usage)?;
+    let program = std::fs::read(&filename).map_err(|e| e.to_string())?;
+
+    let mut emulator = Emulator::new();
+    emulator
+        .load_program(&program)
+        .map_err(|e| format!("error loading program: {}", e))?;
+    let mut target = Target::new(emulator, cycles);
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|e| e.to_string())?;
+    println!("waiting for a debugger on 127.0.0.1:{}", port);
+    // the program keeps its state between connections, so a client can detach and come back
+    for stream in listener.incoming() {
+        let stream = stream.map_err(|e| e.to_string())?;
+        println!("debugger connected");
+        if let Err(e) = serve(stream, &mut target) {
+            println!("connection error: {}", e);
+        }
+        println!("debugger disconnected");
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+
+const INTERRUPT: u8 = 0x03;
+
+/// The largest packet accepted, advertised to the client in `qSupported`.
+pub const MAX_PACKET_SIZE: usize = 0x1000;
+
+pub enum Incoming {
+    Packet(String),
+    // the client pressed ctrl-c
+    Interrupt,
+    // a packet over `MAX_PACKET_SIZE`, its data is dropped
+    TooLarge,
+}
+
+/// Framing of the remote serial protocol: `$<data>#<checksum>` packets, `+`/`-` acks
+/// and the out of band interrupt byte.
+pub struct Connection {
+    stream: TcpStream,
+    ack: bool,
+    buffer: [u8; 4096],
+    begin: usize,
+    end: usize,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> Self {
+        Connection {
+            stream,
+            ack: true,
+            buffer: [0; 4096],
+            begin: 0,
+            end: 0,
+        }
+    }
+
+    pub fn disable_ack(&mut self) {
+        self.ack = false;
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        if self.begin == self.end {
+            self.begin = 0;
+            self.end = self.stream.read(&mut self.buffer)?;
+            if self.end == 0 {
+                return Ok(None);
+            }
+        }
+        self.begin += 1;
+        Ok(Some(self.buffer[self.begin - 1]))
+    }
+
+    /// Wait for the next packet or interrupt, `None` once the client is gone.
+    pub fn read(&mut self) -> io::Result<Option<Incoming>> {
+        loop {
+            match self.read_byte()? {
+                None => return Ok(None),
+                Some(INTERRUPT) => return Ok(Some(Incoming::Interrupt)),
+                Some(b'$') => {}
+                // acks for our packets and noise between packets
+                Some(_) => continue,
+            }
+            // the checksum covers the bytes past the limit too, which are counted but not kept
+            let mut data = Vec::new();
+            let mut sum: u8 = 0;
+            let mut too_large = false;
+            loop {
+                match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(byte) => {
+                        sum = sum.wrapping_add(byte);
+                        too_large |= data.len() == MAX_PACKET_SIZE;
+                        if !too_large {
+                            data.push(byte);
+                        }
+                    }
+                }
+            }
+            let mut checksum = [0; 2];
+            for digit in checksum.iter_mut() {
+                *digit = match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(byte) => byte,
+                };
+            }
+            let valid = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|checksum| u8::from_str_radix(checksum, 16).ok())
+                == Some(sum);
+            if self.ack {
+                self.stream.write_all(if valid { b"+" } else { b"-" })?;
+            }
+            if !valid {
+                // the client sends it again after a `-`
+                continue;
+            }
+            if too_large {
+                return Ok(Some(Incoming::TooLarge));
+            }
+            return Ok(Some(Incoming::Packet(String::from_utf8_lossy(&data).into_owned())));
+        }
+    }
+
+    /// Check, without blocking, whether the client sent an interrupt.
+    pub fn poll_interrupt(&mut self) -> io::Result<bool> {
+        loop {
+            // acks can be left over from our last reply
+            while self.begin < self.end && matches!(self.buffer[self.begin], b'+' | b'-') {
+                self.begin += 1;
+            }
+            if self.begin < self.end {
+                let interrupted = self.buffer[self.begin] == INTERRUPT;
+                if interrupted {
+                    self.begin += 1;
+                }
+                return Ok(interrupted);
+            }
+            self.stream.set_nonblocking(true)?;
+            let res = self.stream.read(&mut self.buffer);
+            self.stream.set_nonblocking(false)?;
+            match res {
+                // the client is gone, which the next `read` reports
+                Ok(0) => return Ok(false),
+                Ok(read) => {
+                    self.begin = 0;
+                    self.end = read;
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn send(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        self.stream.write_all(packet.as_bytes())?;
+        self.stream.flush()
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|begin| hex.get(begin..begin + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
+
+pub fn parse_number(hex: &str) -> Option<u32> {
+    u32::from_str_radix(hex, 16).ok()
+}
@@ -0,0 +1,177 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+
+use chipinho::constants::DEFAULT_CYCLES_PER_FRAME;
+use chipinho::emulator::Emulator;
+use gdb::{serve, Target};
+
+// 0x200: LD V0, 0x2A
+// 0x202: LD I, 0x300
+// 0x204: ADD V0, 0x01
+// 0x206: JP 0x204
+const PROGRAM: [u8; 8] = [0x60, 0x2A, 0xA3, 0x00, 0x70, 0x01, 0x12, 0x04];
+
+struct Client {
+    stream: TcpStream,
+    server: JoinHandle<Target>,
+}
+
+impl Client {
+    fn connect(program: &[u8]) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut emulator = Emulator::new();
+        emulator.load_program(program).unwrap();
+        let server = thread::spawn(move || {
+            let mut target = Target::new(emulator, DEFAULT_CYCLES_PER_FRAME);
+            let (stream, _) = listener.accept().unwrap();
+            serve(stream, &mut target).unwrap();
+            target
+        });
+        let stream = TcpStream::connect(addr).unwrap();
+        stream.set_nodelay(true).unwrap();
+        Client { stream, server }
+    }
+
+    fn read_byte(&mut self) -> u8 {
+        let mut byte = [0];
+        self.stream.read_exact(&mut byte).unwrap();
+        byte[0]
+    }
+
+    fn receive(&mut self) -> String {
+        while self.read_byte() != b'$' {}
+        let mut data = Vec::new();
+        loop {
+            match self.read_byte() {
+                b'#' => break,
+                byte => data.push(byte),
+            }
+        }
+        let checksum = [self.read_byte(), self.read_byte()];
+        let checksum = u8::from_str_radix(std::str::from_utf8(&checksum).unwrap(), 16).unwrap();
+        assert_eq!(checksum, data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)));
+        self.stream.write_all(b"+").unwrap();
+        String::from_utf8(data).unwrap()
+    }
+
+    fn send(&mut self, data: &str) {
+        let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        write!(self.stream, "${}#{:02x}", data, checksum).unwrap();
+        assert_eq!(self.read_byte(), b'+');
+    }
+
+    fn request(&mut self, data: &str) -> String {
+        self.send(data);
+        self.receive()
+    }
+
+    fn detach(mut self) -> Target {
+        assert_eq!(self.request("D"), "OK");
+        self.server.join().unwrap()
+    }
+}
+
+#[test]
+fn reports_registers_and_memory() {
+    let mut client = Client::connect(&PROGRAM);
+    assert!(client.request("qSupported:swbreak+").contains("qXfer:features:read+"));
+    assert_eq!(client.request("?"), "S05");
+    // V0-VF, I, PC 0x200, SP, DT, ST
+    assert_eq!(client.request("g"), format!("{}{}0002{}0000", "00".repeat(16), "0000", "0000"));
+    assert_eq!(client.request("m200,4"), "602aa300");
+    assert_eq!(client.request("p11"), "0002");
+    assert_eq!(client.request("p15"), "E01");
+    client.detach();
+}
+
+#[test]
+fn steps_and_writes_state() {
+    let mut client = Client::connect(&PROGRAM);
+    assert_eq!(client.request("s"), "S05");
+    assert_eq!(client.request("p0"), "2a");
+    assert_eq!(client.request("p11"), "0202");
+    assert_eq!(client.request("P0=10"), "OK");
+    assert_eq!(client.request("M300,2:abcd"), "OK");
+    assert_eq!(client.request("Mfff,2:abcd"), "E01");
+    let target = client.detach();
+    assert_eq!(target.emulator.get_registers()[0], 0x10);
+    assert_eq!(target.emulator.get_memory()[0x300..0x302], [0xAB, 0xCD]);
+}
+
+#[test]
+fn stops_at_breakpoints() {
+    let mut client = Client::connect(&PROGRAM);
+    assert_eq!(client.request("Z0,206,2"), "OK");
+    assert_eq!(client.request("c"), "S05");
+    assert_eq!(client.request("p11"), "0602");
+    assert_eq!(client.request("p0"), "2b");
+    // resuming from a breakpoint executes the instruction under it
+    assert_eq!(client.request("c"), "S05");
+    assert_eq!(client.request("p0"), "2c");
+    assert_eq!(client.request("z0,206,2"), "OK");
+    let target = client.detach();
+    assert!(target.breakpoints.is_empty());
+}
+
+#[test]
+fn interrupts_a_running_program() {
+    let mut client = Client::connect(&PROGRAM);
+    assert_eq!(client.request("QStartNoAckMode"), "OK");
+    client.stream.write_all(b"$c#63").unwrap();
+    client.stream.write_all(&[0x03]).unwrap();
+    assert_eq!(client.receive(), "S02");
+    client.stream.write_all(b"$D#44").unwrap();
+    assert_eq!(client.receive(), "OK");
+    client.server.join().unwrap();
+}
+
+#[test]
+fn reports_invalid_instructions() {
+    let mut client = Client::connect(&[0xF0, 0xFF]);
+    assert_eq!(client.request("c"), "S04");
+    client.detach();
+}
+
+#[test]
+fn serves_target_description() {
+    let mut client = Client::connect(&PROGRAM);
+    let mut document = String::new();
+    loop {
+        let reply = client.request(&format!("qXfer:features:read:target.xml:{:x},80", document.len()));
+        let (more, chunk) = reply.split_at(1);
+        document.push_str(chunk);
+        if more == "l" {
+            break;
+        }
+        assert_eq!(more, "m");
+    }
+    assert!(document.contains("<feature name=\"org.chipinho.chip8\">"));
+    assert!(document.contains("<reg name=\"pc\" bitsize=\"16\" type=\"code_ptr\"/>"));
+    assert_eq!(client.request("vMustReplyEmpty"), "");
+    client.detach();
+}
+
+#[test]
+fn asks_again_for_corrupt_packets() {
+    let mut client = Client::connect(&PROGRAM);
+    // far more retries than a recursive read could take
+    for _ in 0..100_000 {
+        client.stream.write_all(b"$?#00").unwrap();
+        assert_eq!(client.read_byte(), b'-');
+    }
+    assert_eq!(client.request("?"), "S05");
+    client.detach();
+}
+
+#[test]
+fn rejects_packets_over_the_advertised_size() {
+    let mut client = Client::connect(&PROGRAM);
+    let supported = client.request("qSupported");
+    let size = supported.split(';').find_map(|feature| feature.strip_prefix("PacketSize=")).unwrap();
+    let size = usize::from_str_radix(size, 16).unwrap();
+    assert_eq!(client.request(&format!("M300,1:{}", "a".repeat(size))), "E01");
+    assert_eq!(client.request("m300,1"), "00");
+    client.detach();
+}
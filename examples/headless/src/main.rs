@@ -0,0 +1,364 @@
+mod screenshot;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use chipinho::constants::{
+    DEFAULT_CYCLES_PER_FRAME, DEFAULT_HISTORY_SIZE, DEFAULT_RANDOM_SEED, NUM_KEYS, NUM_REGISTERS,
+};
+use chipinho::emulator::{Emulator, MemoryPolicy};
+use chipinho::error::Error;
+use chipinho::history::Recorder;
+use chipinho::random::RandomKind;
+use chipinho::symbols::Symbols;
+
+fn usage() -> String {
+    format!(
+        "usage: chipinho-headless <rom> [options]
+
+options:
+  --frames <n>                      maximum number of frames to run (default 600)
+  --cycles <n>                      instructions executed per frame (default {})
+  --until-halt                      stop once the program halts: it jumps to itself, or
+                                    waits for a key and no more input is scripted
+  --press <frame>:<key>[:<frames>]  hold hex <key> from <frame> on for <frames> frames (default 5)
+  --input <file>                    read --press entries from <file>, one per line
+  --screenshot <path>               save the final screen, format is picked by extension:
+                                    .png, .pbm or anything else for ascii art
+  --scale <n>                       pixel size of png and pbm screenshots, 1 to {} (default 1)
+  --dump <path>                     write the final emulator state to <path> (- for stdout)
+  --trace <path>                    write every executed instruction to <path> (- for stdout)
+  --crash-report <path>             on emulator errors, write a report with the last instructions to
+                                    <path> and the same report as json next to it (.json extension)
+  --symbols <path>                  label addresses using the symbol file at <path>, by default
+                                    the rom path with a .sym extension is used when it exists
+  --memory <policy>                 what happens on accesses past the end of memory: strict
+                                    (error, default), wrap (at 4 KiB) or clamp (skip them)
+  --random <generator>              generator for RND: pcg32 (default), legacy (the old 8 bit LCG)
+                                    or vip (the COSMAC VIP routine)
+  --seed <n>                        seed for the random generator (default {})
+
+exit code is 0 on success, 1 on bad usage or io errors and 2 on emulator errors",
+        DEFAULT_CYCLES_PER_FRAME,
+        screenshot::MAX_SCALE,
+        DEFAULT_RANDOM_SEED
+    )
+}
+
+const EXIT_USAGE: u8 = 1;
+const EXIT_EMULATOR: u8 = 2;
+
+struct KeyPress {
+    frame: u64,
+    key: usize,
+    frames: u64,
+}
+
+impl KeyPress {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = spec.trim().split(':').collect();
+        let invalid = || format!("invalid key press '{}'", spec.trim());
+        let (frame, key, frames) = match fields.as_slice() {
+            [frame, key] => (frame, key, "5"),
+            [frame, key, frames] => (frame, key, *frames),
+            _ => return Err(invalid()),
+        };
+        let key = usize::from_str_radix(key, 16).map_err(|_| invalid())?;
+        if key >= NUM_KEYS {
+            return Err(invalid());
+        }
+        Ok(KeyPress {
+            frame: frame.parse().map_err(|_| invalid())?,
+            key,
+            frames: frames.parse().map_err(|_| invalid())?,
+        })
+    }
+
+    fn is_held(&self, frame: u64) -> bool {
+        frame >= self.frame && frame < self.frame.saturating_add(self.frames)
+    }
+}
+
+struct Options {
+    rom: PathBuf,
+    frames: u64,
+    cycles: u32,
+    until_halt: bool,
+    presses: Vec<KeyPress>,
+    screenshots: Vec<PathBuf>,
+    scale: usize,
+    dump: Option<PathBuf>,
+    trace: Option<PathBuf>,
+    crash_report: Option<PathBuf>,
+    symbols: Option<PathBuf>,
+    memory_policy: MemoryPolicy,
+    random: RandomKind,
+    seed: u64,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            rom: PathBuf::new(),
+            frames: 600,
+            cycles: DEFAULT_CYCLES_PER_FRAME,
+            until_halt: false,
+            presses: Vec::new(),
+            screenshots: Vec::new(),
+            scale: 1,
+            dump: None,
+            trace: None,
+            crash_report: None,
+            symbols: None,
+            memory_policy: MemoryPolicy::Strict,
+            random: RandomKind::Pcg32,
+            seed: DEFAULT_RANDOM_SEED,
+        };
+        let mut rom = None;
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+            match arg.as_str() {
+                "--frames" => options.frames = value()?.parse().map_err(|e| format!("--frames: {}", e))?,
+                "--cycles" => options.cycles = value()?.parse().map_err(|e| format!("--cycles: {}", e))?,
+                "--until-halt" => options.until_halt = true,
+                "--press" => options.presses.push(KeyPress::parse(&value()?)?),
+                "--input" => {
+                    let path = value()?;
+                    let script = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+                    for line in script.lines().map(|line| line.split('#').next().unwrap_or("").trim()) {
+                        if !line.is_empty() {
+                            options.presses.push(KeyPress::parse(line)?);
+                        }
+                    }
+                }
+                "--screenshot" => options.screenshots.push(PathBuf::from(value()?)),
+                "--scale" => {
+                    options.scale = value()?
+                        .parse()
+                        .ok()
+                        .filter(|scale| (1..=screenshot::MAX_SCALE).contains(scale))
+                        .ok_or_else(|| format!("--scale: must be from 1 to {}", screenshot::MAX_SCALE))?
+                }
+                "--dump" => options.dump = Some(PathBuf::from(value()?)),
+                "--trace" => options.trace = Some(PathBuf::from(value()?)),
+                "--crash-report" => options.crash_report = Some(PathBuf::from(value()?)),
+                "--symbols" => options.symbols = Some(PathBuf::from(value()?)),
+                "--memory" => {
+                    options.memory_policy = match value()?.as_str() {
+                        "strict" => MemoryPolicy::Strict,
+                        "wrap" => MemoryPolicy::Wrap,
+                        "clamp" => MemoryPolicy::Clamp,
+                        other => return Err(format!("--memory: unknown policy '{}'", other)),
+                    }
+                }
+                "--random" => {
+                    options.random = match value()?.as_str() {
+                        "pcg32" => RandomKind::Pcg32,
+                        "legacy" => RandomKind::Legacy,
+                        "vip" => RandomKind::Vip,
+                        other => return Err(format!("--random: unknown generator '{}'", other)),
+                    }
+                }
+                "--seed" => options.seed = value()?.parse().map_err(|e| format!("--seed: {}", e))?,
+                "--help" | "-h" => return Err(String::new()),
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+                _ if rom.is_none() => rom = Some(PathBuf::from(arg)),
+                _ => return Err(format!("unexpected argument {}", arg)),
+            }
+        }
+        options.rom = rom.ok_or_else(|| String::from("need a filename"))?;
+        Ok(options)
+    }
+
+    fn has_input_after(&self, frame: u64) -> bool {
+        self.presses.iter().any(|press| press.frame.saturating_add(press.frames) > frame)
+    }
+}
+
+fn dump_state<W: Write>(mut writer: W, emulator: &Emulator, symbols: &Symbols, frame: u64) -> io::Result<()> {
+    writeln!(writer, "frame: {}", frame)?;
+    writeln!(writer, "program counter: {}", symbols.location(emulator.program_counter))?;
+    match emulator.current_instruction() {
+        Ok(instruction) => writeln!(
+            writer,
+            "instruction: {} ({})",
+            instruction.to_str(),
+            instruction.with_symbols(symbols)
+        )?,
+        Err(err) => writeln!(writer, "instruction: {}", symbols.describe_error(&err))?,
+    }
+    writeln!(writer, "index: {}", symbols.location(emulator.index))?;
+    writeln!(writer, "delay timer: {}", emulator.delay_timer)?;
+    writeln!(writer, "sound timer: {}", emulator.sound_timer)?;
+    writeln!(writer, "waiting key: {:?}", emulator.waiting_key)?;
+    for (index, register) in emulator.get_registers().iter().enumerate().take(NUM_REGISTERS) {
+        writeln!(writer, "V{:X}: 0x{:02X}", index, register)?;
+    }
+    let stack: Vec<String> = emulator
+        .get_stack()
+        .iter()
+        .map(|addr| symbols.location(*addr).to_string())
+        .collect();
+    writeln!(writer, "stack: [{}]", stack.join(", "))?;
+    writeln!(writer, "memory:")?;
+    for (line, bytes) in emulator.get_memory().chunks(16).enumerate() {
+        let bytes: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        writeln!(writer, "0x{:04X}: {}", line * 16, bytes.join(" "))?;
+    }
+    Ok(())
+}
+
+// `-` is stdout
+fn create_output(path: &Path) -> Result<Box<dyn Write>, String> {
+    if path.as_os_str() == "-" {
+        return Ok(Box::new(io::stdout().lock()));
+    }
+    let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(Box::new(BufWriter::new(file)))
+}
+
+fn write_dump(path: &Path, emulator: &Emulator, symbols: &Symbols, frame: u64) -> Result<(), String> {
+    let mut writer = create_output(path)?;
+    dump_state(&mut writer, emulator, symbols, frame)
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// same as `Emulator::run_frame`, writing each instruction before it runs
+fn run_traced_frame(
+    recorder: &mut Recorder<DEFAULT_HISTORY_SIZE>,
+    keypad: &[u8],
+    cycles: u32,
+    symbols: &Symbols,
+    frame: u64,
+    trace: &mut dyn Write,
+) -> io::Result<Result<(), Error>> {
+    recorder.emulator.update_timers();
+    for _ in 0..cycles {
+        let location = symbols.location(recorder.emulator.program_counter).to_string();
+        match recorder.emulator.current_instruction() {
+            Ok(instruction) => {
+                writeln!(trace, "{:6}  {:<24} {}", frame, location, instruction.with_symbols(symbols))?
+            }
+            Err(_) => writeln!(trace, "{:6}  {:<24} ???", frame, location)?,
+        }
+        if let Err(err) = recorder.step(keypad) {
+            return Ok(Err(err));
+        }
+    }
+    Ok(Ok(()))
+}
+
+fn write_crash_report(
+    path: &Path,
+    recorder: &Recorder<DEFAULT_HISTORY_SIZE>,
+    error: Error,
+    symbols: &Symbols,
+) -> Result<(), String> {
+    let report = recorder.crash_report(error);
+    let mut text = String::new();
+    let mut json = String::new();
+    report
+        .write_text(&mut text, symbols)
+        .and_then(|_| report.write_json(&mut json))
+        .map_err(|e| e.to_string())?;
+    let json_path = path.with_extension("json");
+    std::fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    std::fs::write(&json_path, json).map_err(|e| format!("{}: {}", json_path.display(), e))
+}
+
+// an explicit symbol file must exist, the one next to the rom is optional
+fn run(options: &Options) -> Result<Option<String>, String> {
+    let program = std::fs::read(&options.rom).map_err(|e| format!("{}: {}", options.rom.display(), e))?;
+    let symbols = Symbols::load_for_rom(&options.rom, options.symbols.as_deref()).map_err(|e| e.to_string())?;
+
+    let mut emulator = Emulator::new();
+    emulator.set_memory_policy(options.memory_policy);
+    emulator.set_random(options.random, options.seed);
+    if let Err(err) = emulator.load_program(&program) {
+        return Ok(Some(format!("error loading program: {} (0x{:08X})", err, u32::from(err))));
+    }
+
+    let mut recorder: Recorder<DEFAULT_HISTORY_SIZE> = Recorder::new(emulator);
+    let mut trace = match options.trace.as_ref() {
+        Some(path) => Some((path, create_output(path)?)),
+        None => None,
+    };
+    let mut keypad: [u8; NUM_KEYS] = [0; NUM_KEYS];
+    let mut emulator_error = None;
+    let mut frame = 0;
+    while frame < options.frames {
+        keypad
+            .iter_mut()
+            .enumerate()
+            .for_each(|(key, pressed)| {
+                *pressed = options
+                    .presses
+                    .iter()
+                    .any(|press| press.key == key && press.is_held(frame)) as u8
+            });
+        let res = match trace.as_mut() {
+            Some((path, trace)) => run_traced_frame(&mut recorder, &keypad, options.cycles, &symbols, frame, trace)
+                .map_err(|e| format!("{}: {}", path.display(), e))?,
+            None => recorder.run_frame(&keypad, options.cycles),
+        };
+        frame += 1;
+        if let Err(err) = res {
+            emulator_error = Some(format!(
+                "error on frame {}: {} (0x{:08X})",
+                frame,
+                symbols.describe_error(&err),
+                u32::from(err)
+            ));
+            if let Some(path) = options.crash_report.as_ref() {
+                write_crash_report(path, &recorder, err, &symbols)?;
+            }
+            break;
+        }
+        if options.until_halt
+            && (recorder.emulator.is_halted()
+                || (recorder.emulator.waiting_key.is_some() && !options.has_input_after(frame)))
+        {
+            break;
+        }
+    }
+    if let Some((path, mut trace)) = trace {
+        trace.flush().map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+
+    let emulator = recorder.emulator;
+    for path in options.screenshots.iter() {
+        screenshot::save(path, emulator.get_vram(), options.scale)?;
+    }
+    if let Some(path) = options.dump.as_ref() {
+        write_dump(path, &emulator, &symbols, frame)?;
+    }
+    Ok(emulator_error)
+}
+
+pub fn main() -> ExitCode {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(msg) => {
+            if !msg.is_empty() {
+                eprintln!("{}\n", msg);
+            }
+            eprintln!("{}", usage());
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    match run(&options) {
+        Ok(None) => ExitCode::SUCCESS,
+        Ok(Some(emulator_error)) => {
+            eprintln!("{}", emulator_error);
+            ExitCode::from(EXIT_EMULATOR)
+        }
+        Err(msg) => {
+            eprintln!("{}", msg);
+            ExitCode::from(EXIT_USAGE)
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use chipinho::constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+/// The largest `--scale`, a 4096x2048 image.
+pub const MAX_SCALE: usize = 64;
+
+pub enum Format {
+    Png,
+    Pbm,
+    Ascii,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => Format::Png,
+            Some("pbm") => Format::Pbm,
+            _ => Format::Ascii,
+        }
+    }
+}
+
+pub fn save(path: &Path, vram: &[u8], scale: usize) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    match Format::from_path(path) {
+        Format::Png => write_png(&mut writer, vram, scale),
+        Format::Pbm => write_pbm(&mut writer, vram, scale),
+        Format::Ascii => write_ascii(&mut writer, vram),
+    }
+    .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// each pixel of the screen becomes a `scale` x `scale` square
+fn scaled_rows(vram: &[u8], scale: usize) -> impl Iterator<Item = Vec<bool>> + '_ {
+    vram.chunks(DISPLAY_WIDTH as usize)
+        .take(DISPLAY_HEIGHT as usize)
+        .flat_map(move |row| {
+            let scaled: Vec<bool> = row
+                .iter()
+                .flat_map(|pixel| std::iter::repeat_n(*pixel != 0, scale))
+                .collect();
+            std::iter::repeat_n(scaled, scale)
+        })
+}
+
+fn write_png<W: Write>(writer: W, vram: &[u8], scale: usize) -> Result<(), String> {
+    let mut encoder = png::Encoder::new(
+        writer,
+        (DISPLAY_WIDTH as usize * scale) as u32,
+        (DISPLAY_HEIGHT as usize * scale) as u32,
+    );
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let data: Vec<u8> = scaled_rows(vram, scale)
+        .flatten()
+        .map(|lit| if lit { u8::MAX } else { 0 })
+        .collect();
+    encoder
+        .write_header()
+        .and_then(|mut png_writer| png_writer.write_image_data(&data))
+        .map_err(|e| e.to_string())
+}
+
+// plain (P1) pbm, lit pixels are written as 1 (black)
+fn write_pbm<W: Write>(mut writer: W, vram: &[u8], scale: usize) -> Result<(), String> {
+    writeln!(
+        writer,
+        "P1\n{} {}",
+        DISPLAY_WIDTH as usize * scale,
+        DISPLAY_HEIGHT as usize * scale
+    )
+    .map_err(|e| e.to_string())?;
+    for row in scaled_rows(vram, scale) {
+        let line: Vec<&str> = row.iter().map(|lit| if *lit { "1" } else { "0" }).collect();
+        writeln!(writer, "{}", line.join(" ")).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn write_ascii<W: Write>(mut writer: W, vram: &[u8]) -> Result<(), String> {
+    for row in scaled_rows(vram, 1) {
+        let line: String = row.iter().map(|lit| if *lit { '#' } else { '.' }).collect();
+        writeln!(writer, "{}", line).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
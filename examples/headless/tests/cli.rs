@@ -0,0 +1,131 @@
+//! Runs the `chipinho-headless` binary on the ROMs at `test_files/` and checks its exit code and outputs.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const EXIT_USAGE: i32 = 1;
+const EXIT_EMULATOR: i32 = 2;
+
+fn repo_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+// a directory of its own for each test, tests run in parallel
+fn scratch(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("headless").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn headless(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_chipinho-headless"))
+        .current_dir(repo_root())
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn takes_screenshots_and_dumps_the_state() {
+    let dir = scratch("screenshots");
+    let ascii = dir.join("ibm.txt");
+    let pbm = dir.join("ibm.pbm");
+    let output = headless(&[
+        "test_files/2-ibm-logo.ch8",
+        "--until-halt",
+        "--screenshot",
+        ascii.to_str().unwrap(),
+        "--screenshot",
+        pbm.to_str().unwrap(),
+        "--scale",
+        "2",
+        "--dump",
+        "-",
+    ]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+
+    let golden = fs::read_to_string(repo_root().join("chipinho/tests/golden/ibm-logo.txt")).unwrap();
+    assert_eq!(fs::read_to_string(&ascii).unwrap(), golden);
+
+    // the same image, every pixel twice as big
+    let mut expected = String::from("P1\n128 64\n");
+    for row in golden.lines() {
+        let line: Vec<&str> = row.chars().flat_map(|c| [if c == '#' { "1" } else { "0" }; 2]).collect();
+        expected += &(line.join(" ") + "\n");
+        expected += &(line.join(" ") + "\n");
+    }
+    assert_eq!(fs::read_to_string(&pbm).unwrap(), expected);
+
+    // the logo is drawn by frame 2, where the program jumps to itself
+    let dump = stdout(&output);
+    assert!(dump.starts_with("frame: 2\nprogram counter: 0x228\ninstruction: 1nnn (JP 0x228)\n"), "{}", dump);
+    assert!(dump.contains("\nV0: 0x31\n"));
+    assert!(dump.contains("\nstack: []\nmemory:\n"));
+    assert_eq!(dump.lines().filter(|line| line.starts_with("0x")).count(), 4096 / 16);
+}
+
+#[test]
+fn writes_dumps_to_files() {
+    let dir = scratch("dump");
+    let dump = dir.join("state.txt");
+    let output = headless(&["test_files/2-ibm-logo.ch8", "--frames", "1", "--dump", dump.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(stdout(&output).is_empty());
+    assert!(fs::read_to_string(&dump).unwrap().starts_with("frame: 1\n"));
+}
+
+#[test]
+fn exits_with_2_on_emulator_errors() {
+    let dir = scratch("errors");
+    let rom = dir.join("invalid.ch8");
+    fs::write(&rom, [0xFF, 0xFF]).unwrap();
+    let output = headless(&[rom.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(EXIT_EMULATOR));
+    let message = stderr(&output);
+    assert!(message.starts_with("error on frame 1: invalid instruction FFFF at 0x200"), "{}", message);
+}
+
+#[test]
+fn exits_with_1_on_bad_usage_and_io_errors() {
+    assert_eq!(headless(&[]).status.code(), Some(EXIT_USAGE));
+    assert_eq!(headless(&["test_files/2-ibm-logo.ch8", "--bogus"]).status.code(), Some(EXIT_USAGE));
+    assert_eq!(headless(&["test_files/2-ibm-logo.ch8", "--press", "0:G"]).status.code(), Some(EXIT_USAGE));
+    for scale in ["0", "65", &u64::MAX.to_string()] {
+        let output = headless(&["test_files/2-ibm-logo.ch8", "--scale", scale]);
+        assert_eq!(output.status.code(), Some(EXIT_USAGE), "--scale {}", scale);
+        assert!(stderr(&output).starts_with("--scale: must be from 1 to 64\n"), "{}", stderr(&output));
+    }
+    let output = headless(&["test_files/missing.ch8"]);
+    assert_eq!(output.status.code(), Some(EXIT_USAGE));
+    assert!(stderr(&output).contains("missing.ch8"));
+}
+
+#[test]
+fn takes_presses_at_the_end_of_time() {
+    let dir = scratch("presses");
+    // 0x200: LD V0, K
+    // 0x202: JP 0x200
+    let rom = dir.join("wait.ch8");
+    fs::write(&rom, [0xF0, 0x0A, 0x12, 0x00]).unwrap();
+    let max = u64::MAX.to_string();
+    let output = headless(&[
+        rom.to_str().unwrap(),
+        "--until-halt",
+        "--frames",
+        "5",
+        "--press",
+        &format!("{}:5:10", max),
+        "--press",
+        &format!("1:6:{}", max),
+    ]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+}
@@ -1,11 +1,12 @@
 use std::env;
-use std::time::Instant;
 
-use chipinho::constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, NUM_KEYS};
+use chipinho::audio::Beeper;
+use chipinho::constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, PHOSPHOR_DEFAULT_DECAY};
 
-use chipinho::emulator::Emulator;
-use chipinho::error::Error;
-use sdl2::audio::{AudioCallback, AudioSpecDesired};
+use chipinho::phosphor::{Phosphor, PhosphorMode};
+use chipinho::runner::{Command, Event as RunnerEvent, Runner};
+use chipinho::scheduler::{Scheduler, SystemClock};
+use sdl2::audio::AudioSpecDesired;
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::mouse::MouseButton;
@@ -13,28 +14,13 @@ use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 
 const PIXEL_SIZE: u32 = 10;
-
-struct SquareWave {
-    phase_inc: f32,
-    phase: f32,
-    volume: f32,
-}
-
-impl AudioCallback for SquareWave {
-    type Channel = f32;
-
-    fn callback(&mut self, out: &mut [f32]) {
-        // Generate a square wave
-        for x in out.iter_mut() {
-            *x = if self.phase <= 0.5 {
-                self.volume
-            } else {
-                -self.volume
-            };
-            self.phase = (self.phase + self.phase_inc) % 1.0;
-        }
-    }
-}
+// longest wait for window events before checking on the emulator thread, in milliseconds
+const EVENT_WAIT_TIME: u32 = 4;
+// audio queued past this many bytes (about 0.1s) is dropped, so sound does not lag behind the screen
+const MAX_QUEUED_AUDIO: u32 = 4410 * 2;
+// `-` and `=` halve and double the speed, within these (in percent)
+const MIN_SPEED: u32 = 25;
+const MAX_SPEED: u32 = 800;
 
 pub fn main() -> Result<(), String> {
     let args: Vec<String> = env::args().collect();
@@ -55,11 +41,9 @@ pub fn main() -> Result<(), String> {
         samples: None,
     };
 
-    let audio_device = audio_subsystem.open_playback(None, &desired_spec, |spec| SquareWave {
-        phase_inc: 440.0 / spec.freq as f32,
-        phase: 0.0,
-        volume: 0.25,
-    })?;
+    // the emulator thread makes the samples, they only need queueing here
+    let audio_queue = audio_subsystem.open_queue::<i16, _>(None, &desired_spec)?;
+    audio_queue.resume();
 
     // the window is the representation of a window in your operating system,
     // however you can only manipulate properties of that window, like its size, whether it's
@@ -84,16 +68,19 @@ pub fn main() -> Result<(), String> {
 
     let mut event_pump = sdl_context.event_pump()?;
 
-    let mut emulator = Emulator::new();
-    if emulator.load_program(&program) != 0 {
-        return Err(format!("error loading program"));
-    }
-    let mut keypad: [u8; NUM_KEYS] = [0; NUM_KEYS];
-    let mut start = Instant::now();
+    // the emulator runs on its own thread, this one only handles the window
+    let beeper = Beeper::new(audio_queue.spec().freq as u32);
+    let runner = Runner::spawn(Scheduler::new(SystemClock::new()), Some(beeper));
+    runner.send(Command::Load(program));
+    let mut phosphor = Phosphor::new(PhosphorMode::Off, 0);
+    let mut paused = false;
+    let mut speed = 100;
+    let mut saved_state: Option<Vec<u8>> = None;
 
     'running: loop {
-        // get the inputs here
-        for event in event_pump.poll_iter() {
+        // get the inputs here, sleeping until there are some or it is time to check for frames
+        let first = event_pump.wait_event_timeout(EVENT_WAIT_TIME);
+        for event in first.into_iter().chain(event_pump.poll_iter()) {
             match event {
                 Event::Quit { .. }
                 | Event::KeyDown {
@@ -104,7 +91,55 @@ pub fn main() -> Result<(), String> {
                     keycode: Some(Keycode::Space),
                     repeat: false,
                     ..
-                } => {}
+                } => {
+                    paused = !paused;
+                    runner.send(if paused { Command::Pause } else { Command::Resume });
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Period),
+                    ..
+                } => runner.send(Command::Step),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Equals),
+                    ..
+                } => {
+                    speed = (speed * 2).min(MAX_SPEED);
+                    runner.send(Command::SetSpeed(speed));
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Minus),
+                    ..
+                } => {
+                    speed = (speed / 2).max(MIN_SPEED);
+                    runner.send(Command::SetSpeed(speed));
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F5),
+                    repeat: false,
+                    ..
+                } => runner.send(Command::SaveState),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F9),
+                    repeat: false,
+                    ..
+                } => {
+                    if let Some(state) = &saved_state {
+                        runner.send(Command::LoadState(state.clone()));
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::P),
+                    repeat: false,
+                    ..
+                } => {
+                    // cycle through the anti-flicker modes
+                    phosphor = match phosphor.mode {
+                        PhosphorMode::Off => Phosphor::new(PhosphorMode::Decay, PHOSPHOR_DEFAULT_DECAY),
+                        PhosphorMode::Decay => Phosphor::new(PhosphorMode::Blend, 2),
+                        PhosphorMode::Blend => Phosphor::new(PhosphorMode::GhostFree, 0),
+                        PhosphorMode::GhostFree => Phosphor::new(PhosphorMode::Off, 0),
+                    };
+                }
                 Event::MouseButtonDown {
                     x,
                     y,
@@ -115,178 +150,177 @@ pub fn main() -> Result<(), String> {
                     keycode: Some(Keycode::Num1),
                     repeat: true,
                     ..
-                } => keypad[0x01] = 1,
+                } => runner.send(Command::KeyDown(0x01)),
                 Event::KeyDown {
                     keycode: Some(Keycode::Num2),
                     ..
-                } => keypad[0x02] = 1,
+                } => runner.send(Command::KeyDown(0x02)),
                 Event::KeyDown {
                     keycode: Some(Keycode::Num3),
                     ..
-                } => keypad[0x03] = 1,
+                } => runner.send(Command::KeyDown(0x03)),
                 Event::KeyDown {
                     keycode: Some(Keycode::Num4),
                     ..
-                } => keypad[0x0C] = 1,
+                } => runner.send(Command::KeyDown(0x0C)),
                 Event::KeyDown {
                     keycode: Some(Keycode::Q),
                     ..
-                } => keypad[0x04] = 1,
+                } => runner.send(Command::KeyDown(0x04)),
                 Event::KeyDown {
                     keycode: Some(Keycode::W),
                     ..
-                } => keypad[0x05] = 1,
+                } => runner.send(Command::KeyDown(0x05)),
                 Event::KeyDown {
                     keycode: Some(Keycode::E),
                     ..
-                } => keypad[0x06] = 1,
+                } => runner.send(Command::KeyDown(0x06)),
                 Event::KeyDown {
                     keycode: Some(Keycode::R),
                     ..
-                } => keypad[0x0D] = 1,
+                } => runner.send(Command::KeyDown(0x0D)),
                 Event::KeyDown {
                     keycode: Some(Keycode::A),
                     ..
-                } => keypad[0x07] = 1,
+                } => runner.send(Command::KeyDown(0x07)),
                 Event::KeyDown {
                     keycode: Some(Keycode::S),
                     ..
-                } => keypad[0x08] = 1,
+                } => runner.send(Command::KeyDown(0x08)),
                 Event::KeyDown {
                     keycode: Some(Keycode::D),
                     ..
-                } => keypad[0x09] = 1,
+                } => runner.send(Command::KeyDown(0x09)),
                 Event::KeyDown {
                     keycode: Some(Keycode::F),
                     ..
-                } => keypad[0x0E] = 1,
+                } => runner.send(Command::KeyDown(0x0E)),
                 Event::KeyDown {
                     keycode: Some(Keycode::Z),
                     ..
-                } => keypad[0x0A] = 1,
+                } => runner.send(Command::KeyDown(0x0A)),
                 Event::KeyDown {
                     keycode: Some(Keycode::X),
                     ..
-                } => keypad[0x00] = 1,
+                } => runner.send(Command::KeyDown(0x00)),
                 Event::KeyDown {
                     keycode: Some(Keycode::C),
                     ..
-                } => keypad[0x0B] = 1,
+                } => runner.send(Command::KeyDown(0x0B)),
                 Event::KeyDown {
                     keycode: Some(Keycode::V),
                     ..
-                } => keypad[0x0F] = 1,
+                } => runner.send(Command::KeyDown(0x0F)),
                 Event::KeyUp {
                     keycode: Some(Keycode::Num1),
                     repeat: false,
                     ..
-                } => keypad[0x01] = 0,
+                } => runner.send(Command::KeyUp(0x01)),
                 Event::KeyUp {
                     keycode: Some(Keycode::Num2),
                     ..
-                } => keypad[0x02] = 0,
+                } => runner.send(Command::KeyUp(0x02)),
                 Event::KeyUp {
                     keycode: Some(Keycode::Num3),
                     ..
-                } => keypad[0x03] = 0,
+                } => runner.send(Command::KeyUp(0x03)),
                 Event::KeyUp {
                     keycode: Some(Keycode::Num4),
                     ..
-                } => keypad[0x0C] = 0,
+                } => runner.send(Command::KeyUp(0x0C)),
                 Event::KeyUp {
                     keycode: Some(Keycode::Q),
                     ..
-                } => keypad[0x04] = 0,
+                } => runner.send(Command::KeyUp(0x04)),
                 Event::KeyUp {
                     keycode: Some(Keycode::W),
                     ..
-                } => keypad[0x05] = 0,
+                } => runner.send(Command::KeyUp(0x05)),
                 Event::KeyUp {
                     keycode: Some(Keycode::E),
                     ..
-                } => keypad[0x06] = 0,
+                } => runner.send(Command::KeyUp(0x06)),
                 Event::KeyUp {
                     keycode: Some(Keycode::R),
                     ..
-                } => keypad[0x0D] = 0,
+                } => runner.send(Command::KeyUp(0x0D)),
                 Event::KeyUp {
                     keycode: Some(Keycode::A),
                     ..
-                } => keypad[0x07] = 0,
+                } => runner.send(Command::KeyUp(0x07)),
                 Event::KeyUp {
                     keycode: Some(Keycode::S),
                     ..
-                } => keypad[0x08] = 0,
+                } => runner.send(Command::KeyUp(0x08)),
                 Event::KeyUp {
                     keycode: Some(Keycode::D),
                     ..
-                } => keypad[0x09] = 0,
+                } => runner.send(Command::KeyUp(0x09)),
                 Event::KeyUp {
                     keycode: Some(Keycode::F),
                     ..
-                } => keypad[0x0E] = 0,
+                } => runner.send(Command::KeyUp(0x0E)),
                 Event::KeyUp {
                     keycode: Some(Keycode::Z),
                     ..
-                } => keypad[0x0A] = 0,
+                } => runner.send(Command::KeyUp(0x0A)),
                 Event::KeyUp {
                     keycode: Some(Keycode::X),
                     ..
-                } => keypad[0x00] = 0,
+                } => runner.send(Command::KeyUp(0x00)),
                 Event::KeyUp {
                     keycode: Some(Keycode::C),
                     ..
-                } => keypad[0x0B] = 0,
+                } => runner.send(Command::KeyUp(0x0B)),
                 Event::KeyUp {
                     keycode: Some(Keycode::V),
                     ..
-                } => keypad[0x0F] = 0,
+                } => runner.send(Command::KeyUp(0x0F)),
                 _ => {}
             }
         }
-        // update the game loop here
-        if (Instant::now() - start).as_millis() >= 16 {
-            let res = emulator.tick(&keypad);
-            if res != 0 {
-                let err : Error = res.into();
-                return Err(format!("error on tick: {:?}", err));
+        // and whatever the emulator thread made meanwhile
+        let mut redraw = false;
+        for event in runner.events().try_iter() {
+            match event {
+                RunnerEvent::Frame(vram) => {
+                    phosphor.update(&vram);
+                    redraw = true;
+                }
+                RunnerEvent::Audio(samples) => {
+                    if audio_queue.size() < MAX_QUEUED_AUDIO {
+                        audio_queue.queue_audio(&samples)?;
+                    }
+                }
+                RunnerEvent::State(state) => saved_state = Some(state),
+                // loading the program or running it
+                RunnerEvent::Error(err) => return Err(format!("error: {}", err)),
             }
-            start = Instant::now();
         }
-
+        if !redraw {
+            continue;
+        }
 
         canvas.set_draw_color(Color::BLACK);
         canvas.clear();
-        canvas.set_draw_color(Color::GRAY);
         // iterate over pixels and get which color to print each square
-        canvas.fill_rects(
-            &emulator
-            .get_vram()
-            .iter()
-            .enumerate()
-            .filter_map(|(index, pixel)| -> Option<Rect> {
-                let i = index as u32;
-                if *pixel != 0 {
-                    let x = i % DISPLAY_WIDTH as u32;
-                    let y = i / DISPLAY_WIDTH as u32;
-                    return Some(Rect::new(
-                        (x * PIXEL_SIZE) as i32,
-                        (y * PIXEL_SIZE) as i32,
-                        PIXEL_SIZE,
-                        PIXEL_SIZE,
-                    ));
-                }
-                None
-            })
-            .collect::<Vec<Rect>>()
-            .as_slice()
-        )?;
-        canvas.present();
-        if emulator.should_beep() {
-            audio_device.resume();
-        } else {
-            audio_device.pause();
+        for (index, intensity) in phosphor.get_intensity().iter().enumerate() {
+            if *intensity == 0 {
+                continue;
+            }
+            let i = index as u32;
+            let x = i % DISPLAY_WIDTH as u32;
+            let y = i / DISPLAY_WIDTH as u32;
+            let shade = (*intensity as u32 * Color::GRAY.r as u32 / u8::MAX as u32) as u8;
+            canvas.set_draw_color(Color::RGB(shade, shade, shade));
+            canvas.fill_rect(Rect::new(
+                (x * PIXEL_SIZE) as i32,
+                (y * PIXEL_SIZE) as i32,
+                PIXEL_SIZE,
+                PIXEL_SIZE,
+            ))?;
         }
+        canvas.present();
     }
 
     Ok(())
@@ -0,0 +1,356 @@
+use std::env;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chipinho::constants::{DEFAULT_CYCLES_PER_FRAME, DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE, NUM_REGISTERS};
+use chipinho::emulator::Emulator;
+use chipinho::instruction::Instruction;
+use chipinho::symbols::Symbols;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+
+use tui::debugger::{self, Breakpoints, Command, Stop};
+use tui::input::Keypad;
+use tui::render::{self, Mode};
+use tui::terminal::Terminal;
+
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+const MEMORY_ROW_SIZE: u16 = 8;
+const MEMORY_PAGE_SIZE: u16 = 0x40;
+
+const USAGE: &str = "usage: debugger <filename> [--cycles <n>] [--symbols <file>]
+
+labels are read from <file>, or from the rom path with a .sym extension when it exists";
+
+const HELP: &str = "F5/^R run/pause  F10/^N step  F4/^G run to cursor  F9/^B breakpoint  F2/^P poke  \
+                    ↑↓ cursor  PgUp/PgDn memory  Home memory at I  End cursor at PC  Esc quit";
+
+struct Debugger {
+    emulator: Emulator,
+    symbols: Symbols,
+    keypad: Keypad,
+    cycles: u32,
+    running: bool,
+    breakpoints: Breakpoints,
+    cursor: u16,
+    memory_base: u16,
+    prompt: Option<String>,
+    message: String,
+}
+
+impl Debugger {
+    fn new(emulator: Emulator, symbols: Symbols, keypad: Keypad, cycles: u32) -> Self {
+        Debugger {
+            cursor: emulator.program_counter,
+            memory_base: emulator.program_counter,
+            emulator,
+            symbols,
+            keypad,
+            cycles,
+            running: false,
+            breakpoints: Breakpoints::new(),
+            prompt: None,
+            message: String::from("paused"),
+        }
+    }
+
+    fn pause(&mut self, message: String) {
+        self.running = false;
+        self.breakpoints.pause();
+        self.cursor = self.emulator.program_counter;
+        self.message = message;
+    }
+
+    fn resume(&mut self, run_to: Option<u16>) {
+        self.running = true;
+        self.breakpoints.resume(self.emulator.program_counter, run_to);
+        self.message = String::from("running");
+    }
+
+    // returns false when the emulator stopped with an error
+    fn step(&mut self) -> bool {
+        if let Err(err) = self.emulator.step(self.keypad.state()) {
+            self.pause(format!("error: {}", self.symbols.describe_error(&err)));
+            return false;
+        }
+        self.cursor = self.emulator.program_counter;
+        true
+    }
+
+    fn run_frame(&mut self) {
+        let keypad = self.keypad.state();
+        match debugger::run_frame(&mut self.emulator, keypad, self.cycles, &mut self.breakpoints) {
+            Ok(None) => self.cursor = self.emulator.program_counter,
+            Ok(Some(Stop::Breakpoint(pc))) => self.pause(format!("breakpoint at {}", self.symbols.location(pc))),
+            Ok(Some(Stop::Reached(pc))) => self.pause(format!("reached {}", self.symbols.location(pc))),
+            Err(err) => self.pause(format!("error: {}", self.symbols.describe_error(&err))),
+        }
+    }
+
+    fn poke(&mut self, command: &str) -> Result<String, String> {
+        let (addr, bytes) = debugger::parse_poke(command)?;
+        let start = addr as usize;
+        self.emulator.get_memory_mut()[start..start + bytes.len()].copy_from_slice(&bytes);
+        Ok(format!("wrote {} bytes at 0x{:04X}", bytes.len(), addr))
+    }
+
+    // returns false to quit
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if let Some(prompt) = self.prompt.as_mut() {
+            if key.kind == KeyEventKind::Release {
+                return true;
+            }
+            match key.code {
+                KeyCode::Esc => self.prompt = None,
+                KeyCode::Backspace => {
+                    prompt.pop();
+                }
+                KeyCode::Enter => {
+                    let command = self.prompt.take().unwrap_or_default();
+                    self.message = self.poke(&command).unwrap_or_else(|err| err);
+                }
+                KeyCode::Char(c) => prompt.push(c),
+                _ => {}
+            }
+            return true;
+        }
+
+        if key.kind == KeyEventKind::Release {
+            self.keypad.handle(&key, Instant::now());
+            return true;
+        }
+        match Command::from_key(&key) {
+            Some(Command::Quit) => return false,
+            Some(Command::RunPause) if self.running => self.pause(String::from("paused")),
+            Some(Command::RunPause) => self.resume(None),
+            Some(Command::Step) => {
+                if !self.running && self.step() {
+                    self.message = String::from("stepped");
+                }
+            }
+            Some(Command::RunToCursor) => self.resume(Some(self.cursor)),
+            Some(Command::ToggleBreakpoint) => self.breakpoints.toggle(self.cursor),
+            Some(Command::Poke) => self.prompt = Some(String::new()),
+            Some(Command::CursorUp) => self.cursor = self.cursor.saturating_sub(2),
+            Some(Command::CursorDown) => self.cursor = (self.cursor + 2).min(MEMORY_SIZE - 2),
+            Some(Command::MemoryUp) => self.memory_base = self.memory_base.saturating_sub(MEMORY_PAGE_SIZE),
+            Some(Command::MemoryDown) => {
+                self.memory_base = (self.memory_base + MEMORY_PAGE_SIZE).min(MEMORY_SIZE - MEMORY_PAGE_SIZE)
+            }
+            Some(Command::MemoryAtIndex) => {
+                self.memory_base = self.emulator.index.min(MEMORY_SIZE - 1) & !(MEMORY_ROW_SIZE - 1)
+            }
+            Some(Command::CursorAtPc) => self.cursor = self.emulator.program_counter,
+            None => {
+                self.keypad.handle(&key, Instant::now());
+            }
+        }
+        true
+    }
+
+    fn disassembly(&self, height: u16) -> Vec<Line<'static>> {
+        // keep the cursor in the middle, on the same alignment as the cursor itself
+        let first = self.cursor.saturating_sub(2 * (height / 2));
+        let mut cursor_line = 0;
+        let mut lines = Vec::new();
+        // twice as many addresses as lines, as labels take lines too
+        for addr in (0..2 * height).map(|line| first + 2 * line).take_while(|addr| *addr < MEMORY_SIZE - 1) {
+            // labels get a line of their own, like in an assembler listing
+            if let Some(label) = self.symbols.label(addr) {
+                lines.push(Line::styled(format!("{}:", label), Style::default().fg(Color::Cyan)));
+            }
+            if addr == self.cursor {
+                cursor_line = lines.len();
+            }
+            let marker = match (addr == self.emulator.program_counter, self.breakpoints.contains(addr)) {
+                (true, true) => "●>",
+                (true, false) => " >",
+                (false, true) => "● ",
+                (false, false) => "  ",
+            };
+            let opcode = self.emulator.opcode_at(addr).unwrap_or(0);
+            let text = match Instruction::parse(opcode) {
+                Ok(instruction) => instruction.with_symbols(&self.symbols).to_string(),
+                Err(_) => String::from("???"),
+            };
+            let mut style = Style::default();
+            if self.breakpoints.contains(addr) {
+                style = style.fg(Color::Red);
+            }
+            if addr == self.emulator.program_counter {
+                style = style.add_modifier(Modifier::BOLD).fg(Color::Yellow);
+            }
+            if addr == self.cursor {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            lines.push(Line::styled(format!("{} 0x{:04X}  {:04X}  {}", marker, addr, opcode, text), style));
+        }
+        // labels push the cursor down, scroll them out of the way
+        let skip = cursor_line.saturating_sub(height as usize / 2);
+        lines.into_iter().skip(skip).take(height as usize).collect()
+    }
+
+    fn registers(&self) -> Vec<Line<'static>> {
+        let registers = self.emulator.get_registers();
+        let mut lines: Vec<Line> = (0..NUM_REGISTERS / 2)
+            .map(|index| {
+                let high = index + NUM_REGISTERS / 2;
+                Line::raw(format!(
+                    "V{:X} {:02X}    V{:X} {:02X}",
+                    index, registers[index], high, registers[high]
+                ))
+            })
+            .collect();
+        lines.push(Line::raw(""));
+        lines.push(Line::raw(format!("PC {:04X}  I {:04X}", self.emulator.program_counter, self.emulator.index)));
+        lines.push(Line::raw(format!("DT {:02X}    ST {:02X}", self.emulator.delay_timer, self.emulator.sound_timer)));
+        lines.push(Line::raw(format!("SP {:02X}", self.emulator.stack_size)));
+        match self.symbols.lookup(self.emulator.program_counter) {
+            Some((label, 0)) => lines.push(Line::raw(format!("in {}", label))),
+            Some((label, offset)) => lines.push(Line::raw(format!("in {}+{}", label, offset))),
+            None => {}
+        }
+        if self.emulator.waiting_key.is_some() {
+            lines.push(Line::styled("waiting for key", Style::default().fg(Color::Yellow)));
+        }
+        lines
+    }
+
+    fn stack(&self) -> Vec<Line<'static>> {
+        // most recent call first
+        self.emulator
+            .get_stack()
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(depth, addr)| Line::raw(format!("{:2}  {}", depth, self.symbols.location(*addr))))
+            .collect()
+    }
+
+    fn memory(&self, height: u16) -> Vec<Line<'static>> {
+        let memory = self.emulator.get_memory();
+        (0..height)
+            .map(|row| self.memory_base as usize + (row * MEMORY_ROW_SIZE) as usize)
+            .take_while(|addr| *addr < memory.len())
+            .map(|addr| {
+                let mut spans = vec![Span::raw(format!("{:04X}:", addr))];
+                spans.extend(
+                    memory
+                        .iter()
+                        .enumerate()
+                        .skip(addr)
+                        .take(MEMORY_ROW_SIZE as usize)
+                        .map(|(byte_addr, byte)| {
+                            let pc = self.emulator.program_counter as usize;
+                            let style = if byte_addr == self.emulator.index as usize {
+                                Style::default().fg(Color::Black).bg(Color::Cyan)
+                            } else if byte_addr == pc || byte_addr == pc + 1 {
+                                Style::default().fg(Color::Yellow)
+                            } else {
+                                Style::default()
+                            };
+                            Span::styled(format!(" {:02X}", byte), style)
+                        }),
+                );
+                Line::from(spans)
+            })
+            .collect()
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(frame.area());
+        let [code, state, right] =
+            Layout::horizontal([Constraint::Length(36), Constraint::Length(22), Constraint::Min(36)]).areas(main);
+        let [registers, stack] = Layout::vertical([Constraint::Length(15), Constraint::Min(0)]).areas(state);
+        let screen_lines = render::screen_to_lines(
+            self.emulator.get_vram(),
+            DISPLAY_WIDTH as usize,
+            DISPLAY_HEIGHT as usize,
+            Mode::Braille,
+        );
+        let [screen, memory] =
+            Layout::vertical([Constraint::Length(screen_lines.len() as u16 + 2), Constraint::Min(0)]).areas(right);
+
+        let pane = |title: &'static str| Block::default().borders(Borders::ALL).title(title);
+        let inner_height = |area: Rect| area.height.saturating_sub(2);
+        frame.render_widget(Paragraph::new(self.disassembly(inner_height(code))).block(pane("disassembly")), code);
+        frame.render_widget(Paragraph::new(self.registers()).block(pane("registers")), registers);
+        frame.render_widget(Paragraph::new(self.stack()).block(pane("stack")), stack);
+        let screen_lines: Vec<Line> = screen_lines.into_iter().map(Line::raw).collect();
+        frame.render_widget(Paragraph::new(screen_lines).block(pane("screen")), screen);
+        frame.render_widget(Paragraph::new(self.memory(inner_height(memory))).block(pane("memory")), memory);
+
+        let first_line = match self.prompt.as_ref() {
+            Some(prompt) => Line::raw(format!("poke <addr> <byte>...> {}", prompt)),
+            None => Line::raw(self.message.clone()),
+        };
+        frame.render_widget(
+            Paragraph::new(vec![first_line, Line::styled(HELP, Style::default().add_modifier(Modifier::DIM))]),
+            status,
+        );
+    }
+}
+
+// an explicit symbol file must exist, the one next to the rom is optional
+pub fn main() -> Result<(), String> {
+    let mut filename = None;
+    let mut cycles = DEFAULT_CYCLES_PER_FRAME;
+    let mut symbols_path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cycles" => {
+                cycles = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| String::from(USAGE))?
+            }
+            "--symbols" => symbols_path = Some(args.next().ok_or_else(|| String::from(USAGE))?),
+            _ if filename.is_none() && !arg.starts_with("--") => filename = Some(arg),
+            _ => return Err(String::from(USAGE)),
+        }
+    }
+    let filename = filename.ok_or_else(|| String::from(USAGE))?;
+    let program = std::fs::read(&filename).map_err(|e| e.to_string())?;
+
+    let mut emulator = Emulator::new();
+    emulator
+        .load_program(&program)
+        .map_err(|e| format!("error loading program: {}", e))?;
+    let symbols = Symbols::load_for_rom(Path::new(&filename), symbols_path.as_deref().map(Path::new))
+        .map_err(|e| e.to_string())?;
+
+    let guard = Terminal::setup().map_err(|e| e.to_string())?;
+    let mut terminal = ratatui::Terminal::new(CrosstermBackend::new(io::stdout())).map_err(|e| e.to_string())?;
+    let mut debugger = Debugger::new(emulator, symbols, Keypad::new(guard.enhanced_keyboard), cycles);
+    let mut next_frame = Instant::now();
+
+    loop {
+        terminal.draw(|frame| debugger.draw(frame)).map_err(|e| e.to_string())?;
+
+        let timeout = next_frame.saturating_duration_since(Instant::now());
+        if event::poll(timeout).map_err(|e| e.to_string())? {
+            if let Event::Key(key) = event::read().map_err(|e| e.to_string())? {
+                if !debugger.handle_key(key) {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        let now = Instant::now();
+        debugger.keypad.release_expired(now);
+        if debugger.running {
+            debugger.run_frame();
+        }
+        next_frame = (next_frame + FRAME_TIME).max(now);
+    }
+
+    Ok(())
+}
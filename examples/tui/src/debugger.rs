@@ -0,0 +1,144 @@
+use std::collections::BTreeSet;
+
+use chipinho::constants::MEMORY_SIZE;
+use chipinho::emulator::Emulator;
+use chipinho::error::Error;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// What a key does in the debugger, keys that are none of these go to the keypad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Quit,
+    RunPause,
+    Step,
+    RunToCursor,
+    ToggleBreakpoint,
+    Poke,
+    CursorUp,
+    CursorDown,
+    MemoryUp,
+    MemoryDown,
+    MemoryAtIndex,
+    CursorAtPc,
+}
+
+impl Command {
+    pub fn from_key(key: &KeyEvent) -> Option<Self> {
+        let ctrl = |c: char| key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char(c);
+        let command = match key.code {
+            KeyCode::Esc => Command::Quit,
+            KeyCode::F(5) => Command::RunPause,
+            KeyCode::F(10) => Command::Step,
+            KeyCode::F(4) => Command::RunToCursor,
+            KeyCode::F(9) => Command::ToggleBreakpoint,
+            KeyCode::F(2) => Command::Poke,
+            KeyCode::Up => Command::CursorUp,
+            KeyCode::Down => Command::CursorDown,
+            KeyCode::PageUp => Command::MemoryUp,
+            KeyCode::PageDown => Command::MemoryDown,
+            KeyCode::Home => Command::MemoryAtIndex,
+            KeyCode::End => Command::CursorAtPc,
+            _ if ctrl('c') => Command::Quit,
+            _ if ctrl('r') => Command::RunPause,
+            _ if ctrl('n') => Command::Step,
+            _ if ctrl('g') => Command::RunToCursor,
+            _ if ctrl('b') => Command::ToggleBreakpoint,
+            _ if ctrl('p') => Command::Poke,
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+/// Parse a poke prompt, `<addr> <byte>...` in hex, into the address and the bytes to write there.
+pub fn parse_poke(command: &str) -> Result<(u16, Vec<u8>), String> {
+    let values: Vec<u16> = command
+        .split_whitespace()
+        .map(|value| u16::from_str_radix(value.trim_start_matches("0x"), 16))
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("not hex: {}", command))?;
+    let (addr, bytes) = values
+        .split_first()
+        .filter(|(_, bytes)| !bytes.is_empty())
+        .ok_or_else(|| String::from("poke needs an address and at least one byte"))?;
+    let bytes = bytes
+        .iter()
+        .map(|byte| u8::try_from(*byte).ok())
+        .collect::<Option<Vec<u8>>>()
+        .filter(|bytes| *addr as usize + bytes.len() <= MEMORY_SIZE as usize)
+        .ok_or_else(|| String::from("bytes go from 00 to FF and must fit in memory"))?;
+    Ok((*addr, bytes))
+}
+
+/// Why a run stopped before an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    Breakpoint(u16),
+    Reached(u16),
+}
+
+/// The breakpoints and the run to cursor target.
+#[derive(Default)]
+pub struct Breakpoints {
+    addresses: BTreeSet<u16>,
+    run_to: Option<u16>,
+    // the instruction the emulator resumed on, so continuing from a breakpoint does not stop right away
+    resumed_at: Option<u16>,
+}
+
+impl Breakpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self, addr: u16) {
+        if !self.addresses.remove(&addr) {
+            self.addresses.insert(addr);
+        }
+    }
+
+    pub fn contains(&self, addr: u16) -> bool {
+        self.addresses.contains(&addr)
+    }
+
+    /// Start running from `pc`, until a breakpoint or `run_to`.
+    pub fn resume(&mut self, pc: u16, run_to: Option<u16>) {
+        self.run_to = run_to;
+        self.resumed_at = Some(pc);
+    }
+
+    /// Forget the run to cursor target.
+    pub fn pause(&mut self) {
+        self.run_to = None;
+    }
+
+    /// Whether to stop before running the instruction at `pc`.
+    pub fn check(&mut self, pc: u16) -> Option<Stop> {
+        if self.resumed_at.take() == Some(pc) {
+            None
+        } else if self.addresses.contains(&pc) {
+            Some(Stop::Breakpoint(pc))
+        } else if self.run_to == Some(pc) {
+            Some(Stop::Reached(pc))
+        } else {
+            None
+        }
+    }
+}
+
+/// Run a frame of `cycles` instructions, stopping early before one `breakpoints` stops at.
+pub fn run_frame(
+    emulator: &mut Emulator,
+    keypad: &[u8],
+    cycles: u32,
+    breakpoints: &mut Breakpoints,
+) -> Result<Option<Stop>, Error> {
+    emulator.update_timers();
+    for _ in 0..cycles {
+        if let Some(stop) = breakpoints.check(emulator.program_counter) {
+            return Ok(Some(stop));
+        }
+        emulator.step(keypad)?;
+    }
+    Ok(None)
+}
@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+use chipinho::constants::NUM_KEYS;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+
+// most terminals only report key presses, so without release events a key counts as
+// held until this long after its last press (or auto repeat)
+const HOLD_TIME: Duration = Duration::from_millis(150);
+
+// same layout as the other examples:
+// 1 2 3 4      1 2 3 C
+// q w e r  ->  4 5 6 D
+// a s d f      7 8 9 E
+// z x c v      A 0 B F
+pub fn key_index(code: KeyCode) -> Option<usize> {
+    let key = match code {
+        KeyCode::Char(c) => c.to_ascii_lowercase(),
+        _ => return None,
+    };
+    match key {
+        '1' => Some(0x1),
+        '2' => Some(0x2),
+        '3' => Some(0x3),
+        '4' => Some(0xC),
+        'q' => Some(0x4),
+        'w' => Some(0x5),
+        'e' => Some(0x6),
+        'r' => Some(0xD),
+        'a' => Some(0x7),
+        's' => Some(0x8),
+        'd' => Some(0x9),
+        'f' => Some(0xE),
+        'z' => Some(0xA),
+        'x' => Some(0x0),
+        'c' => Some(0xB),
+        'v' => Some(0xF),
+        _ => None,
+    }
+}
+
+pub struct Keypad {
+    keys: [u8; NUM_KEYS],
+    held_until: [Option<Instant>; NUM_KEYS],
+    // whether the terminal sends key release events
+    reports_release: bool,
+}
+
+impl Keypad {
+    pub fn new(reports_release: bool) -> Self {
+        Keypad {
+            keys: [0; NUM_KEYS],
+            held_until: [None; NUM_KEYS],
+            reports_release,
+        }
+    }
+
+    /// Returns whether the key is part of the keypad.
+    pub fn handle(&mut self, event: &KeyEvent, now: Instant) -> bool {
+        let Some(index) = key_index(event.code) else {
+            return false;
+        };
+        match event.kind {
+            KeyEventKind::Press | KeyEventKind::Repeat => {
+                self.keys[index] = 1;
+                if !self.reports_release {
+                    self.held_until[index] = Some(now + HOLD_TIME);
+                }
+            }
+            KeyEventKind::Release => {
+                self.keys[index] = 0;
+                self.held_until[index] = None;
+            }
+        }
+        true
+    }
+
+    pub fn release_expired(&mut self, now: Instant) {
+        self.keys
+            .iter_mut()
+            .zip(self.held_until.iter_mut())
+            .filter(|(_, held_until)| held_until.is_some_and(|until| until <= now))
+            .for_each(|(key, held_until)| {
+                *key = 0;
+                *held_until = None;
+            });
+    }
+
+    pub fn state(&self) -> &[u8] {
+        &self.keys
+    }
+}
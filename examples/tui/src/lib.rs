@@ -0,0 +1,4 @@
+pub mod debugger;
+pub mod input;
+pub mod render;
+pub mod terminal;
@@ -0,0 +1,131 @@
+use std::env;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use chipinho::constants::{DEFAULT_CYCLES_PER_FRAME, DEFAULT_HISTORY_SIZE, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use chipinho::emulator::Emulator;
+use chipinho::history::Recorder;
+use chipinho::scheduler::{Scheduler, SystemClock};
+use crossterm::cursor::MoveTo;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{execute, queue};
+
+use tui::input::Keypad;
+use tui::render::{self, Mode};
+use tui::terminal::Terminal;
+
+const USAGE: &str = "usage: tui <filename> [--braille] [--cycles <n>]
+
+keys: 1234/qwer/asdf/zxcv for the keypad, tab to switch between half blocks and braille, esc to quit";
+
+fn draw(terminal: &mut Terminal, emulator: &Emulator, mode: Mode) -> io::Result<()> {
+    let lines = render::screen_to_lines(
+        emulator.get_vram(),
+        DISPLAY_WIDTH as usize,
+        DISPLAY_HEIGHT as usize,
+        mode,
+    );
+    for (row, line) in lines.iter().enumerate() {
+        queue!(terminal.stdout, MoveTo(0, row as u16), Print(line))?;
+    }
+    let mode_name = match mode {
+        Mode::HalfBlock => "half blocks",
+        Mode::Braille => "braille",
+    };
+    let status = format!(
+        "PC 0x{:04X}  I 0x{:04X}  DT {:3}  ST {:3}  [{}]",
+        emulator.program_counter, emulator.index, emulator.delay_timer, emulator.sound_timer, mode_name
+    );
+    queue!(
+        terminal.stdout,
+        MoveTo(0, lines.len() as u16),
+        Clear(ClearType::CurrentLine),
+        Print(status)
+    )?;
+    terminal.stdout.flush()
+}
+
+pub fn main() -> Result<(), String> {
+    let mut filename = None;
+    let mut mode = Mode::HalfBlock;
+    let mut cycles = DEFAULT_CYCLES_PER_FRAME;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--braille" => mode = Mode::Braille,
+            "--cycles" => {
+                cycles = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| String::from(USAGE))?
+            }
+            _ if filename.is_none() && !arg.starts_with("--") => filename = Some(arg),
+            _ => return Err(String::from(USAGE)),
+        }
+    }
+    let filename = filename.ok_or_else(|| String::from(USAGE))?;
+    let program = std::fs::read(&filename).map_err(|e| e.to_string())?;
+
+    let mut emulator = Emulator::new();
+    emulator
+        .load_program(&program)
+        .map_err(|e| format!("error loading program: {}", e))?;
+    // keeps the last instructions around for the crash report
+    let mut recorder: Recorder<DEFAULT_HISTORY_SIZE> = Recorder::new(emulator);
+
+    let mut terminal = Terminal::setup().map_err(|e| e.to_string())?;
+    let mut keypad = Keypad::new(terminal.enhanced_keyboard);
+    let mut scheduler = Scheduler::new(SystemClock::new()).with_cycles_per_frame(cycles);
+    let mut was_beeping = false;
+
+    'running: loop {
+        // get the inputs here, waiting at most until the next frame is due
+        let timeout = Duration::from_micros(scheduler.micros_until_next_frame().unwrap_or_default());
+        if event::poll(timeout).map_err(|e| e.to_string())? {
+            match event::read().map_err(|e| e.to_string())? {
+                Event::Key(key) if key.code == KeyCode::Esc => break 'running,
+                Event::Key(key) if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break 'running
+                }
+                Event::Key(key) if key.code == KeyCode::Tab && key.kind == KeyEventKind::Press => {
+                    mode = mode.toggle();
+                    execute!(terminal.stdout, Clear(ClearType::All)).map_err(|e| e.to_string())?;
+                }
+                Event::Key(key) => {
+                    keypad.handle(&key, Instant::now());
+                }
+                Event::Resize(_, _) => {
+                    execute!(terminal.stdout, Clear(ClearType::All)).map_err(|e| e.to_string())?;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        // update the game loop here, a suspended terminal drops the frames it missed
+        keypad.release_expired(Instant::now());
+        let frames = scheduler.frames_due();
+        for _ in 0..frames.ran {
+            if let Err(err) = recorder.run_frame(keypad.state(), cycles) {
+                drop(terminal);
+                eprint!("{}", recorder.crash_report(err));
+                return Err(format!("error on tick: {}", err));
+            }
+        }
+        if !frames.should_draw() {
+            continue;
+        }
+
+        draw(&mut terminal, &recorder.emulator, mode).map_err(|e| e.to_string())?;
+        // the terminal bell is the closest thing to a beeper we have
+        let beeping = recorder.emulator.should_beep();
+        if beeping && !was_beeping {
+            execute!(terminal.stdout, Print('\x07')).map_err(|e| e.to_string())?;
+        }
+        was_beeping = beeping;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    // one character for every 1x2 pixels
+    HalfBlock,
+    // one character for every 2x4 pixels
+    Braille,
+}
+
+impl Mode {
+    pub fn toggle(self) -> Self {
+        match self {
+            Mode::HalfBlock => Mode::Braille,
+            Mode::Braille => Mode::HalfBlock,
+        }
+    }
+
+    pub fn cell_size(self) -> (usize, usize) {
+        match self {
+            Mode::HalfBlock => (1, 2),
+            Mode::Braille => (2, 4),
+        }
+    }
+}
+
+// braille dots are numbered column first, with the last row added later to the standard
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+const BRAILLE_BLANK: u32 = 0x2800;
+
+/// Turn a `width` x `height` screen into lines of text.
+pub fn screen_to_lines(vram: &[u8], width: usize, height: usize, mode: Mode) -> Vec<String> {
+    let lit = |x: usize, y: usize| x < width && y < height && vram.get(y * width + x).is_some_and(|pixel| *pixel != 0);
+    let (cell_width, cell_height) = mode.cell_size();
+    (0..height.div_ceil(cell_height))
+        .map(|row| {
+            (0..width.div_ceil(cell_width))
+                .map(|column| {
+                    let (x, y) = (column * cell_width, row * cell_height);
+                    match mode {
+                        Mode::HalfBlock => match (lit(x, y), lit(x, y + 1)) {
+                            (true, true) => '█',
+                            (true, false) => '▀',
+                            (false, true) => '▄',
+                            (false, false) => ' ',
+                        },
+                        Mode::Braille => {
+                            let dots = BRAILLE_DOTS
+                                .iter()
+                                .enumerate()
+                                .flat_map(|(dy, row_dots)| {
+                                    row_dots
+                                        .iter()
+                                        .enumerate()
+                                        .filter(move |(dx, _)| lit(x + dx, y + dy))
+                                        .map(|(_, dot)| *dot)
+                                })
+                                .sum::<u32>();
+                            char::from_u32(BRAILLE_BLANK + dots).unwrap_or(' ')
+                        }
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,82 @@
+use chipinho::constants::NUM_KEYS;
+use chipinho::emulator::Emulator;
+use chipinho::error::ErrorKind;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui::debugger::{parse_poke, run_frame, Breakpoints, Command, Stop};
+
+const KEYPAD: [u8; NUM_KEYS] = [0; NUM_KEYS];
+
+// 0x200: ADD V0, 1
+// 0x202: ADD V1, 1
+// 0x204: JP 0x200
+fn looping() -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.load_program(&[0x70, 0x01, 0x71, 0x01, 0x12, 0x00]).unwrap();
+    emulator
+}
+
+#[test]
+fn maps_keys_and_ctrl_shortcuts_to_commands() {
+    let key = |code, modifiers| Command::from_key(&KeyEvent::new(code, modifiers));
+    assert_eq!(key(KeyCode::F(5), KeyModifiers::NONE), Some(Command::RunPause));
+    assert_eq!(key(KeyCode::Char('r'), KeyModifiers::CONTROL), Some(Command::RunPause));
+    assert_eq!(key(KeyCode::Char('c'), KeyModifiers::CONTROL), Some(Command::Quit));
+    assert_eq!(key(KeyCode::Esc, KeyModifiers::NONE), Some(Command::Quit));
+    assert_eq!(key(KeyCode::Char('b'), KeyModifiers::CONTROL), Some(Command::ToggleBreakpoint));
+    assert_eq!(key(KeyCode::Home, KeyModifiers::NONE), Some(Command::MemoryAtIndex));
+    // without ctrl they are keypad keys
+    assert_eq!(key(KeyCode::Char('r'), KeyModifiers::NONE), None);
+    assert_eq!(key(KeyCode::Char('x'), KeyModifiers::CONTROL), None);
+}
+
+#[test]
+fn parses_pokes() {
+    assert_eq!(parse_poke("200 12 0x34"), Ok((0x200, vec![0x12, 0x34])));
+    assert_eq!(parse_poke(" 0xFFF ff "), Ok((0xFFF, vec![0xFF])));
+    assert_eq!(parse_poke("200 zz"), Err(String::from("not hex: 200 zz")));
+    assert!(parse_poke("200").is_err());
+    assert!(parse_poke("").is_err());
+    assert!(parse_poke("200 100").is_err());
+    assert!(parse_poke("FFF 1 2").is_err());
+}
+
+#[test]
+fn stops_at_breakpoints_and_continues_past_them() {
+    let mut emulator = looping();
+    let mut breakpoints = Breakpoints::new();
+    breakpoints.toggle(0x204);
+    assert!(breakpoints.contains(0x204));
+    breakpoints.resume(emulator.program_counter, None);
+    assert_eq!(run_frame(&mut emulator, &KEYPAD, 10, &mut breakpoints), Ok(Some(Stop::Breakpoint(0x204))));
+    assert_eq!(emulator.program_counter, 0x204);
+
+    // resuming on the breakpoint runs it, and stops there on the next time around
+    breakpoints.resume(emulator.program_counter, None);
+    assert_eq!(run_frame(&mut emulator, &KEYPAD, 10, &mut breakpoints), Ok(Some(Stop::Breakpoint(0x204))));
+    assert_eq!(emulator.get_registers()[0], 2);
+
+    breakpoints.toggle(0x204);
+    breakpoints.resume(emulator.program_counter, None);
+    assert_eq!(run_frame(&mut emulator, &KEYPAD, 10, &mut breakpoints), Ok(None));
+}
+
+#[test]
+fn runs_to_the_cursor() {
+    let mut emulator = looping();
+    let mut breakpoints = Breakpoints::new();
+    breakpoints.resume(emulator.program_counter, Some(0x202));
+    assert_eq!(run_frame(&mut emulator, &KEYPAD, 10, &mut breakpoints), Ok(Some(Stop::Reached(0x202))));
+    // pausing forgets the target
+    breakpoints.pause();
+    breakpoints.resume(emulator.program_counter, None);
+    assert_eq!(run_frame(&mut emulator, &KEYPAD, 10, &mut breakpoints), Ok(None));
+}
+
+#[test]
+fn stops_at_errors() {
+    let mut emulator = Emulator::new();
+    emulator.load_program(&[0xFF, 0xFF]).unwrap();
+    let mut breakpoints = Breakpoints::new();
+    let err = run_frame(&mut emulator, &KEYPAD, 10, &mut breakpoints).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ParseInvalidInstruction(0xFFFF));
+}
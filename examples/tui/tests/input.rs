@@ -0,0 +1,49 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
+use tui::input::{key_index, Keypad};
+
+fn event(c: char, kind: KeyEventKind) -> KeyEvent {
+    KeyEvent {
+        code: KeyCode::Char(c),
+        modifiers: KeyModifiers::NONE,
+        kind,
+        state: KeyEventState::NONE,
+    }
+}
+
+#[test]
+fn maps_the_keyboard_to_the_keypad_layout() {
+    let layout = ["1234", "qwer", "asdf", "zxcv"];
+    let keys: Vec<Option<usize>> =
+        layout.iter().flat_map(|row| row.chars()).map(|c| key_index(KeyCode::Char(c))).collect();
+    let expected = [0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF];
+    assert_eq!(keys, expected.map(Some));
+    // caps lock or shift on
+    assert_eq!(key_index(KeyCode::Char('W')), Some(0x5));
+    assert_eq!(key_index(KeyCode::Char('5')), None);
+    assert_eq!(key_index(KeyCode::Enter), None);
+}
+
+#[test]
+fn holds_keys_for_terminals_without_release_events() {
+    let now = Instant::now();
+    let mut keypad = Keypad::new(false);
+    assert!(keypad.handle(&event('w', KeyEventKind::Press), now));
+    assert!(!keypad.handle(&event('p', KeyEventKind::Press), now));
+    keypad.release_expired(now + Duration::from_millis(100));
+    assert_eq!(keypad.state()[0x5], 1);
+    keypad.release_expired(now + Duration::from_millis(150));
+    assert_eq!(keypad.state()[0x5], 0);
+}
+
+#[test]
+fn releases_keys_on_release_events() {
+    let now = Instant::now();
+    let mut keypad = Keypad::new(true);
+    keypad.handle(&event('x', KeyEventKind::Press), now);
+    keypad.release_expired(now + Duration::from_secs(1));
+    assert_eq!(keypad.state()[0x0], 1);
+    keypad.handle(&event('x', KeyEventKind::Release), now);
+    assert_eq!(keypad.state()[0x0], 0);
+}
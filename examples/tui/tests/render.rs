@@ -0,0 +1,41 @@
+use chipinho::constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, VRAM_SIZE};
+use tui::render::{screen_to_lines, Mode};
+
+// 1 0 1
+// 1 1 0
+// 0 1 0
+const VRAM: [u8; 9] = [1, 0, 1, 1, 1, 0, 0, 1, 0];
+
+#[test]
+fn draws_two_rows_per_half_block() {
+    assert_eq!(screen_to_lines(&VRAM, 3, 3, Mode::HalfBlock), ["█▄▀", " ▀ "]);
+}
+
+#[test]
+fn draws_2x4_pixels_per_braille_character() {
+    // dots 1, 2, 5 and 6, then dot 1 alone
+    assert_eq!(screen_to_lines(&VRAM, 3, 3, Mode::Braille), ["\u{2833}\u{2801}"]);
+    assert_eq!(screen_to_lines(&[1; 8], 2, 4, Mode::Braille), ["\u{28FF}"]);
+    assert_eq!(screen_to_lines(&[0; 8], 2, 4, Mode::Braille), ["\u{2800}"]);
+}
+
+#[test]
+fn fits_the_whole_screen() {
+    let mut vram = [0; VRAM_SIZE];
+    vram[VRAM_SIZE - 1] = 1;
+    let lines = screen_to_lines(&vram, DISPLAY_WIDTH as usize, DISPLAY_HEIGHT as usize, Mode::HalfBlock);
+    assert_eq!(lines.len(), 16);
+    assert!(lines.iter().all(|line| line.chars().count() == 64));
+    assert!(lines[15].ends_with('▄'));
+
+    let lines = screen_to_lines(&vram, DISPLAY_WIDTH as usize, DISPLAY_HEIGHT as usize, Mode::Braille);
+    assert_eq!(lines.len(), 8);
+    assert!(lines.iter().all(|line| line.chars().count() == 32));
+    // dot 8, bottom right
+    assert!(lines[7].ends_with('\u{2880}'));
+}
+
+#[test]
+fn leaves_missing_pixels_off() {
+    assert_eq!(screen_to_lines(&[1, 1], 2, 2, Mode::HalfBlock), ["▀▀"]);
+}
@@ -0,0 +1,48 @@
+use chipinho::constants::{BEEPER_FREQUENCY, BEEPER_VOLUME};
+use wasm_bindgen::JsValue;
+use web_sys::{AudioContext, GainNode, OscillatorNode, OscillatorType};
+
+/// A square wave that is always playing, muted unless the sound timer is running.
+///
+/// Browsers keep an `AudioContext` suspended until the page gets a click or a key press,
+/// so call `resume` from those.
+pub struct WebBeeper {
+    context: AudioContext,
+    gain: GainNode,
+    _oscillator: OscillatorNode,
+    on: bool,
+}
+
+impl WebBeeper {
+    pub fn new() -> Result<Self, JsValue> {
+        let context = AudioContext::new()?;
+        let oscillator = context.create_oscillator()?;
+        oscillator.set_type(OscillatorType::Square);
+        oscillator.frequency().set_value(BEEPER_FREQUENCY as f32);
+        let gain = context.create_gain()?;
+        gain.gain().set_value(0.0);
+        oscillator.connect_with_audio_node(&gain)?;
+        gain.connect_with_audio_node(&context.destination())?;
+        oscillator.start()?;
+        Ok(WebBeeper {
+            context,
+            gain,
+            _oscillator: oscillator,
+            on: false,
+        })
+    }
+
+    pub fn resume(&self) {
+        // fails only when the context is closed, which this never does
+        let _ = self.context.resume();
+    }
+
+    pub fn set(&mut self, on: bool) {
+        if on == self.on {
+            return;
+        }
+        self.on = on;
+        let volume = if on { BEEPER_VOLUME as f32 / i16::MAX as f32 } else { 0.0 };
+        let _ = self.gain.gain().set_value_at_time(volume, self.context.current_time());
+    }
+}
@@ -0,0 +1,106 @@
+use chipinho::constants::NUM_KEYS;
+
+// same layout as the other examples:
+// 1 2 3 4      1 2 3 C
+// q w e r  ->  4 5 6 D
+// a s d f      7 8 9 E
+// z x c v      A 0 B F
+pub fn key_index(key: &str) -> Option<usize> {
+    let mut chars = key.chars();
+    let (Some(key), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    match key.to_ascii_lowercase() {
+        '1' => Some(0x1),
+        '2' => Some(0x2),
+        '3' => Some(0x3),
+        '4' => Some(0xC),
+        'q' => Some(0x4),
+        'w' => Some(0x5),
+        'e' => Some(0x6),
+        'r' => Some(0xD),
+        'a' => Some(0x7),
+        's' => Some(0x8),
+        'd' => Some(0x9),
+        'f' => Some(0xE),
+        'z' => Some(0xA),
+        'x' => Some(0x0),
+        'c' => Some(0xB),
+        'v' => Some(0xF),
+        _ => None,
+    }
+}
+
+/// The on-screen keypad, row by row, laid out like the COSMAC VIP one.
+pub const TOUCH_LAYOUT: [usize; NUM_KEYS] = [
+    0x1, 0x2, 0x3, 0xC,
+    0x4, 0x5, 0x6, 0xD,
+    0x7, 0x8, 0x9, 0xE,
+    0xA, 0x0, 0xB, 0xF,
+];
+
+/// Keys held on the keyboard or on the on-screen keypad, a key is pressed while anything holds it.
+#[derive(Default)]
+pub struct Keypad {
+    keyboard: [bool; NUM_KEYS],
+    // the key each finger (or mouse) on the on-screen keypad holds, by pointer id
+    pointers: Vec<(i32, usize)>,
+    keys: [u8; NUM_KEYS],
+}
+
+impl Keypad {
+    pub fn new() -> Self {
+        Keypad::default()
+    }
+
+    /// Returns whether `key` (a `KeyboardEvent.key`) is part of the keypad.
+    pub fn key_down(&mut self, key: &str) -> bool {
+        self.set_keyboard(key, true)
+    }
+
+    pub fn key_up(&mut self, key: &str) -> bool {
+        self.set_keyboard(key, false)
+    }
+
+    pub fn pointer_down(&mut self, pointer: i32, key: usize) {
+        if key >= NUM_KEYS {
+            return;
+        }
+        self.pointers.retain(|(id, _)| *id != pointer);
+        self.pointers.push((pointer, key));
+        self.update();
+    }
+
+    /// The pointer was lifted or cancelled, pointers that held nothing are ignored.
+    pub fn pointer_up(&mut self, pointer: i32) {
+        self.pointers.retain(|(id, _)| *id != pointer);
+        self.update();
+    }
+
+    /// Let go of everything, for when the page loses focus and release events would go elsewhere.
+    pub fn release_all(&mut self) {
+        self.keyboard = [false; NUM_KEYS];
+        self.pointers.clear();
+        self.update();
+    }
+
+    pub fn state(&self) -> &[u8] {
+        &self.keys
+    }
+
+    fn set_keyboard(&mut self, key: &str, pressed: bool) -> bool {
+        let Some(index) = key_index(key) else {
+            return false;
+        };
+        self.keyboard[index] = pressed;
+        self.update();
+        true
+    }
+
+    fn update(&mut self) {
+        for (index, key) in self.keys.iter_mut().enumerate() {
+            let touched = self.pointers.iter().any(|(_, held)| *held == index);
+            *key = (self.keyboard[index] || touched) as u8;
+        }
+    }
+}
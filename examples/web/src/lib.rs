@@ -0,0 +1,244 @@
+//! Browser frontend, build it with `make build-web` and open `examples/web/index.html` through
+//! `make serve-web`. The page only needs the elements with the ids below, all the logic is here.
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::Uint8Array;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::convert::FromWasmAbi;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{
+    CanvasRenderingContext2d, Document, Event, HtmlCanvasElement, HtmlElement, HtmlInputElement, ImageData,
+    KeyboardEvent, PointerEvent, Window,
+};
+
+use chipinho::constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+pub mod audio;
+pub mod input;
+pub mod session;
+
+use crate::audio::WebBeeper;
+use crate::input::TOUCH_LAYOUT;
+use crate::session::Session;
+
+const CANVAS_ID: &str = "canvas";
+const FILE_INPUT_ID: &str = "file-handler";
+const RUN_BUTTON_ID: &str = "btn-run";
+const PAUSE_BUTTON_ID: &str = "btn-pause";
+const RESET_BUTTON_ID: &str = "btn-reset";
+// an empty element that gets the on-screen keypad buttons
+const KEYPAD_ID: &str = "keypad";
+
+type Action = fn(&mut App);
+type FrameCallback = Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>>;
+
+struct App {
+    session: Session,
+    context: CanvasRenderingContext2d,
+    // made on the first click or key press, browsers refuse to play audio before one
+    beeper: Option<WebBeeper>,
+}
+
+impl App {
+    fn animation_frame(&mut self, timestamp: f64) {
+        match self.session.animation_frame(timestamp) {
+            Ok(0) => {}
+            Ok(_) => self.draw(),
+            Err(err) => {
+                web_sys::console::error_1(&format!("chipinho: {}", err).into());
+                self.draw();
+            }
+        }
+        let beeping = self.session.should_beep();
+        if let Some(beeper) = &mut self.beeper {
+            beeper.set(beeping);
+        }
+    }
+
+    fn draw(&self) {
+        let rgba = Clamped(self.session.framebuffer.get_rgba());
+        let image = ImageData::new_with_u8_clamped_array_and_sh(rgba, DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32);
+        if let Ok(image) = image {
+            let _ = self.context.put_image_data(&image, 0.0, 0.0);
+        }
+    }
+
+    fn user_gesture(&mut self) {
+        if self.beeper.is_none() {
+            self.beeper = WebBeeper::new().ok();
+        }
+        if let Some(beeper) = &self.beeper {
+            beeper.resume();
+        }
+    }
+}
+
+/// Hook the page up, run by wasm-bindgen when the module loads. Without a page (like under
+/// `wasm-pack test --node`) there is nothing to do.
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let Some(window) = web_sys::window() else {
+        return Ok(());
+    };
+    let document = window.document().ok_or("no document")?;
+    let canvas: HtmlCanvasElement = element(&document, CANVAS_ID)?;
+    canvas.set_width(DISPLAY_WIDTH as u32);
+    canvas.set_height(DISPLAY_HEIGHT as u32);
+    let context = canvas
+        .get_context("2d")?
+        .ok_or("no 2d context")?
+        .dyn_into::<CanvasRenderingContext2d>()?;
+
+    let app = Rc::new(RefCell::new(App {
+        session: Session::new(),
+        context,
+        beeper: None,
+    }));
+    app.borrow().draw();
+    listen_to_keys(&window, &document, &app)?;
+    listen_to_keypad(&document, &app)?;
+    listen_to_buttons(&document, &app)?;
+    listen_to_file_input(&document, &app)?;
+    run_animation_frames(&window, &app)
+}
+
+fn element<T: JsCast>(document: &Document, id: &str) -> Result<T, JsValue> {
+    document
+        .get_element_by_id(id)
+        .ok_or_else(|| JsValue::from_str(&format!("no #{} element", id)))?
+        .dyn_into::<T>()
+        .map_err(|_| JsValue::from_str(&format!("#{} is the wrong kind of element", id)))
+}
+
+// the listeners live as long as the page, so the closures are leaked on purpose
+fn listen<E: FromWasmAbi + 'static>(
+    target: &web_sys::EventTarget,
+    event: &str,
+    handler: impl FnMut(E) + 'static,
+) -> Result<(), JsValue> {
+    let closure = Closure::<dyn FnMut(E)>::new(handler);
+    target.add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())?;
+    closure.forget();
+    Ok(())
+}
+
+fn listen_to_keys(window: &Window, document: &Document, app: &Rc<RefCell<App>>) -> Result<(), JsValue> {
+    let down = app.clone();
+    listen(document, "keydown", move |event: KeyboardEvent| {
+        let mut app = down.borrow_mut();
+        app.user_gesture();
+        if app.session.keypad.key_down(&event.key()) {
+            event.prevent_default();
+        }
+    })?;
+    let up = app.clone();
+    listen(document, "keyup", move |event: KeyboardEvent| {
+        if up.borrow_mut().session.keypad.key_up(&event.key()) {
+            event.prevent_default();
+        }
+    })?;
+    // release events go to whatever has the focus now
+    let blur = app.clone();
+    listen(window, "blur", move |_: Event| blur.borrow_mut().session.keypad.release_all())
+}
+
+fn listen_to_keypad(document: &Document, app: &Rc<RefCell<App>>) -> Result<(), JsValue> {
+    let keypad: HtmlElement = element(document, KEYPAD_ID)?;
+    for key in TOUCH_LAYOUT {
+        let button: HtmlElement = document.create_element("button")?.dyn_into()?;
+        button.set_text_content(Some(&format!("{:X}", key)));
+        button.set_attribute("data-key", &key.to_string())?;
+        keypad.append_child(&button)?;
+    }
+
+    let down = app.clone();
+    listen(&keypad, "pointerdown", move |event: PointerEvent| {
+        let Some(button) = event.target().and_then(|target| target.dyn_into::<HtmlElement>().ok()) else {
+            return;
+        };
+        let Some(key) = button.get_attribute("data-key").and_then(|key| key.parse().ok()) else {
+            return;
+        };
+        event.prevent_default();
+        // keep getting this pointer's events when the finger slides off the button
+        let _ = button.set_pointer_capture(event.pointer_id());
+        let mut app = down.borrow_mut();
+        app.user_gesture();
+        app.session.keypad.pointer_down(event.pointer_id(), key);
+    })?;
+    for released in ["pointerup", "pointercancel"] {
+        let up = app.clone();
+        listen(&keypad, released, move |event: PointerEvent| {
+            up.borrow_mut().session.keypad.pointer_up(event.pointer_id());
+        })?;
+    }
+    Ok(())
+}
+
+fn listen_to_buttons(document: &Document, app: &Rc<RefCell<App>>) -> Result<(), JsValue> {
+    let actions: [(&str, Action); 3] = [
+        (RUN_BUTTON_ID, |app| app.session.play()),
+        (PAUSE_BUTTON_ID, |app| app.session.pause()),
+        (RESET_BUTTON_ID, |app| {
+            app.session.reset();
+            app.draw();
+        }),
+    ];
+    for (id, action) in actions {
+        let button: HtmlElement = element(document, id)?;
+        let app = app.clone();
+        listen(&button, "click", move |_: Event| {
+            let mut app = app.borrow_mut();
+            app.user_gesture();
+            action(&mut app);
+        })?;
+    }
+    Ok(())
+}
+
+fn listen_to_file_input(document: &Document, app: &Rc<RefCell<App>>) -> Result<(), JsValue> {
+    let input: HtmlInputElement = element(document, FILE_INPUT_ID)?;
+    let app = app.clone();
+    let target = input.clone();
+    listen(&input, "change", move |_: Event| {
+        let Some(file) = target.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        let app = app.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let rom = match wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await {
+                Ok(buffer) => Uint8Array::new(&buffer).to_vec(),
+                Err(err) => {
+                    web_sys::console::error_2(&"chipinho: could not read the file".into(), &err);
+                    return;
+                }
+            };
+            let mut app = app.borrow_mut();
+            match app.session.load(&rom) {
+                Ok(()) => {
+                    app.session.play();
+                    app.draw();
+                }
+                Err(err) => web_sys::console::error_1(&format!("chipinho: {}", err).into()),
+            }
+        });
+    })
+}
+
+fn run_animation_frames(window: &Window, app: &Rc<RefCell<App>>) -> Result<(), JsValue> {
+    // the callback requests itself for the next frame, so it keeps a handle to itself
+    let callback: FrameCallback = Rc::new(RefCell::new(None));
+    let next = callback.clone();
+    let app = app.clone();
+    let window_handle = window.clone();
+    *callback.borrow_mut() = Some(Closure::new(move |timestamp: f64| {
+        app.borrow_mut().animation_frame(timestamp);
+        if let Some(next) = next.borrow().as_ref() {
+            let _ = window_handle.request_animation_frame(next.as_ref().unchecked_ref());
+        }
+    }));
+    let first = callback.borrow();
+    window.request_animation_frame(first.as_ref().ok_or("no callback")?.as_ref().unchecked_ref())?;
+    Ok(())
+}
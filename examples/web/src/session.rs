@@ -0,0 +1,101 @@
+use chipinho::emulator::Emulator;
+use chipinho::error::Error;
+use chipinho::framebuffer::Framebuffer;
+use chipinho::scheduler::{ManualClock, Scheduler};
+
+use crate::input::Keypad;
+
+/// Everything but the browser: the emulator with its program, input, timing and pixels.
+pub struct Session {
+    pub emulator: Emulator,
+    pub keypad: Keypad,
+    pub framebuffer: Framebuffer,
+    // set to the `requestAnimationFrame` timestamps, 120Hz screens run a frame every other callback
+    pub scheduler: Scheduler<ManualClock>,
+    program: Option<Vec<u8>>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        let mut scheduler = Scheduler::new(ManualClock::new());
+        scheduler.pause();
+        Session {
+            emulator: Emulator::new(),
+            keypad: Keypad::new(),
+            // black on white, like the page
+            framebuffer: Framebuffer::new(0x000000FF, 0xFFFFFFFF),
+            scheduler,
+            program: None,
+        }
+    }
+
+    /// Start over with `rom`, paused. On error the previous program stays loaded.
+    pub fn load(&mut self, rom: &[u8]) -> Result<(), Error> {
+        let mut emulator = Emulator::new();
+        emulator.load_program(rom)?;
+        self.emulator = emulator;
+        self.program = Some(rom.to_vec());
+        self.pause();
+        self.framebuffer.update_from(&self.emulator);
+        Ok(())
+    }
+
+    /// Start the loaded program over, paused.
+    pub fn reset(&mut self) {
+        self.emulator = Emulator::new();
+        if let Some(program) = &self.program {
+            // it loaded once already, so it fits
+            let _ = self.emulator.load_program(program);
+        }
+        self.pause();
+        self.framebuffer.update_from(&self.emulator);
+    }
+
+    /// Run the program, if there is one.
+    pub fn play(&mut self) {
+        if self.program.is_some() {
+            self.scheduler.resume();
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.scheduler.pause();
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.scheduler.is_paused()
+    }
+
+    pub fn has_program(&self) -> bool {
+        self.program.is_some()
+    }
+
+    /// Run the frames due at `timestamp` (from `requestAnimationFrame`, in milliseconds) and paint them,
+    /// returning how many ran. A fault pauses the program.
+    pub fn animation_frame(&mut self, timestamp: f64) -> Result<u32, Error> {
+        self.scheduler.clock.set((timestamp * 1000.0) as u64);
+        match self.scheduler.update(&mut self.emulator, self.keypad.state()) {
+            Ok(frames) => {
+                if frames.should_draw() {
+                    self.framebuffer.update_from(&self.emulator);
+                }
+                Ok(frames.ran)
+            }
+            Err(err) => {
+                self.pause();
+                self.framebuffer.update_from(&self.emulator);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn should_beep(&self) -> bool {
+        self.is_running() && self.emulator.should_beep()
+    }
+}
@@ -0,0 +1,114 @@
+//! Runs natively with `cargo test -p chipinho-web`, and in wasm under Node with
+//! `wasm-pack test --node examples/web` (`make test-web`).
+use chipinho::constants::NUM_KEYS;
+use chipinho::testing::programs::BEEPER_PROGRAM;
+use chipinho_web::input::{key_index, Keypad, TOUCH_LAYOUT};
+use chipinho_web::session::Session;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const FRAME: f64 = 1000.0 / 60.0;
+
+#[wasm_bindgen_test(unsupported = test)]
+fn maps_the_keyboard_like_the_other_frontends() {
+    assert_eq!(key_index("1"), Some(0x1));
+    assert_eq!(key_index("W"), Some(0x5));
+    assert_eq!(key_index("v"), Some(0xF));
+    assert_eq!(key_index("Enter"), None);
+    assert_eq!(key_index("g"), None);
+
+    let mut layout = TOUCH_LAYOUT;
+    layout.sort();
+    assert_eq!(layout, core::array::from_fn::<usize, NUM_KEYS, _>(|key| key));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn holds_keys_from_keyboard_and_fingers() {
+    let mut keypad = Keypad::new();
+    assert!(keypad.key_down("w"));
+    assert!(!keypad.key_down("Shift"));
+    keypad.pointer_down(1, 0x5);
+    keypad.pointer_down(2, 0xF);
+    assert_eq!(keypad.state()[0x5], 1);
+    assert_eq!(keypad.state()[0xF], 1);
+
+    // still held by the finger
+    keypad.key_up("w");
+    assert_eq!(keypad.state()[0x5], 1);
+    keypad.pointer_up(1);
+    assert_eq!(keypad.state()[0x5], 0);
+    // a finger sliding to another key moves with it
+    keypad.pointer_down(2, 0xE);
+    assert_eq!(keypad.state()[0xF], 0);
+    assert_eq!(keypad.state()[0xE], 1);
+
+    keypad.key_down("1");
+    keypad.release_all();
+    assert!(keypad.state().iter().all(|key| *key == 0));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn paces_frames_at_60hz_whatever_the_display() {
+    let mut session = Session::new();
+    session.load(&BEEPER_PROGRAM).unwrap();
+    session.play();
+    assert_eq!(session.animation_frame(1000.0), Ok(0));
+    // 60Hz, a little early and a little late
+    assert_eq!(session.animation_frame(1000.0 + FRAME - 0.3), Ok(1));
+    assert_eq!(session.animation_frame(1000.0 + 2.0 * FRAME + 0.3), Ok(1));
+    // 120Hz: one frame every other callback
+    let frames: u32 = (5..=8).map(|n| session.animation_frame(1000.0 + n as f64 * FRAME / 2.0).unwrap()).sum();
+    assert_eq!(frames, 2);
+    // 30Hz: two frames per callback
+    assert_eq!(session.animation_frame(1000.0 + 6.0 * FRAME), Ok(2));
+    // back from a hidden tab, without fast forwarding through it
+    assert_eq!(session.animation_frame(60_000.0), Ok(4));
+    assert_eq!(session.animation_frame(60_000.0 + FRAME), Ok(1));
+
+    // no catching up on the time spent paused either
+    session.pause();
+    session.play();
+    assert_eq!(session.animation_frame(90_000.0), Ok(0));
+    assert_eq!(session.animation_frame(90_000.0 + FRAME), Ok(1));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn runs_a_program_on_animation_frames() {
+    let mut session = Session::new();
+    // nothing to run yet
+    session.play();
+    assert!(!session.is_running());
+
+    session.load(&BEEPER_PROGRAM).unwrap();
+    assert!(!session.is_running());
+    session.play();
+    assert_eq!(session.animation_frame(0.0), Ok(0));
+    assert_eq!(session.animation_frame(FRAME), Ok(1));
+    let lit = |session: &Session| session.framebuffer.get_rgba().chunks(4).filter(|color| color[0] == 0).count();
+    assert_eq!(lit(&session), 4);
+    assert!(!session.should_beep());
+
+    session.keypad.pointer_down(7, 0x5);
+    assert_eq!(session.animation_frame(2.0 * FRAME), Ok(1));
+    assert!(session.should_beep());
+    session.pause();
+    assert!(!session.should_beep());
+    assert_eq!(session.animation_frame(3.0 * FRAME), Ok(0));
+
+    session.reset();
+    assert!(!session.is_running());
+    assert_eq!(session.emulator.sound_timer, 0);
+    assert_eq!(lit(&session), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn pauses_on_faults_and_keeps_good_programs() {
+    let mut session = Session::new();
+    assert!(session.load(&[0; 4000]).is_err());
+    assert!(!session.has_program());
+
+    session.load(&[0xFF, 0xFF]).unwrap();
+    session.play();
+    session.animation_frame(0.0).unwrap();
+    assert!(session.animation_frame(FRAME).is_err());
+    assert!(!session.is_running());
+}
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// long enough for programs to loop, short enough to keep the executions per second up
+const TICKS: usize = 10_000;
+
+fuzz_target!(|data: &[u8]| {
+    // errors are expected, panics are the bugs
+    let _ = chipinho::testing::fuzz::exercise(data, TICKS);
+});